@@ -0,0 +1,133 @@
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::agent_config::AgentConfig;
+use crate::packet::{Packet, CLIENT_ID};
+
+/// Represents the set of participants an agent is willing to answer `MsgQueryValue` requests from.
+///
+/// Under every policy other than `Open`, the signature of a query must be valid for the public key
+/// of its sender, i.e, the game's client or one of the agent's known peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum AccessPolicy {
+    /// Only queries signed by the game's client are answered. Agents relaying values in expert
+    /// mode are refused, so every agent appears unreachable to its peers.
+    ClientOnly,
+    /// Queries signed by the game's client or by any agent in the game are answered.
+    #[default]
+    KnownPeers,
+    /// Every query is answered, whether it is signed or not.
+    Open,
+}
+
+impl fmt::Display for AccessPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessPolicy::ClientOnly => write!(f, "client-only"),
+            AccessPolicy::KnownPeers => write!(f, "known-peers"),
+            AccessPolicy::Open => write!(f, "open"),
+        }
+    }
+}
+
+impl AccessPolicy {
+    /// Checks whether the query in `packet` should be answered under this policy. Signatures are
+    /// verified against `client_pubkey` for the game's client and against the public keys of
+    /// `known_peers`, indexed by agent ID, for agents. Returns Ok(()) if the query is authorized,
+    /// and the reason for refusing it otherwise.
+    pub fn authorize(
+        &self,
+        packet: &Packet,
+        client_pubkey: &str,
+        known_peers: &HashMap<usize, AgentConfig>,
+    ) -> Result<(), String> {
+        if *self == AccessPolicy::Open {
+            return Ok(());
+        }
+
+        let public_key = match packet.sender {
+            CLIENT_ID => client_pubkey,
+            sender if *self == AccessPolicy::ClientOnly => {
+                return Err(format!(
+                    "only the game's client may query this agent, but the query was sent by Agent {}",
+                    sender
+                ));
+            }
+            sender => match known_peers.get(&sender) {
+                Some(peer) => peer.get_public_key(),
+                None => return Err(format!("Agent {} is not a known peer", sender)),
+            },
+        };
+
+        if packet.msg_sig.is_none() {
+            return Err("the query is not signed".to_owned());
+        }
+
+        packet
+            .verify(public_key)
+            .map_err(|_| "the signature of the query does not match its sender".to_owned())
+    }
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::Keys;
+    use crate::message::Message;
+
+    #[test]
+    fn test_authorize_queries() {
+        let client_keys = Keys::new_key_pair();
+        let peer_keys = Keys::new_key_pair();
+        let stranger_keys = Keys::new_key_pair();
+        let known_peers = HashMap::from([(
+            2,
+            AgentConfig::new(2, "127.0.0.1", 5001, peer_keys.get_public_key()),
+        )]);
+        let client_pubkey = client_keys.get_public_key();
+
+        let query = |sender: usize, keys: Option<&Keys>| {
+            let packet = Packet::new(sender, 1, 7, Message::build_msg_query_value(7).unwrap());
+            match keys {
+                Some(keys) => packet.sign(keys).unwrap(),
+                None => packet,
+            }
+        };
+
+        let from_client = query(CLIENT_ID, Some(&client_keys));
+        let from_peer = query(2, Some(&peer_keys));
+        let from_stranger = query(3, Some(&stranger_keys));
+        let impersonating_client = query(CLIENT_ID, Some(&stranger_keys));
+        let unsigned = query(2, None);
+
+        let authorize = |policy: AccessPolicy, packet: &Packet| {
+            policy
+                .authorize(packet, client_pubkey, &known_peers)
+                .is_ok()
+        };
+
+        assert!(authorize(AccessPolicy::ClientOnly, &from_client));
+        assert!(!authorize(AccessPolicy::ClientOnly, &from_peer));
+        assert!(!authorize(AccessPolicy::ClientOnly, &impersonating_client));
+
+        assert!(authorize(AccessPolicy::KnownPeers, &from_client));
+        assert!(authorize(AccessPolicy::KnownPeers, &from_peer));
+        assert!(!authorize(AccessPolicy::KnownPeers, &from_stranger));
+        assert!(!authorize(AccessPolicy::KnownPeers, &impersonating_client));
+        assert!(!authorize(AccessPolicy::KnownPeers, &unsigned));
+
+        for packet in [
+            &from_client,
+            &from_stranger,
+            &impersonating_client,
+            &unsigned,
+        ] {
+            assert!(authorize(AccessPolicy::Open, packet));
+        }
+    }
+}
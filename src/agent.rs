@@ -1,34 +1,44 @@
 use anyhow::{bail, Context};
-use rand::Rng;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use rand::seq::SliceRandom;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 use text_colorizer::Colorize;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::spawn;
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, Mutex as AsyncMutex};
+use tokio::time::{sleep, timeout};
 use tokio_util::sync::CancellationToken;
 
 use crate::agent_config::AgentConfig;
+use crate::behavior::{AgentBehavior, Honest, RandomLiar, RelayStrategy, TamperingRelay};
+use crate::codec::Codec;
+use crate::connection::{split_frames, Connection, ConnectionPool, NetworkOptions};
+use crate::gossip::{GossipOptions, GossipStore};
+use crate::handshake::{accept_handshake, Capabilities};
 use crate::keys::Keys;
 use crate::message::Message;
-use crate::network_utils::*;
-use crate::packet::Packet;
+use crate::network_utils::FrameError;
+use crate::packet::{Packet, CLIENT_ID, FRESHNESS_WINDOW};
+use crate::secure_channel::accept_secure_channel;
 
 static AGENT_ID_COUNTER: AtomicUsize = AtomicUsize::new(1);
 static BASE_PORT: AtomicUsize = AtomicUsize::new(5_000);
 const AGENT_ADDR: &str = "127.0.0.1";
+/// How often an agent checks whether gossip has been enabled while it is disabled.
+const GOSSIP_IDLE_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Represents an agent in the Liars Lie game.
 ///
-/// Each `Agent` has an unique identifier `agent_id`, a value `value` to report when
-/// queried, and a network `address` and `port` used for communication with clients and
-/// other Agents. Agents can be instantiated as either honest or liars.
-#[derive(Debug, Clone, PartialEq)]
+/// Each `Agent` has an unique identifier `agent_id`, a `behavior` that decides the value it
+/// reports when queried, and a network `address` and `port` used for communication with clients
+/// and other Agents. Agents can be instantiated as either honest or liars, or with any other
+/// implementation of `AgentBehavior`.
+#[derive(Debug, Clone)]
 pub struct Agent {
     /// An identifier for each instance of Agent.
     agent_id: usize,
-    /// A value to be reported by the agent when queried.
-    value: u64,
     /// The network address in which the agent listens when deployed.
     address: String,
     /// The network port in which the agent listens when deployed.
@@ -39,10 +49,77 @@ pub struct Agent {
     game_client_pubkey: String,
     /// A flag to indicate whether this agent has been deployed or not.
     status: AgentStatus,
-    /// A flag to indicate whether this agent is a liar or not.
-    is_liar: bool,
-    /// The probability that the agent will tamper with messages when forwarding them
-    tamper_chance: f32,
+    /// How the agent answers queries, relays values and handles control messages. Shared by every
+    /// clone of the agent.
+    behavior: Arc<dyn AgentBehavior>,
+    /// The limits, transport and codec of the agent's connections, and its access policy.
+    network: NetworkOptions,
+    /// The other agents in the game, indexed by agent ID. Used to authenticate queries from peers
+    /// and to reach peers whose values are requested with `MsgQueryValues`. Shared by every clone
+    /// of the agent.
+    known_peers: Arc<RwLock<HashMap<usize, AgentConfig>>>,
+    /// The latest signed value received from each peer, indexed by agent ID. Used to answer
+    /// `MsgQueryValues` without querying peers again within the same round.
+    relay_cache: Arc<Mutex<HashMap<usize, Packet>>>,
+    /// The agents this agent may connect to according to the network topology, or None if it may
+    /// connect to every agent. Shared between clones, so that the topology can change at runtime.
+    neighbours: Arc<RwLock<Option<BTreeSet<usize>>>>,
+    /// Whether the game's client is barred from contacting the agent directly, which is the case
+    /// while the agent is left out of the subset of an expert round.
+    client_lockout: Arc<AtomicBool>,
+    /// The number of requests signed by the game's client that were refused under `client_lockout`.
+    lockout_violations: Arc<AtomicUsize>,
+    /// How the agent gossips its collection of values, or None if gossip is disabled.
+    gossip: Arc<RwLock<Option<GossipOptions>>>,
+    /// The signed values the agent has gathered through gossip, including its own.
+    gossip_store: GossipStore,
+    /// The number of requests and replies the agent has sent since it was created.
+    messages_sent: Arc<AtomicUsize>,
+    /// Persistent connections to peers, reused when relaying values for `MsgFetchValues`.
+    peer_connections: ConnectionPool,
+}
+
+/// Represents the options, chosen when a game is started, that control how liars misbehave beyond
+/// reporting a false value.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LiarOptions {
+    /// The probability (0.0 to 1.0) that a liar will tamper with a message when forwarding it.
+    pub tamper_chance: f32,
+    /// Whether liars sign a different false value for every query they answer.
+    pub equivocate: bool,
+    /// The relay strategies assigned to liars, in turn, as they are spawned.
+    pub strategies: Vec<RelayStrategy>,
+    /// The number of liars, in spawn order, that form each coalition sharing a false value. Liars
+    /// report independent values if it is 1.
+    pub coalition_size: u16,
+}
+
+impl LiarOptions {
+    /// Returns a new instance of `LiarOptions` in which liars tamper with forwarded messages with
+    /// probability `tamper_chance`, following `strategies`, equivocate if `equivocate` is `true`
+    /// and collude in coalitions of `coalition_size` members.
+    pub fn new(
+        tamper_chance: f32,
+        equivocate: bool,
+        strategies: &[RelayStrategy],
+        coalition_size: u16,
+    ) -> Self {
+        LiarOptions {
+            tamper_chance,
+            equivocate,
+            strategies: strategies.to_vec(),
+            coalition_size,
+        }
+    }
+
+    /// Returns the relay strategy of the `index`-th liar spawned with these options. Strategies
+    /// are assigned in turn, and liars forge values if no strategy was given.
+    pub fn get_strategy(&self, index: usize) -> RelayStrategy {
+        match self.strategies.len() {
+            0 => RelayStrategy::default(),
+            len => self.strategies[index % len],
+        }
+    }
 }
 
 #[derive(PartialEq, Clone, Debug, Copy)]
@@ -53,56 +130,67 @@ pub enum AgentStatus {
 }
 
 impl Agent {
-    /// Returns a new honest instance of `Agent` with the `value` field set to the value
-    /// received as argument. Each new instance is assigned an unique `agent_id`
-    /// and `port`.
-    pub fn new_honest(value: u64, game_client_pubkey: String) -> Self {
-        let agent_id = Self::get_new_id();
-        let address = AGENT_ADDR.to_owned();
-        let port = Self::get_new_port();
-        let keys = Keys::new_key_pair();
-        let status = AgentStatus::Uninitialized;
-        let is_liar = false;
-        let tamper_chance = 0.0;
-        Agent {
-            agent_id,
-            value,
-            address,
-            port,
-            keys,
-            game_client_pubkey,
-            status,
-            is_liar,
-            tamper_chance,
-        }
+    /// Returns a new honest instance of `Agent` that reports the value received as argument.
+    /// Each new instance is assigned an unique `agent_id` and `port`. The agent's connections
+    /// are configured according to `network`.
+    pub fn new_honest(value: u64, game_client_pubkey: String, network: NetworkOptions) -> Self {
+        Self::with_behavior(Arc::new(Honest::new(value)), game_client_pubkey, network)
     }
 
-    /// Returns a new liar instance of `Agent` with the `value` field set to an arbitrary
-    /// value x, such that x != honest_value AND 1 <= x <= max_value. Each new instance
-    /// is assigned an unique `agent_id` and `port`.
+    /// Returns a new liar instance of `Agent` that reports an arbitrary value x, such that
+    /// x != honest_value AND 1 <= x <= max_value, or the `coalition_value` shared with the other
+    /// members of its coalition. Each new instance is assigned an unique `agent_id` and `port`.
+    /// The liar misbehaves according to `liar`, relaying values with `relay_strategy`, and its
+    /// connections are configured according to `network`.
     pub fn new_liar(
         honest_value: u64,
         max_value: u64,
+        coalition_value: Option<u64>,
         game_client_pubkey: String,
-        tamper_chance: f32,
+        liar: &LiarOptions,
+        relay_strategy: RelayStrategy,
+        network: NetworkOptions,
+    ) -> Self {
+        let random_liar = match coalition_value {
+            Some(value) => RandomLiar::colluding(value, honest_value, max_value, liar.equivocate),
+            None => RandomLiar::new(honest_value, max_value, liar.equivocate),
+        };
+        let behavior = TamperingRelay::new(random_liar, liar.tamper_chance, relay_strategy);
+        Self::with_behavior(Arc::new(behavior), game_client_pubkey, network)
+    }
+
+    /// Returns a new instance of `Agent` that behaves according to `behavior`. Each new instance
+    /// is assigned an unique `agent_id` and `port`. The agent's connections are configured
+    /// according to `network`.
+    pub fn with_behavior(
+        behavior: Arc<dyn AgentBehavior>,
+        game_client_pubkey: String,
+        network: NetworkOptions,
     ) -> Self {
         let agent_id = Self::get_new_id();
-        let value = Self::get_liar_value(honest_value, max_value);
         let address = AGENT_ADDR.to_owned();
         let port = Self::get_new_port();
         let keys = Keys::new_key_pair();
         let status = AgentStatus::Uninitialized;
-        let is_liar = true;
+        let peer_connections = ConnectionPool::new(network, keys.clone());
         Agent {
             agent_id,
-            value,
             address,
             port,
             keys,
             game_client_pubkey,
             status,
-            is_liar,
-            tamper_chance,
+            behavior,
+            network,
+            known_peers: Arc::new(RwLock::new(HashMap::new())),
+            relay_cache: Arc::new(Mutex::new(HashMap::new())),
+            neighbours: Arc::new(RwLock::new(None)),
+            client_lockout: Arc::new(AtomicBool::new(false)),
+            lockout_violations: Arc::new(AtomicUsize::new(0)),
+            gossip: Arc::new(RwLock::new(None)),
+            gossip_store: GossipStore::default(),
+            messages_sent: Arc::new(AtomicUsize::new(0)),
+            peer_connections,
         }
     }
 
@@ -126,6 +214,11 @@ impl Agent {
         self.port
     }
 
+    /// Returns an agent's base64-encoded public key.
+    pub fn get_public_key(&self) -> &str {
+        self.keys.get_public_key()
+    }
+
     /// Sets an agent's status ready. Used to indicate whether or not the agent has been spawned.
     pub fn set_ready(&mut self) {
         self.status = AgentStatus::Ready
@@ -138,7 +231,111 @@ impl Agent {
 
     /// Returns a bool indicating whether the agent is a liar or not.
     pub fn is_liar(&self) -> bool {
-        self.is_liar
+        self.behavior.is_liar()
+    }
+
+    /// Replaces the agent's known peers with the agents in `peers`, whose queries are answered
+    /// under the `KnownPeers` access policy. Takes effect on running clones of the agent as well.
+    pub fn set_known_peers(&self, peers: &[AgentConfig]) {
+        let mut known_peers = self.known_peers.write().unwrap();
+        *known_peers = peers
+            .iter()
+            .map(|peer| (peer.get_id(), peer.clone()))
+            .collect();
+    }
+
+    /// Replaces the agents this agent may connect to with `neighbours`, or lifts every restriction
+    /// if `neighbours` is None. Takes effect on running clones of the agent as well.
+    pub fn set_neighbours(&self, neighbours: Option<&BTreeSet<usize>>) {
+        *self.neighbours.write().unwrap() = neighbours.cloned();
+    }
+
+    /// Checks whether the network topology allows this agent and the participant `id` to connect
+    /// to each other. The game's client is not part of the topology and is always allowed.
+    fn is_neighbour(&self, id: usize) -> bool {
+        match &*self.neighbours.read().unwrap() {
+            Some(neighbours) => id == CLIENT_ID || id == self.agent_id || neighbours.contains(&id),
+            None => true,
+        }
+    }
+
+    /// Returns the public key of the participant `id`, i.e, the game's client or one of the agent's
+    /// known peers, or None if it is unknown.
+    fn get_participant_pubkey(&self, id: usize) -> Option<String> {
+        match id {
+            CLIENT_ID => Some(self.game_client_pubkey.clone()),
+            id => self
+                .known_peers
+                .read()
+                .unwrap()
+                .get(&id)
+                .map(|peer| peer.get_public_key().to_owned()),
+        }
+    }
+
+    /// Checks whether the participant holding `public_key` may connect to this agent according to
+    /// the network topology.
+    fn is_neighbour_identity(&self, public_key: &str) -> bool {
+        if self.neighbours.read().unwrap().is_none() || public_key == self.game_client_pubkey {
+            return true;
+        }
+        let id = self
+            .known_peers
+            .read()
+            .unwrap()
+            .values()
+            .find(|peer| peer.get_public_key() == public_key)
+            .map(AgentConfig::get_id);
+        id.is_some_and(|id| self.is_neighbour(id))
+    }
+
+    /// Checks whether `packet` was sent by a participant that the network topology links to this
+    /// agent. The sender is only trusted once the packet is verified against its public key, so
+    /// that no agent can pose as one of this agent's neighbours. Returns the reason for refusing
+    /// the packet otherwise.
+    fn authorize_neighbour(&self, packet: &Packet) -> Result<(), String> {
+        if self.neighbours.read().unwrap().is_none() {
+            return Ok(());
+        }
+        if !self.is_neighbour(packet.sender) {
+            return Err(format!(
+                "Agent {} is not linked to Agent {} in the network topology",
+                packet.sender, self.agent_id
+            ));
+        }
+
+        match self.get_participant_pubkey(packet.sender) {
+            Some(public_key) if packet.verify(&public_key).is_ok() => Ok(()),
+            _ => Err("the signature of the request does not match its sender".to_owned()),
+        }
+    }
+
+    /// Bars the game's client from contacting this agent directly if `lockout` is `true`, or lifts
+    /// the restriction otherwise. Takes effect on running clones of the agent as well.
+    pub fn set_client_lockout(&self, lockout: bool) {
+        self.client_lockout.store(lockout, Ordering::Relaxed);
+    }
+
+    /// Returns the number of requests from the game's client refused under the client lockout
+    /// since the last call, and resets it.
+    pub fn take_lockout_violations(&self) -> usize {
+        self.lockout_violations.swap(0, Ordering::Relaxed)
+    }
+
+    /// Enables gossip with the given `options`, or disables it if `options` is None. Takes effect
+    /// on running clones of the agent as well.
+    pub fn set_gossip(&self, options: Option<GossipOptions>) {
+        *self.gossip.write().unwrap() = options;
+    }
+
+    /// Returns the number of requests and replies the agent has sent since it was created.
+    pub fn get_messages_sent(&self) -> usize {
+        self.messages_sent.load(Ordering::Relaxed)
+    }
+
+    /// Records that the agent sent a request or a reply.
+    fn count_message(&self) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
     }
 
     /// Receives an instance of `Agent` to generate a new instance of `AgentConfig`,
@@ -149,121 +346,484 @@ impl Agent {
             self.agent_id,
             &self.address,
             self.port,
-            &self.keys.get_public_key(),
+            self.keys.get_public_key(),
         )
     }
 
-    /// Receives a Vec<Packet> containing packets to be forwarded to the game's client and tampers
-    /// with their contents with a probability equal to `Agent.tamper_chance`.
-    fn tamper_with_messages(&self, peer_values: &mut Vec<Packet>) -> Result<(), bincode::Error> {
-        // For `tamper_chance` == 0.05, the probability of tampering wih any given message is 5%.
-        let tamper_chance = (self.tamper_chance * 100.0) as i32;
+    /// Builds a `MsgSendValue` packet as a response to the `MsgQueryValue` in `request`. The
+    /// requester's `nonce` is included in the reply, which is addressed to the requester and
+    /// signed along with the requester's round.
+    fn handle_msg_query_value(&self, request: &Packet, nonce: u64) -> anyhow::Result<Packet> {
+        // Build a MsgSendValue to send as a reply to MsgQueryValue
+        let value = self.behavior.answer_query(request.sender, nonce);
+        let reply = Message::build_msg_send_value(value, self.agent_id, nonce)?;
 
-        for packet in peer_values {
-            let tamper_roll = rand::thread_rng().gen_range(0..=(100));
-            if tamper_roll <= tamper_chance {
-                packet.message =
-                    // Change the message contained within the packet to an arbitrary message.
-                    Message::build_msg_send_value(tamper_roll as u64, tamper_roll as usize)?;
-            }
+        // Build a packet containing the message and sign it along with its context
+        Packet::new(self.agent_id, request.sender, request.round, reply).sign(&self.keys)
+    }
+
+    /// Builds a `MsgQueryRefused` packet as a response to the `MsgQueryValue` in `request`, which
+    /// was not authorized by the agent's access policy for `reason`.
+    fn build_msg_query_refused_reply(
+        &self,
+        request: &Packet,
+        nonce: u64,
+        reason: &str,
+    ) -> anyhow::Result<Packet> {
+        let reply = Message::build_msg_query_refused(self.agent_id, nonce, reason)?;
+
+        Packet::new(self.agent_id, request.sender, request.round, reply).sign(&self.keys)
+    }
+
+    /// Checks whether the query in `packet` is authorized by the agent's access policy. Returns the
+    /// reason for refusing it otherwise.
+    fn authorize_query(&self, packet: &Packet) -> Result<(), String> {
+        self.network.access_policy.authorize(
+            packet,
+            &self.game_client_pubkey,
+            &self.known_peers.read().unwrap(),
+        )
+    }
+
+    /// Verifies that `packet` was sent and signed by the game's client. Returns Ok(()) if so.
+    fn verify_client_packet(&self, packet: &Packet) -> anyhow::Result<()> {
+        if packet.sender != CLIENT_ID {
+            bail!(
+                "[!] error: Agent {} expected a message from the game's client, but it was sent by Agent {}\n",
+                self.agent_id,
+                packet.sender
+            );
         }
-        Ok(())
+        packet.verify(&self.game_client_pubkey)
     }
 
-    /// Builds and sends a `MsgSendValue` packet as a response to a `MsgQueryValue` request.
-    async fn handle_msg_query_value(&self, socket: &mut TcpStream) -> anyhow::Result<()> {
-        // Build a MsgSendValue to send as a reply to MsgQueryValue
-        let reply = Message::build_msg_send_value(self.value, self.agent_id)?;
+    /// Receives a MsgKillAgent, verifies the intendend recipient against self and verifies the
+    /// packet signature. Returns Ok(()) if the agent should be killed.
+    fn handle_msg_kill_agent(&self, packet: &Packet, agent_id: usize) -> anyhow::Result<()> {
+        // If the received message is addressed to this agent, verify if the packet was signed by
+        // the game client.
+        if agent_id != self.agent_id {
+            bail!("[!] error: MsgKillAgent was intended for a different recipient\n")
+        }
+        self.verify_client_packet(packet)
+    }
+
+    /// Builds a `MsgFwdValues` containing the values fetched from other agents, to be sent back to
+    /// the game's client as a reply to `request`, and returns it in a signed packet.
+    fn build_msg_fwd_values_reply(
+        &self,
+        request: &Packet,
+        peer_values: &[Packet],
+    ) -> anyhow::Result<Packet> {
+        let message = Message::build_msg_fwd_values(self.agent_id, peer_values)?;
 
-        // Generate a signature of the message
-        let reply_sig = self.keys.sign(&reply)?;
+        Packet::new(self.agent_id, request.sender, request.round, message).sign(&self.keys)
+    }
 
-        // Build a packet containing the message and the message signature
-        let reply_packet = Packet::build_packet(reply, Some(reply_sig))?;
+    /// Builds a `MsgSendValues` containing the cached values requested by the game's client, to be
+    /// sent back as a reply to `request`, and returns it in a signed packet.
+    fn build_msg_send_values_reply(
+        &self,
+        request: &Packet,
+        values: &[Packet],
+    ) -> anyhow::Result<Packet> {
+        let message = Message::build_msg_send_values(self.agent_id, values)?;
 
-        send_packet(&reply_packet, socket).await?;
+        Packet::new(self.agent_id, request.sender, request.round, message).sign(&self.keys)
+    }
 
-        Ok(())
+    /// Hands the values in `peer_values` to the agent's behaviour before they are relayed, so that
+    /// liars may tamper with them. The original values are kept if tampering fails.
+    fn apply_relay_behavior(&self, peer_values: &mut Vec<Packet>, nonce: u64) {
+        let received_replies = peer_values.clone();
+        if self
+            .behavior
+            .relay_values(self.agent_id, peer_values, nonce)
+            .is_err()
+        {
+            // If tampering fails, revert back to the original replies
+            *peer_values = received_replies;
+        }
     }
 
-    /// Receives a MsgKillAgent, verifies the intendend recipient against self and verifies the
-    /// message signature. Returns Ok(()) if the agent should be killed.
-    fn handle_msg_kill_agent(
+    /// Processes a `MsgGossipValues` pushed by the agent `agent_id`, if the push is authorized by
+    /// the agent's access policy. Every value is verified before it is merged into the agent's
+    /// collection, so that values forged along the way are never gossiped any further.
+    fn handle_msg_gossip_values(
         &self,
-        message_bytes: &[u8],
-        signature: &Option<Vec<u8>>,
+        packet: &Packet,
         agent_id: usize,
+        values: &[Packet],
     ) -> anyhow::Result<()> {
-        // If the received message is accompanied by a signature and is addressed to this agent,
-        // verify if the signature was generated by the game client.
-        if let Some(signature) = signature {
-            if agent_id == self.agent_id {
-                Keys::verify(message_bytes, signature, &self.game_client_pubkey)?;
-            } else {
-                bail!("[!] error: MsgKillAgent was intended for a different recipient\n")
-            }
-        } else {
+        if agent_id != packet.sender {
+            bail!(
+                "[!] error: Agent {} received gossip from Agent {} on behalf of Agent {}\n",
+                self.agent_id,
+                packet.sender,
+                agent_id
+            );
+        }
+        if let Err(reason) = self.authorize_query(packet) {
             bail!(
-                "[!] error: MsgKillAgent requires a signature, but the received packet contains None\n"
+                "[!] error: Agent {} refused gossip from Agent {} - {}\n",
+                self.agent_id,
+                agent_id,
+                reason
             );
         }
+
+        self.gossip_store
+            .merge(values, &self.known_peers.read().unwrap());
+        Ok(())
+    }
+
+    /// Builds a `MsgSendValues` containing every value the agent has gathered through gossip, as
+    /// a reply to the `MsgQueryGossip` in `request`. Liars may tamper with the values first.
+    fn handle_msg_query_gossip(&self, request: &Packet) -> anyhow::Result<Packet> {
+        let mut values = self.gossip_store.snapshot();
+        self.apply_relay_behavior(&mut values, request.round);
+        self.build_msg_send_values_reply(request, &values)
+    }
+
+    /// Signs the agent's own value for gossip under the next sequence number, stores it in the
+    /// agent's collection and pushes the
+    /// whole collection to `fanout` neighbours chosen at random among the known peers that the
+    /// network topology links to this agent. Liars may tamper with the values they push. Gossip
+    /// is best-effort, so neighbours that cannot be reached are skipped silently.
+    async fn push_gossip(&self, fanout: usize) -> anyhow::Result<()> {
+        let sequence = self.gossip_store.next_sequence();
+        let value = self.behavior.answer_query(CLIENT_ID, sequence);
+        let message = Message::build_msg_send_value(value, self.agent_id, sequence)?;
+        let own_value =
+            Packet::new(self.agent_id, CLIENT_ID, sequence, message).sign(&self.keys)?;
+        self.gossip_store.insert(own_value);
+
+        let mut values = self.gossip_store.snapshot();
+        self.apply_relay_behavior(&mut values, sequence);
+
+        let neighbours: Vec<AgentConfig> = {
+            let known_peers = self.known_peers.read().unwrap();
+            let candidates: Vec<&AgentConfig> = known_peers
+                .values()
+                .filter(|peer| peer.get_id() != self.agent_id && self.is_neighbour(peer.get_id()))
+                .collect();
+            candidates
+                .choose_multiple(&mut rand::thread_rng(), fanout)
+                .map(|peer| (*peer).clone())
+                .collect()
+        };
+
+        for neighbour in neighbours {
+            let _ = self
+                .send_msg_gossip_values(&neighbour, &values, sequence)
+                .await;
+        }
         Ok(())
     }
 
-    /// Builds a `MsgFwdValues` containing the values fetched from other agents and sends it to
-    /// the game's client.
-    async fn send_msg_fwd_values(
+    /// Pushes `values` to the agent `neighbour` in a `MsgGossipValues` signed for the gossip
+    /// `sequence`, which does not expect a reply.
+    async fn send_msg_gossip_values(
         &self,
-        peer_values: &Vec<Packet>,
-        client_socket: &mut TcpStream,
+        neighbour: &AgentConfig,
+        values: &[Packet],
+        sequence: u64,
     ) -> anyhow::Result<()> {
-        let message = Message::build_msg_fwd_values(self.agent_id, peer_values)?;
-        let message_signature = self.keys.sign(&message)?;
+        let connection = self
+            .peer_connections
+            .get(
+                neighbour.get_address(),
+                neighbour.get_port(),
+                neighbour.get_public_key(),
+            )
+            .await?;
 
-        let packet = Packet::build_packet(message, Some(message_signature))
-            .context("[!] error: failed to build packet\n")?;
+        let message = Message::build_msg_gossip_values(self.agent_id, values)?;
+        let packet = Packet::new(self.agent_id, neighbour.get_id(), sequence, message)
+            .sign(&self.keys)?
+            .encode(connection.get_codec())?;
+
+        connection.send(&packet).await?;
+        self.count_message();
+        Ok(())
+    }
+
+    /// Pushes the agent's collection of values to its neighbours on every gossip interval, for as
+    /// long as gossip is enabled, until the agent is killed.
+    async fn gossip_loop(&self, shutdown_token: CancellationToken) {
+        loop {
+            let options = *self.gossip.read().unwrap();
+            let interval = options.map_or(GOSSIP_IDLE_INTERVAL, |options| options.get_interval());
 
-        match send_packet(&packet, client_socket).await {
-            Ok(()) => Ok(()),
-            Err(e) => bail!("[!] error: unable to forward values back to client - {}", e),
+            tokio::select! {
+                _ = sleep(interval) => {
+                    if let Some(options) = options {
+                        if let Err(e) = self.push_gossip(options.get_fanout()).await {
+                            println!("{}", e);
+                        }
+                    }
+                }
+                _ = shutdown_token.cancelled() => break,
+            }
         }
     }
 
-    /// Processes a `MsgFetchValues` received from the game's client. This method receives the
-    /// addresses of peers as a Vec of `AgentConfig` instances and attempts to query each peer for
-    /// its individual value with a `MsgQueryValue`. The received replies are then used to construct
-    /// a `MsgFwdValues`. This method does not verify the signature of received replies, the task of
-    /// performing authentication is delegated to the game's client upon receiving the `MsgFwdValues`.
+    /// Processes a `MsgFetchValues` received from the game's client, or from a relay agent allowed
+    /// by the agent's access policy. This method receives the addresses of peers as a Vec of
+    /// `AgentConfig` instances and attempts to query each peer for its individual value with a
+    /// `MsgQueryValue`, reusing pooled connections to peers whenever possible. While `ttl` is
+    /// greater than 1, the peers that could not be reached are then fetched through neighbours,
+    /// whose signed `MsgFwdValues` are forwarded as they are. The received replies are then used
+    /// to construct a `MsgFwdValues`, which is returned as the reply. The client's `nonce` is
+    /// propagated to every peer query, so that relayed values are bound to the client's current
+    /// round. Only replies signed by the peer that sent them are forwarded, so that the agent's
+    /// own signature on the `MsgFwdValues` vouches for having received every value intact. The
+    /// rest of the authentication is delegated to the game's client upon receiving the
+    /// `MsgFwdValues`.
     async fn handle_msg_fetch_values(
         &self,
-        message_bytes: &[u8],
-        signature: &Option<Vec<u8>>,
-        client_socket: &mut TcpStream,
+        request: &Packet,
         agent_id: usize,
-        peer_addresses: &Vec<AgentConfig>,
-    ) -> anyhow::Result<()> {
-        if let Some(signature) = signature {
-            if agent_id == self.agent_id {
-                Keys::verify(message_bytes, signature, &self.game_client_pubkey)?;
-            } else {
-                bail!("[!] error: Agent {} received MsgFetchValues, but message is addressed to Agent {}\n", 
-                self.agent_id, agent_id);
+        peer_addresses: &[AgentConfig],
+        nonce: u64,
+        ttl: u8,
+        visited: &[usize],
+    ) -> anyhow::Result<Packet> {
+        if agent_id != self.agent_id {
+            bail!("[!] error: Agent {} received MsgFetchValues, but message is addressed to Agent {}\n", 
+            self.agent_id, agent_id);
+        }
+
+        if request.sender == CLIENT_ID {
+            self.verify_client_packet(request)?;
+        } else if let Err(reason) = self.authorize_query(request) {
+            return self.build_msg_query_refused_reply(request, nonce, &reason);
+        }
+
+        let mut peer_values = self.fetch_peer_values(peer_addresses, nonce).await;
+        if ttl > 1 {
+            let relayed = self
+                .relay_unreachable(peer_addresses, &peer_values, nonce, ttl - 1, visited)
+                .await;
+            peer_values.extend(relayed);
+        }
+        self.apply_relay_behavior(&mut peer_values, nonce);
+
+        self.build_msg_fwd_values_reply(request, &peer_values)
+    }
+
+    /// Processes a `MsgQueryValues` received from the game's client, requesting the values of the
+    /// agents in `agent_ids` for the round identified by `nonce`. Values already received from a
+    /// peer during this round are served from the agent's cache, while the remaining known peers
+    /// are queried as in `MsgFetchValues`. Unknown agent IDs are ignored. The selected values are
+    /// returned in a `MsgSendValues`, without verifying their signatures.
+    async fn handle_msg_query_values(
+        &self,
+        request: &Packet,
+        agent_ids: &[usize],
+        nonce: u64,
+    ) -> anyhow::Result<Packet> {
+        self.verify_client_packet(request)?;
+
+        let (mut values, uncached) = {
+            let cache = self.relay_cache.lock().unwrap();
+            let mut values = Vec::new();
+            let mut uncached = Vec::new();
+            for agent_id in agent_ids {
+                match cache.get(agent_id) {
+                    Some(packet) if packet.round == nonce => values.push(packet.clone()),
+                    _ => uncached.push(*agent_id),
+                }
             }
-        } else {
+            (values, uncached)
+        };
+
+        let peers: Vec<AgentConfig> = {
+            let known_peers = self.known_peers.read().unwrap();
+            uncached
+                .iter()
+                .filter_map(|agent_id| known_peers.get(agent_id).cloned())
+                .collect()
+        };
+        values.extend(self.fetch_peer_values(&peers, nonce).await);
+        self.apply_relay_behavior(&mut values, nonce);
+
+        self.build_msg_send_values_reply(request, &values)
+    }
+
+    /// Asks neighbours, one at a time, to fetch the values of the agents in `peers` that are not
+    /// covered by `peer_values`, with a hop limit of `ttl`. Neighbours are the known peers that
+    /// are neither unreachable nor in `visited`, so that a request never loops back through a
+    /// relay it has already gone through. Returns the signed `MsgFwdValues` packets received from
+    /// the neighbours.
+    async fn relay_unreachable(
+        &self,
+        peers: &[AgentConfig],
+        peer_values: &[Packet],
+        nonce: u64,
+        ttl: u8,
+        visited: &[usize],
+    ) -> Vec<Packet> {
+        let mut visited = visited.to_vec();
+        visited.push(self.agent_id);
+
+        let covered: HashSet<usize> = peer_values.iter().flat_map(Self::relayed_ids).collect();
+        let mut unreachable: Vec<AgentConfig> = peers
+            .iter()
+            .filter(|peer| !covered.contains(&peer.get_id()))
+            .cloned()
+            .collect();
+
+        let mut neighbours: Vec<AgentConfig> = {
+            let known_peers = self.known_peers.read().unwrap();
+            known_peers
+                .values()
+                .filter(|peer| {
+                    !visited.contains(&peer.get_id())
+                        && !unreachable.iter().any(|u| u.get_id() == peer.get_id())
+                        && self.is_neighbour(peer.get_id())
+                })
+                .cloned()
+                .collect()
+        };
+        neighbours.sort_by_key(|peer| peer.get_id());
+
+        let mut relayed = Vec::new();
+        for neighbour in neighbours {
+            if unreachable.is_empty() {
+                break;
+            }
+
+            match self
+                .send_msg_fetch_values(&neighbour, &unreachable, nonce, ttl, &visited)
+                .await
+            {
+                Ok(packet) => {
+                    let covered = Self::relayed_ids(&packet);
+                    unreachable.retain(|peer| !covered.contains(&peer.get_id()));
+                    relayed.push(packet);
+                }
+                Err(e) => println!("{}", e),
+            }
+        }
+
+        relayed
+    }
+
+    /// Returns the IDs of the agents whose values are carried by `packet`, either directly in a
+    /// `MsgSendValue` or through any number of nested `MsgFwdValues`. Signatures are not verified.
+    fn relayed_ids(packet: &Packet) -> Vec<usize> {
+        match Message::deserialize_message(&packet.message) {
+            Ok(Message::MsgSendValue { agent_id, .. }) => vec![agent_id],
+            Ok(Message::MsgFwdValues { peer_values, .. }) => {
+                peer_values.iter().flat_map(Self::relayed_ids).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Asks the agent `neighbour` to fetch the values of the agents in `peers` with a hop limit of
+    /// `ttl`, on behalf of the relays in `visited`. Neighbours that did not advertise
+    /// `Capabilities::MULTI_HOP` during the handshake are not asked. Returns the `MsgFwdValues`
+    /// packet sent by the neighbour once its signature has been verified. The values nested in it are vouched for by
+    /// the neighbour's signature and are not verified here.
+    async fn send_msg_fetch_values(
+        &self,
+        neighbour: &AgentConfig,
+        peers: &[AgentConfig],
+        nonce: u64,
+        ttl: u8,
+        visited: &[usize],
+    ) -> anyhow::Result<Packet> {
+        let connection = self
+            .peer_connections
+            .get(
+                neighbour.get_address(),
+                neighbour.get_port(),
+                neighbour.get_public_key(),
+            )
+            .await?;
+        if !connection
+            .get_session()
+            .get_capabilities()
+            .contains(Capabilities::MULTI_HOP)
+        {
+            bail!(
+                "[!] error: Agent {} does not support fetching values through neighbours\n",
+                neighbour.get_id()
+            );
+        }
+
+        let message =
+            Message::build_msg_fetch_values(neighbour.get_id(), peers, nonce, ttl, visited)
+                .context("[!] error: failed to build MsgFetchValues\n")?;
+
+        let codec = connection.get_codec();
+        let packet = Packet::new(self.agent_id, neighbour.get_id(), nonce, message)
+            .sign(&self.keys)?
+            .encode(codec)
+            .context("[!] error: failed to build packet\n")?;
+
+        // Every further hop may take up to a read timeout to query its own peers
+        let read_timeout = self.network.frame_config.get_read_timeout() * (u32::from(ttl) + 1);
+        self.count_message();
+        let reply = match timeout(read_timeout, connection.request(&packet)).await {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(e)) => bail!("[!] error: unable to reach agent - {}", e),
+            Err(_) => bail!(
+                "[!] error: Agent {} did not relay values within {} ms\n",
+                neighbour.get_id(),
+                read_timeout.as_millis()
+            ),
+        };
+        let reply_packet = Packet::unpack(&reply, codec)?;
+        if reply_packet.sender != neighbour.get_id() {
+            bail!(
+                "[!] error: expected a reply from Agent {}, received one from Agent {}\n",
+                neighbour.get_id(),
+                reply_packet.sender
+            );
+        }
+        reply_packet.check_context(self.agent_id, FRESHNESS_WINDOW)?;
+        reply_packet.verify(neighbour.get_public_key())?;
+        if reply_packet.round != nonce {
             bail!(
-                "[!] error: MsgFetchValues requires a signature, but the received packet contains None\n"
+                "[!] error: Agent {} relayed values for another round, possibly a replay\n",
+                neighbour.get_id()
             );
         }
 
+        match Message::deserialize_message(&reply_packet.message) {
+            Ok(Message::MsgFwdValues { .. }) => Ok(reply_packet),
+            Ok(Message::MsgQueryRefused { reason, .. }) => bail!(
+                "[!] error: Agent {} refused to relay values for Agent {} - {}\n",
+                neighbour.get_id(),
+                self.agent_id,
+                reason
+            ),
+            Ok(other) => bail!("[!] error: expected MsgFwdValues, received {:?}\n", other),
+            Err(e) => bail!("[!] error: unable to decode message - {}\n", e),
+        }
+    }
+
+    /// Queries every agent in `peers` for its value with a `MsgQueryValue` carrying `nonce`, reusing
+    /// pooled connections to peers whenever possible. Peers that the network topology does not
+    /// allow this agent to connect to are skipped. Every reply received is stored in the agent's
+    /// relay cache. Returns the replies in the order in which the queries were sent.
+    async fn fetch_peer_values(&self, peers: &[AgentConfig], nonce: u64) -> Vec<Packet> {
         let mut agent_conn_handles = Vec::new();
         let mut peer_values = Vec::new();
         let agent_arc = Arc::new(self.clone());
 
-        for peer in peer_addresses {
+        for peer in peers.iter().filter(|peer| self.is_neighbour(peer.get_id())) {
             let address = peer.get_address();
             let port = peer.get_port();
-            let mut socket = match connect(address, port).await {
-                Ok(socket) => socket,
+            let connection = match self
+                .peer_connections
+                .get(address, port, peer.get_public_key())
+                .await
+            {
+                Ok(connection) => connection,
                 Err(e) => {
                     println!(
                         "[!] error: Agent {} failed to connect to (Agent ID: {} - {}:{}) - {}\n",
@@ -278,8 +838,10 @@ impl Agent {
             };
 
             let querying_agent = agent_arc.clone();
-            let handle =
-                spawn(async move { Self::send_msg_query_value(querying_agent, &mut socket).await });
+            let peer = peer.clone();
+            let handle = spawn(async move {
+                Self::send_msg_query_value(querying_agent, &connection, &peer, nonce).await
+            });
             agent_conn_handles.push(handle);
         }
 
@@ -293,89 +855,201 @@ impl Agent {
             }
         }
 
-        // If the agent is a liar, attempt to modify the messages before forwarding them to the client
-        if self.is_liar() {
-            let received_replies = peer_values.clone();
-            if let Err(_) = self.tamper_with_messages(&mut peer_values) {
-                // If tampering fails, revert back to the original replies
-                peer_values = received_replies;
-            }
+        let mut cache = self.relay_cache.lock().unwrap();
+        for peer_value in &peer_values {
+            cache.insert(peer_value.sender, peer_value.clone());
         }
 
-        self.send_msg_fwd_values(&peer_values, client_socket)
-            .await?;
-
-        Ok(())
+        peer_values
     }
 
-    /// Queries an individual agent peer for its value by sending a `MsgQueryValue`. This function
-    /// does not perform the authentication of received messages.
+    /// Queries the agent `peer` for its value by sending a `MsgQueryValue` carrying `nonce` over
+    /// `connection`, encoded with the codec negotiated for the connection. Gives up if no reply is
+    /// received before the agent's read timeout. The reply must have been sent and signed by
+    /// `peer`, since relaying it vouches that it was received intact; checking the contents of the
+    /// value is left to the client, but values sent for another round are refused as replays.
     async fn send_msg_query_value(
         querying_agent: Arc<Self>,
-        socket: &mut TcpStream,
+        connection: &Connection,
+        peer: &AgentConfig,
+        nonce: u64,
     ) -> anyhow::Result<Packet> {
-        let message = Message::build_msg_query_value()
+        let peer_id = peer.get_id();
+        let message = Message::build_msg_query_value(nonce)
             .context("[!] error: failed to build MsgQueryValue\n")?;
 
-        let message_signature = querying_agent.keys.sign(&message)?;
-
-        // Build a packet with the message and message signature
-        let packet = Packet::build_packet(message, Some(message_signature))
+        // Build a packet with the message and sign it along with its context
+        let codec = connection.get_codec();
+        let packet = Packet::new(querying_agent.agent_id, peer_id, nonce, message)
+            .sign(&querying_agent.keys)?
+            .encode(codec)
             .context("[!] error: failed to build packet\n")?;
 
-        match send_packet(&packet, socket).await {
-            Ok(()) => (),
-            Err(e) => bail!("[!] error: unable to reach agent - {}", e),
+        // A peer that never replies must not stall the relay of the other peers' values
+        let read_timeout = querying_agent.network.frame_config.get_read_timeout();
+        querying_agent.count_message();
+        let reply = match timeout(read_timeout, connection.request(&packet)).await {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(e)) => bail!("[!] error: unable to reach agent - {}", e),
+            Err(_) => bail!(
+                "[!] error: agent did not reply within {} ms\n",
+                read_timeout.as_millis()
+            ),
+        };
+        let reply_packet = Packet::unpack(&reply, codec)?;
+        if reply_packet.sender != peer_id {
+            bail!(
+                "[!] error: expected a reply from Agent {}, received one from Agent {}\n",
+                peer_id,
+                reply_packet.sender
+            );
         }
-
-        let reply = recv_packet(socket).await?;
-        let reply_packet = Packet::unpack(&reply)?;
+        reply_packet.check_context(querying_agent.agent_id, FRESHNESS_WINDOW)?;
+        reply_packet.verify(peer.get_public_key())?;
 
         match Message::deserialize_message(&reply_packet.message) {
-            Ok(Message::MsgSendValue { .. }) => Ok(reply_packet),
+            Ok(Message::MsgSendValue {
+                agent_id,
+                nonce: reply_nonce,
+                ..
+            }) if agent_id == peer_id && reply_nonce == nonce && reply_packet.round == nonce => {
+                Ok(reply_packet)
+            }
+            Ok(Message::MsgSendValue { .. }) => bail!(
+                "[!] error: Agent {} sent a value for another round, possibly a replay\n",
+                peer_id
+            ),
+            Ok(Message::MsgQueryRefused { reason, .. }) => bail!(
+                "[!] error: Agent {} refused the query of Agent {} - {}\n",
+                peer_id,
+                querying_agent.agent_id,
+                reason
+            ),
             Ok(other) => bail!("[!] error: expected MsgSendValue, received {:?}\n", other),
             Err(e) => bail!("[!] error: unable to decode message - {}\n", e),
         }
     }
 
-    /// Receives a packet and executes the required logic according to the type of message it contains.
+    /// Receives a packet encoded with `codec` and executes the required logic according to the
+    /// type of message it contains. Returns the reply packet, encoded with the same codec, if the
+    /// message requires one.
     async fn packet_handler(
         &self,
         packet_bytes: &[u8],
-        socket: &mut TcpStream,
+        codec: Codec,
         shutdown_token: CancellationToken,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Option<Vec<u8>>> {
         let packet =
-            Packet::unpack(packet_bytes).context("[!] error: unable to decode packet\n")?;
+            Packet::unpack(packet_bytes, codec).context("[!] error: unable to decode packet\n")?;
+
+        let reply = self.message_handler(&packet, shutdown_token).await?;
+        match reply {
+            Some(reply) => Ok(Some(
+                reply
+                    .encode(codec)
+                    .context("[!] error: failed to build packet\n")?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Executes the required logic according to the type of message contained in `packet`.
+    /// Packets addressed to another participant or outside the freshness window are rejected, and
+    /// requests that are not signed by an agent the network topology links to this agent are
+    /// refused.
+    /// Returns the reply packet, if the message requires one.
+    async fn message_handler(
+        &self,
+        packet: &Packet,
+        shutdown_token: CancellationToken,
+    ) -> anyhow::Result<Option<Packet>> {
+        packet
+            .check_context(self.agent_id, FRESHNESS_WINDOW)
+            .with_context(|| format!("[!] warning: Agent {} rejected a packet", self.agent_id))?;
+
+        // Requests from agents that the topology does not link to this agent are always refused
+        if let Err(reason) = self.authorize_neighbour(packet) {
+            return self
+                .build_msg_query_refused_reply(packet, packet.round, &reason)
+                .map(Some);
+        }
+
+        // Agents left out of an expert round may only be reached by the client through relays
+        if packet.sender == CLIENT_ID && self.client_lockout.load(Ordering::Relaxed) {
+            // Only requests that were really sent by the client count as violations
+            if packet.verify(&self.game_client_pubkey).is_ok() {
+                self.lockout_violations.fetch_add(1, Ordering::Relaxed);
+            }
+            let reason = format!(
+                "Agent {} is outside the expert subset and cannot be contacted directly by the client",
+                self.agent_id
+            );
+            return self
+                .build_msg_query_refused_reply(packet, packet.round, &reason)
+                .map(Some);
+        }
+
         let message = Message::deserialize_message(&packet.message);
 
         match message {
-            Ok(Message::MsgQueryValue) => self.handle_msg_query_value(socket).await?,
+            Ok(Message::MsgHello { .. })
+            | Ok(Message::MsgHelloAck { .. })
+            | Ok(Message::MsgHelloReject { .. }) => {
+                bail!(
+                    "[!] warning: Agent {} received an unexpected handshake message",
+                    self.agent_id
+                );
+            }
+            Ok(Message::MsgQueryValue { nonce }) => match self.authorize_query(packet) {
+                Ok(()) => Ok(Some(self.handle_msg_query_value(packet, nonce)?)),
+                Err(reason) => Ok(Some(
+                    self.build_msg_query_refused_reply(packet, nonce, &reason)?,
+                )),
+            },
             Ok(Message::MsgSendValue { .. }) => {
                 bail!(
                     "[!] warning: Agent {} received an unexpected MsgSendValue",
                     self.agent_id
                 );
             }
-            Ok(Message::MsgKillAgent { agent_id }) => {
-                if let Ok(()) =
-                    self.handle_msg_kill_agent(&packet.message, &packet.msg_sig, agent_id)
-                {
-                    shutdown_token.cancel();
+            Ok(ref message @ Message::MsgKillAgent { agent_id }) => {
+                if let Ok(()) = self.handle_msg_kill_agent(packet, agent_id) {
+                    if self.behavior.handle_control(message) {
+                        shutdown_token.cancel();
+                    }
                 }
+                Ok(None)
             }
             Ok(Message::MsgFetchValues {
                 agent_id,
                 peer_addresses,
+                nonce,
+                ttl,
+                visited,
             }) => {
-                self.handle_msg_fetch_values(
-                    &packet.message,
-                    &packet.msg_sig,
-                    socket,
-                    agent_id,
-                    &peer_addresses,
-                )
-                .await?
+                let reply = self
+                    .handle_msg_fetch_values(
+                        packet,
+                        agent_id,
+                        &peer_addresses,
+                        nonce,
+                        ttl,
+                        &visited,
+                    )
+                    .await?;
+                Ok(Some(reply))
+            }
+            Ok(Message::MsgQueryValues { agent_ids, nonce }) => {
+                let reply = self
+                    .handle_msg_query_values(packet, &agent_ids, nonce)
+                    .await?;
+                Ok(Some(reply))
+            }
+            Ok(Message::MsgSendValues { .. }) => {
+                bail!(
+                    "[!] warning: Agent {} received an unexpected MsgSendValues",
+                    self.agent_id
+                );
             }
             Ok(Message::MsgFwdValues { .. }) => {
                 bail!(
@@ -383,22 +1057,120 @@ impl Agent {
                     self.agent_id
                 );
             }
-            Err(e) => println!("[!] error: unable to decode message - {}\n", e),
+            Ok(Message::MsgQueryRefused { .. }) => {
+                bail!(
+                    "[!] warning: Agent {} received an unexpected MsgQueryRefused",
+                    self.agent_id
+                );
+            }
+            Ok(Message::MsgGossipValues { agent_id, values }) => {
+                self.handle_msg_gossip_values(packet, agent_id, &values)?;
+                Ok(None)
+            }
+            Ok(Message::MsgQueryGossip { nonce }) => match self.authorize_query(packet) {
+                Ok(()) => Ok(Some(self.handle_msg_query_gossip(packet)?)),
+                Err(reason) => Ok(Some(
+                    self.build_msg_query_refused_reply(packet, nonce, &reason)?,
+                )),
+            },
+            Err(e) => {
+                println!("[!] error: unable to decode message - {}\n", e);
+                Ok(None)
+            }
         }
-
-        Ok(())
     }
 
-    /// Processes incoming packets from an active TCP connection. This method reads packets from
-    /// a `TcpStream` and handles them using internal packet handling logic.
+    /// Processes incoming packets from an active TCP connection. This method performs the protocol
+    /// handshake, refusing encrypted connections from agents the network topology does not link to
+    /// this agent, then reads frames from the `TcpStream` until the peer closes the connection or
+    /// the agent is killed. Each frame is handled by its own task, so that many requests may be in
+    /// flight on the same connection, and every reply is tagged with the ID of its request.
     async fn connection_handler(
         &self,
-        socket: &mut TcpStream,
+        mut socket: TcpStream,
         shutdown_token: CancellationToken,
     ) -> anyhow::Result<()> {
-        let packet_bytes = recv_packet(socket).await?;
-        self.packet_handler(&packet_bytes, socket, shutdown_token)
-            .await?;
+        let frame_config = self.network.frame_config;
+        let session = accept_handshake(
+            &mut socket,
+            &frame_config,
+            self.network.acceptor_capabilities(),
+            self.network.required_capabilities(),
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "[!] error: Agent {} failed to complete handshake\n",
+                self.agent_id
+            )
+        })?;
+
+        let channel = if session
+            .get_capabilities()
+            .contains(Capabilities::ENCRYPTION)
+        {
+            let channel = accept_secure_channel(&mut socket, &frame_config, &self.keys)
+                .await
+                .with_context(|| {
+                    format!(
+                        "[!] error: Agent {} failed to establish a secure channel\n",
+                        self.agent_id
+                    )
+                })?;
+            if !self.is_neighbour_identity(channel.get_peer_identity()) {
+                bail!(
+                    "[!] error: Agent {} refused a connection from an agent it is not linked to\n",
+                    self.agent_id
+                );
+            }
+            Some(channel)
+        } else {
+            None
+        };
+
+        let codec = session.get_codec();
+        let (mut reader, writer) = split_frames(socket, channel);
+        let writer = Arc::new(AsyncMutex::new(writer));
+
+        loop {
+            let frame = tokio::select! {
+                frame = reader.recv_frame(&frame_config) => match frame {
+                    Ok(frame) => frame,
+                    // The peer closed the connection
+                    Err(FrameError::PeerClosed) => break,
+                    Err(e) => bail!("[!] error: Agent {} failed to read frame - {}\n", self.agent_id, e),
+                },
+                _ = shutdown_token.cancelled() => break,
+            };
+
+            let agent = self.clone();
+            let writer = writer.clone();
+            let shutdown_token = shutdown_token.clone();
+
+            spawn(async move {
+                match agent
+                    .packet_handler(&frame.packet, codec, shutdown_token)
+                    .await
+                {
+                    Ok(Some(reply)) => {
+                        let mut writer = writer.lock().await;
+                        match writer
+                            .send_frame(frame.request_id, &reply, &frame_config)
+                            .await
+                        {
+                            Ok(()) => agent.count_message(),
+                            Err(e) => println!(
+                                "[!] error: Agent {} failed to send reply - {}\n",
+                                agent.agent_id, e
+                            ),
+                        }
+                    }
+                    Ok(None) => (),
+                    Err(e) => println!("{}", e),
+                }
+            });
+        }
+
         Ok(())
     }
 
@@ -432,10 +1204,14 @@ impl Agent {
 
         let cancellation_token = CancellationToken::new();
 
+        let agent = self.clone();
+        let shutdown_token = cancellation_token.clone();
+        spawn(async move { agent.gossip_loop(shutdown_token).await });
+
         loop {
             tokio::select! {
                 conn = listener.accept() => {
-                    if let Ok((mut socket, _)) = conn {
+                    if let Ok((socket, _)) = conn {
                         // NOTE: Cloning can be expensive, however, given that instances of `Agent`
                         // do not contain large amounts of data, using it here allows us to
                         // avoid the extra complexity of having to manage lifetimes.
@@ -443,7 +1219,7 @@ impl Agent {
                         let shutdown_token = cancellation_token.clone();
 
                         spawn(async move {
-                            if let Err(e) = agent.connection_handler(&mut socket, shutdown_token)
+                            if let Err(e) = agent.connection_handler(socket, shutdown_token)
                             .await {
                                 println!("{}", e);
                             }
@@ -466,21 +1242,6 @@ impl Agent {
     fn get_new_id() -> usize {
         AGENT_ID_COUNTER.fetch_add(1, Ordering::Relaxed)
     }
-
-    /// Returns an arbitrary `liar_value`, such that `liar_value` != `honest_value` and
-    /// 1 <= `liar_value` <= `max_value`.
-    fn get_liar_value(honest_value: u64, max_value: u64) -> u64 {
-        let value_to_skip = honest_value;
-
-        // Shorten the gen_range by 1 and increment by 1 if liar_value >= value_to_skip
-        // This effectively skips value_to_skip and is an alternative to a "loop until different"
-        // approach, which might require a theoretically unbounded number of tries
-        let mut liar_value = rand::thread_rng().gen_range(1..=(max_value - 1));
-        if liar_value >= value_to_skip {
-            liar_value += 1;
-        }
-        liar_value
-    }
 }
 
 // ******************************************************************************************
@@ -490,30 +1251,8 @@ impl Agent {
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    #[test]
-    fn liar_value_is_diff_from_honest() {
-        // Must be careful when testing randomly generated values like this.
-        // Even though the chance of the test failing is negligible for a
-        // high number of iterations, for applications where security is critical
-        // a more robust testing strategy should be used.
-        let honest_value = 5;
-        let max_value = 10;
-        let iter = 10_000;
-
-        for _ in 0..iter {
-            let liar_value = Agent::get_liar_value(honest_value, max_value);
-            assert_ne!(liar_value, 0, "Liar value cannot be 0");
-            assert_ne!(
-                liar_value, honest_value,
-                "Liar value must be different from honest value"
-            );
-            assert!(
-                liar_value <= max_value,
-                "Liar value cannot be greater than max_value"
-            );
-        }
-    }
+    use crate::connection::recv_frame;
+    use crate::network_utils::FrameConfig;
 
     #[test]
     fn gen_unique_port() {
@@ -535,16 +1274,25 @@ mod tests {
 
     #[test]
     fn test_agent_to_config() {
+        let keys = Keys::new_key_pair();
         let agent = Agent {
             agent_id: 1,
-            value: 10,
             address: "127.0.0.1".to_owned(),
             port: 9001,
-            keys: Keys::new_key_pair(),
+            keys: keys.clone(),
             game_client_pubkey: "Hv9PImawhJ9+0ulJ/dlKjxTu+vKcKnyoJG5ahh4+DjY=".to_owned(),
             status: AgentStatus::Uninitialized,
-            is_liar: false,
-            tamper_chance: 0.0,
+            behavior: Arc::new(Honest::new(10)),
+            network: NetworkOptions::default(),
+            known_peers: Arc::new(RwLock::new(HashMap::new())),
+            relay_cache: Arc::new(Mutex::new(HashMap::new())),
+            neighbours: Arc::new(RwLock::new(None)),
+            client_lockout: Arc::new(AtomicBool::new(false)),
+            lockout_violations: Arc::new(AtomicUsize::new(0)),
+            gossip: Arc::new(RwLock::new(None)),
+            gossip_store: GossipStore::default(),
+            messages_sent: Arc::new(AtomicUsize::new(0)),
+            peer_connections: ConnectionPool::new(NetworkOptions::default(), keys.clone()),
         };
 
         assert_eq!(
@@ -552,4 +1300,87 @@ mod tests {
             AgentConfig::new(1, "127.0.0.1", 9001, agent.keys.get_public_key(),)
         );
     }
+
+    // An agent that is not linked to the recipient cannot pose as one of its neighbours
+    #[tokio::test]
+    async fn test_topology_refuses_spoofed_neighbours() {
+        let client_keys = Keys::new_key_pair();
+        let client_pubkey = client_keys.get_public_key().to_owned();
+        let agent = Agent::new_honest(5, client_pubkey.clone(), NetworkOptions::default());
+        let neighbour = Agent::new_honest(5, client_pubkey.clone(), NetworkOptions::default());
+        let outsider = Agent::new_honest(5, client_pubkey, NetworkOptions::default());
+
+        agent.set_known_peers(&[
+            agent.to_config(),
+            neighbour.to_config(),
+            outsider.to_config(),
+        ]);
+        agent.set_neighbours(Some(&BTreeSet::from([neighbour.get_id()])));
+        assert!(agent.is_neighbour_identity(neighbour.get_public_key()));
+        assert!(agent.is_neighbour_identity(client_keys.get_public_key()));
+        assert!(!agent.is_neighbour_identity(outsider.get_public_key()));
+
+        let query = Message::build_msg_query_value(1).unwrap();
+        let genuine = Packet::new(neighbour.get_id(), agent.get_id(), 1, query.clone())
+            .sign(&neighbour.keys)
+            .unwrap();
+        let spoofed = Packet::new(neighbour.get_id(), agent.get_id(), 1, query)
+            .sign(&outsider.keys)
+            .unwrap();
+
+        for (packet, refused) in [(genuine, false), (spoofed, true)] {
+            let reply = agent
+                .message_handler(&packet, CancellationToken::new())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                matches!(
+                    Message::deserialize_message(&reply.message),
+                    Ok(Message::MsgQueryRefused { .. })
+                ),
+                refused
+            );
+        }
+    }
+
+    // Neighbours that do not support multi-hop relaying are never sent a MsgFetchValues
+    #[tokio::test]
+    async fn test_fetch_values_skips_neighbours_without_multi_hop() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port().into();
+        let received = Arc::new(AtomicUsize::new(0));
+
+        let counter = received.clone();
+        spawn(async move {
+            let config = FrameConfig::default();
+            let (mut socket, _) = listener.accept().await.unwrap();
+            accept_handshake(
+                &mut socket,
+                &config,
+                Capabilities::NONCES,
+                Capabilities::NONE,
+            )
+            .await
+            .unwrap();
+            while recv_frame(&mut socket, &config).await.is_ok() {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        let client_keys = Keys::new_key_pair();
+        let agent = Agent::new_honest(
+            5,
+            client_keys.get_public_key().to_owned(),
+            NetworkOptions::default(),
+        );
+        let neighbour_keys = Keys::new_key_pair();
+        let neighbour = AgentConfig::new(99, "127.0.0.1", port, neighbour_keys.get_public_key());
+
+        assert!(agent
+            .send_msg_fetch_values(&neighbour, &[], 1, 2, &[agent.get_id()])
+            .await
+            .is_err());
+        assert_eq!(received.load(Ordering::Relaxed), 0);
+    }
 }
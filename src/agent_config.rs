@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 ///
 /// `AgentConfig` contains information regarding an agent's `agent_id`, `address`,
 ///  `port` and `public_key`, which are necessary for communication with other participants of
-/// the game. `AgentConfig` omits `Agent.value`, which should be obtainable only
+/// the game. `AgentConfig` omits the value reported by the agent, which should be obtainable only
 /// by directly querying each instance of `Agent`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct AgentConfig {
@@ -48,6 +48,15 @@ impl Args {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::access::AccessPolicy;
+    use crate::behavior::RelayStrategy;
+    use crate::client::{
+        DEFAULT_AGENT_TIMEOUT_MS, DEFAULT_BACKOFF_MS, DEFAULT_RELAY_TTL, DEFAULT_RETRIES,
+        DEFAULT_ROUND_TIMEOUT_MS,
+    };
+    use crate::codec::Codec;
+    use crate::inference::InferenceKind;
+    use crate::network_utils::DEFAULT_MAX_FRAME_SIZE;
 
     #[test]
     fn test_parse_start_command() {
@@ -61,6 +70,13 @@ mod tests {
                     num_agents: 5,
                     liar_ratio: 0.2,
                     tamper_chance: 0.35,
+                    equivocate: false,
+                    liar_strategy: vec![],
+                    coalition_size: 1,
+                    max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+                    encrypted: false,
+                    codec: Codec::Bincode,
+                    access_policy: AccessPolicy::KnownPeers,
                 }
             }),
             Args::parse_args(input)
@@ -68,6 +84,53 @@ mod tests {
 
         let incomplete_input = "start --value 5 --max-value 8 --num-agents 5";
         assert!(Args::parse_args(incomplete_input).is_err());
+
+        let codec_input = format!("{} --codec cbor", input);
+        assert!(matches!(
+            Args::parse_args(&codec_input).unwrap().get_command(),
+            Commands::Start {
+                codec: Codec::Cbor,
+                ..
+            }
+        ));
+        assert!(Args::parse_args(&format!("{} --codec xml", input)).is_err());
+
+        assert!(matches!(
+            Args::parse_args(&format!("{} --equivocate", input))
+                .unwrap()
+                .get_command(),
+            Commands::Start {
+                equivocate: true,
+                ..
+            }
+        ));
+
+        let strategy_input = format!("{} --liar-strategy omit,replay", input);
+        assert!(matches!(
+            Args::parse_args(&strategy_input).unwrap().get_command(),
+            Commands::Start { liar_strategy, .. }
+                if liar_strategy == vec![RelayStrategy::Omit, RelayStrategy::Replay]
+        ));
+        assert!(Args::parse_args(&format!("{} --liar-strategy drop", input)).is_err());
+
+        assert!(matches!(
+            Args::parse_args(&format!("{} --coalition-size 3", input))
+                .unwrap()
+                .get_command(),
+            Commands::Start {
+                coalition_size: 3,
+                ..
+            }
+        ));
+
+        let policy_input = format!("{} --access-policy client-only", input);
+        assert!(matches!(
+            Args::parse_args(&policy_input).unwrap().get_command(),
+            Commands::Start {
+                access_policy: AccessPolicy::ClientOnly,
+                ..
+            }
+        ));
     }
 
     #[test]
@@ -75,11 +138,58 @@ mod tests {
         let input = "play";
         assert_eq!(
             Ok(Args {
-                command: Commands::Play
+                command: Commands::Play {
+                    agent_timeout: DEFAULT_AGENT_TIMEOUT_MS,
+                    round_timeout: DEFAULT_ROUND_TIMEOUT_MS,
+                    retries: DEFAULT_RETRIES,
+                    backoff: DEFAULT_BACKOFF_MS,
+                    strategy: InferenceKind::Plurality,
+                    fault_bound: None,
+                    adaptive: false,
+                    liar_prior: None,
+                }
             }),
             Args::parse_args(input)
         );
 
+        let input = "play --agent-timeout 500 --round-timeout 3000 --retries 0 --backoff 50";
+        assert_eq!(
+            Ok(Args {
+                command: Commands::Play {
+                    agent_timeout: 500,
+                    round_timeout: 3000,
+                    retries: 0,
+                    backoff: 50,
+                    strategy: InferenceKind::Plurality,
+                    fault_bound: None,
+                    adaptive: false,
+                    liar_prior: None,
+                }
+            }),
+            Args::parse_args(input)
+        );
+
+        assert!(matches!(
+            Args::parse_args("play --strategy trimmed-mean")
+                .unwrap()
+                .get_command(),
+            Commands::Play {
+                strategy: InferenceKind::TrimmedMean,
+                ..
+            }
+        ));
+        assert!(Args::parse_args("play --strategy bogus").is_err());
+        assert!(matches!(
+            Args::parse_args("play --strategy supermajority --fault-bound 2 --adaptive")
+                .unwrap()
+                .get_command(),
+            Commands::Play {
+                fault_bound: Some(2),
+                adaptive: true,
+                ..
+            }
+        ));
+
         let wrong_input = "play --id 3";
         assert!(Args::parse_args(wrong_input).is_err());
     }
@@ -91,12 +201,35 @@ mod tests {
             Ok(Args {
                 command: Commands::Extend {
                     num_agents: 5,
-                    liar_ratio: 0.6
+                    liar_ratio: 0.6,
+                    liar_strategy: vec![],
+                    coalition_size: None,
+                    coalition: None,
                 }
             }),
             Args::parse_args(input)
         );
 
+        let strategy_input = format!("{} --liar-strategy duplicate", input);
+        assert!(matches!(
+            Args::parse_args(&strategy_input).unwrap().get_command(),
+            Commands::Extend { liar_strategy, .. } if liar_strategy == vec![RelayStrategy::Duplicate]
+        ));
+
+        assert!(matches!(
+            Args::parse_args(&format!("{} --coalition 2", input))
+                .unwrap()
+                .get_command(),
+            Commands::Extend {
+                coalition: Some(2),
+                ..
+            }
+        ));
+        // New liars either join an existing coalition or form new ones
+        let conflicting_input = format!("{} --coalition 2 --coalition-size 3", input);
+        assert!(Args::parse_args(&conflicting_input).is_err());
+        assert!(Args::parse_args(&format!("{} --coalition-size 0", input)).is_err());
+
         let incomplete_input = "extend --liar-ratio 0.5";
         assert!(Args::parse_args(incomplete_input).is_err());
     }
@@ -108,12 +241,32 @@ mod tests {
             Ok(Args {
                 command: Commands::PlayExpert {
                     num_agents: 2,
-                    liar_ratio: 0.5
+                    liar_ratio: 0.5,
+                    agent_timeout: DEFAULT_AGENT_TIMEOUT_MS,
+                    round_timeout: DEFAULT_ROUND_TIMEOUT_MS,
+                    retries: DEFAULT_RETRIES,
+                    backoff: DEFAULT_BACKOFF_MS,
+                    strategy: InferenceKind::Plurality,
+                    fault_bound: None,
+                    adaptive: false,
+                    liar_prior: None,
+                    ttl: DEFAULT_RELAY_TTL,
+                    by_reputation: false,
                 }
             }),
             Args::parse_args(input)
         );
 
+        let strategy_input = format!("{} --strategy reputation --by-reputation", input);
+        assert!(matches!(
+            Args::parse_args(&strategy_input).unwrap().get_command(),
+            Commands::PlayExpert {
+                strategy: InferenceKind::Reputation,
+                by_reputation: true,
+                ..
+            }
+        ));
+
         let incomplete_input = "extend --num-agents 2";
         assert!(Args::parse_args(incomplete_input).is_err());
     }
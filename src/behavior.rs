@@ -0,0 +1,402 @@
+use clap::ValueEnum;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::message::Message;
+use crate::packet::Packet;
+
+/// Represents how an agent behaves in the game, i.e, the value it reports, what it does to the
+/// values it relays and whether it complies with control messages from the game's client.
+///
+/// Every hook is called by `Agent` after the request it answers has been authenticated and
+/// authorized, so implementations only decide on the agent's reply. Custom adversaries can be
+/// deployed with `Agent::with_behavior`.
+pub trait AgentBehavior: fmt::Debug + Send + Sync {
+    /// Whether the agent counts as a liar when the game forms subsets of honest agents and liars.
+    fn is_liar(&self) -> bool;
+
+    /// Returns the value the agent reports when answering a query signed by `requester` for the
+    /// round `nonce`. Values pushed through gossip are requested by the game's client.
+    fn answer_query(&self, requester: usize, nonce: u64) -> u64;
+
+    /// Called with the values fetched from peers by the agent `agent_id` for the round `nonce`,
+    /// before they are relayed. Values may be altered, dropped or added. If an error is returned,
+    /// the values fetched from peers are relayed untouched. Values are relayed as received by
+    /// default.
+    fn relay_values(
+        &self,
+        _agent_id: usize,
+        _peer_values: &mut Vec<Packet>,
+        _nonce: u64,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called with a control message, such as `MsgKillAgent`, once it has been authenticated as
+    /// coming from the game's client. Returns whether the agent complies, which it does by default.
+    fn handle_control(&self, _message: &Message) -> bool {
+        true
+    }
+}
+
+/// An agent that always reports the honest value and relays values as received.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Honest {
+    /// The value reported by the agent.
+    value: u64,
+}
+
+impl Honest {
+    /// Returns a new instance of `Honest` that reports `value`.
+    pub fn new(value: u64) -> Self {
+        Honest { value }
+    }
+}
+
+impl AgentBehavior for Honest {
+    fn is_liar(&self) -> bool {
+        false
+    }
+
+    fn answer_query(&self, _requester: usize, _nonce: u64) -> u64 {
+        self.value
+    }
+}
+
+/// An agent that reports a false value drawn at random, and relays values as received.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomLiar {
+    /// The false value reported by the agent.
+    value: u64,
+    /// The value reported by honest agents.
+    honest_value: u64,
+    /// The maximum value of the game if the agent equivocates, i.e, signs a different false value
+    /// for every query it answers, or None if it always reports `value`.
+    equivocation: Option<u64>,
+}
+
+impl RandomLiar {
+    /// Returns a new instance of `RandomLiar` reporting a false value x, such that
+    /// x != honest_value AND 1 <= x <= max_value. If `equivocate` is `true`, a new false value is
+    /// drawn for every query instead.
+    pub fn new(honest_value: u64, max_value: u64, equivocate: bool) -> Self {
+        RandomLiar {
+            value: random_liar_value(honest_value, max_value),
+            honest_value,
+            equivocation: equivocate.then_some(max_value),
+        }
+    }
+
+    /// Returns a new instance of `RandomLiar` reporting `value`, the false value shared by the
+    /// coalition it colludes with. If `equivocate` is `true`, a new false value x, such that
+    /// x != honest_value AND 1 <= x <= max_value, is drawn for every query instead.
+    pub fn colluding(value: u64, honest_value: u64, max_value: u64, equivocate: bool) -> Self {
+        RandomLiar {
+            value,
+            honest_value,
+            equivocation: equivocate.then_some(max_value),
+        }
+    }
+}
+
+impl AgentBehavior for RandomLiar {
+    fn is_liar(&self) -> bool {
+        true
+    }
+
+    fn answer_query(&self, _requester: usize, _nonce: u64) -> u64 {
+        match self.equivocation {
+            Some(max_value) => random_liar_value(self.honest_value, max_value),
+            None => self.value,
+        }
+    }
+}
+
+/// Represents the ways in which a liar may tamper with the values it relays. Every strategy is
+/// applied to each relayed value, or to the whole batch for `Reorder`, with the liar's tamper chance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum RelayStrategy {
+    /// Overwrites the message of a value with an arbitrary, unsigned `MsgSendValue`.
+    #[default]
+    Forge,
+    /// Drops honest values.
+    Omit,
+    /// Replaces a value with the one signed by the same agent in an earlier round.
+    Replay,
+    /// Replaces an honest value with a validly signed value of another liar.
+    Substitute,
+    /// Relays a value more than once.
+    Duplicate,
+    /// Shuffles the order in which values are relayed.
+    Reorder,
+}
+
+impl fmt::Display for RelayStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelayStrategy::Forge => write!(f, "forge"),
+            RelayStrategy::Omit => write!(f, "omit"),
+            RelayStrategy::Replay => write!(f, "replay"),
+            RelayStrategy::Substitute => write!(f, "substitute"),
+            RelayStrategy::Duplicate => write!(f, "duplicate"),
+            RelayStrategy::Reorder => write!(f, "reorder"),
+        }
+    }
+}
+
+/// A liar that answers queries like `RandomLiar` and tampers with the values it relays according
+/// to its `RelayStrategy`. Clones share the values kept for replays.
+#[derive(Debug, Clone)]
+pub struct TamperingRelay {
+    /// Answers queries on behalf of the relay.
+    liar: RandomLiar,
+    /// The probability that the relay will tamper with values when forwarding them.
+    tamper_chance: f32,
+    /// How the relay tampers with the values it forwards.
+    strategy: RelayStrategy,
+    /// The latest signed value relayed for each peer, indexed by agent ID. Used to replay values
+    /// from earlier rounds.
+    replay_pool: Arc<Mutex<HashMap<usize, Packet>>>,
+}
+
+impl TamperingRelay {
+    /// Returns a new instance of `TamperingRelay` answering queries like `liar`, which tampers
+    /// with relayed values with probability `tamper_chance`, following `strategy`.
+    pub fn new(liar: RandomLiar, tamper_chance: f32, strategy: RelayStrategy) -> Self {
+        TamperingRelay {
+            liar,
+            tamper_chance,
+            strategy,
+            replay_pool: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns `true` with a probability equal to `TamperingRelay.tamper_chance`.
+    fn roll_tamper(&self) -> bool {
+        rand::thread_rng().gen::<f32>() < self.tamper_chance
+    }
+
+    /// Checks whether `packet` carries the value reported by honest agents.
+    fn is_honest_value(&self, packet: &Packet) -> bool {
+        matches!(
+            Message::deserialize_message(&packet.message),
+            Ok(Message::MsgSendValue { value, .. }) if value == self.liar.honest_value
+        )
+    }
+}
+
+impl AgentBehavior for TamperingRelay {
+    fn is_liar(&self) -> bool {
+        self.liar.is_liar()
+    }
+
+    fn answer_query(&self, requester: usize, nonce: u64) -> u64 {
+        self.liar.answer_query(requester, nonce)
+    }
+
+    /// Tampers with the values in `peer_values` according to `TamperingRelay.strategy`. Forged
+    /// messages carry the round's `nonce`, so that only their signatures can give them away.
+    fn relay_values(
+        &self,
+        agent_id: usize,
+        peer_values: &mut Vec<Packet>,
+        nonce: u64,
+    ) -> anyhow::Result<()> {
+        let received_values = peer_values.clone();
+
+        match self.strategy {
+            RelayStrategy::Forge => {
+                for packet in peer_values.iter_mut() {
+                    if self.roll_tamper() {
+                        let forged_value = rand::thread_rng().gen_range(0..=100);
+                        // Change the message contained within the packet to an arbitrary message.
+                        packet.message = Message::build_msg_send_value(
+                            forged_value,
+                            forged_value as usize,
+                            nonce,
+                        )?;
+                    }
+                }
+            }
+            RelayStrategy::Omit => {
+                peer_values.retain(|packet| !(self.is_honest_value(packet) && self.roll_tamper()))
+            }
+            RelayStrategy::Replay => {
+                let replay_pool = self.replay_pool.lock().unwrap();
+                for packet in peer_values.iter_mut() {
+                    match replay_pool.get(&packet.sender) {
+                        Some(older) if older.round != nonce && self.roll_tamper() => {
+                            *packet = older.clone()
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            RelayStrategy::Substitute => {
+                // Another liar's value is any value, signed by a peer, that is not the honest one
+                let liar_value = received_values
+                    .iter()
+                    .find(|packet| packet.sender != agent_id && !self.is_honest_value(packet));
+                if let Some(liar_value) = liar_value {
+                    for packet in peer_values.iter_mut() {
+                        if self.is_honest_value(packet) && self.roll_tamper() {
+                            *packet = liar_value.clone();
+                        }
+                    }
+                }
+            }
+            RelayStrategy::Duplicate => {
+                let duplicates: Vec<Packet> = received_values
+                    .iter()
+                    .filter(|_| self.roll_tamper())
+                    .cloned()
+                    .collect();
+                peer_values.extend(duplicates);
+            }
+            RelayStrategy::Reorder => {
+                if self.roll_tamper() {
+                    peer_values.shuffle(&mut rand::thread_rng());
+                }
+            }
+        }
+
+        // Keep the genuine values, so that they can be replayed in later rounds
+        let mut replay_pool = self.replay_pool.lock().unwrap();
+        for packet in received_values {
+            replay_pool.insert(packet.sender, packet);
+        }
+        Ok(())
+    }
+}
+
+/// Returns an arbitrary `liar_value`, such that `liar_value` != `honest_value` and
+/// 1 <= `liar_value` <= `max_value`.
+pub fn random_liar_value(honest_value: u64, max_value: u64) -> u64 {
+    let value_to_skip = honest_value;
+
+    // Shorten the gen_range by 1 and increment by 1 if liar_value >= value_to_skip
+    // This effectively skips value_to_skip and is an alternative to a "loop until different"
+    // approach, which might require a theoretically unbounded number of tries
+    let mut liar_value = rand::thread_rng().gen_range(1..=(max_value - 1));
+    if liar_value >= value_to_skip {
+        liar_value += 1;
+    }
+    liar_value
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::Keys;
+
+    #[test]
+    fn liar_value_is_diff_from_honest() {
+        // Must be careful when testing randomly generated values like this.
+        // Even though the chance of the test failing is negligible for a
+        // high number of iterations, for applications where security is critical
+        // a more robust testing strategy should be used.
+        let honest_value = 5;
+        let max_value = 10;
+        let iter = 10_000;
+
+        for _ in 0..iter {
+            let liar_value = random_liar_value(honest_value, max_value);
+            assert_ne!(liar_value, 0, "Liar value cannot be 0");
+            assert_ne!(
+                liar_value, honest_value,
+                "Liar value must be different from honest value"
+            );
+            assert!(
+                liar_value <= max_value,
+                "Liar value cannot be greater than max_value"
+            );
+        }
+    }
+
+    #[test]
+    fn test_behaviors_answer_queries() {
+        let honest = Honest::new(5);
+        assert!(!honest.is_liar());
+        assert_eq!(honest.answer_query(0, 1), 5);
+
+        let liar = RandomLiar::new(5, 10, false);
+        let value = liar.answer_query(0, 1);
+        assert!(liar.is_liar());
+        assert_ne!(value, 5);
+        assert!((0..10).all(|nonce| liar.answer_query(0, nonce) == value));
+
+        // Colluding liars report the value of their coalition
+        let colluding = RandomLiar::colluding(7, 5, 10, false);
+        assert!((0..10).all(|nonce| colluding.answer_query(0, nonce) == 7));
+
+        // Equivocating liars draw a new false value for every query
+        let equivocating = RandomLiar::new(5, 1_000_000, true);
+        let values: Vec<u64> = (0..10)
+            .map(|nonce| equivocating.answer_query(0, nonce))
+            .collect();
+        assert!(values.iter().all(|value| *value != 5));
+        assert!(values.windows(2).any(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn test_tampering_relay_strategies() {
+        let keys = Keys::new_key_pair();
+        let relay = |strategy: RelayStrategy| {
+            TamperingRelay::new(RandomLiar::new(10, 1000, false), 1.0, strategy)
+        };
+        let batch = |nonce: u64| -> Vec<Packet> {
+            [(2, 10), (3, 500)]
+                .iter()
+                .map(|&(sender, value)| {
+                    let message = Message::build_msg_send_value(value, sender, nonce).unwrap();
+                    Packet::new(sender, 1, nonce, message).sign(&keys).unwrap()
+                })
+                .collect()
+        };
+        let tampered = |relay: &TamperingRelay, values: &[Packet]| {
+            let mut peer_values = values.to_vec();
+            relay
+                .relay_values(1, &mut peer_values, values[0].round)
+                .unwrap();
+            peer_values
+        };
+        let previous = batch(6);
+        let current = batch(7);
+        let liar_value = &current[1];
+
+        // Honest values are dropped, or swapped for the value of another liar
+        assert_eq!(
+            tampered(&relay(RelayStrategy::Omit), &current),
+            vec![liar_value.clone()]
+        );
+        assert_eq!(
+            tampered(&relay(RelayStrategy::Substitute), &current),
+            vec![liar_value.clone(), liar_value.clone()]
+        );
+        assert_eq!(
+            tampered(&relay(RelayStrategy::Duplicate), &current),
+            [current.clone(), current.clone()].concat()
+        );
+
+        let mut reordered = tampered(&relay(RelayStrategy::Reorder), &current);
+        reordered.sort_by_key(|packet| packet.sender);
+        assert_eq!(reordered, current);
+
+        // Values relayed in a previous round are forwarded instead of the current ones
+        let replaying = relay(RelayStrategy::Replay);
+        assert_eq!(tampered(&replaying, &previous), previous);
+        assert_eq!(tampered(&replaying, &current), previous);
+
+        // Relays that never tamper forward values as received
+        let untouched =
+            TamperingRelay::new(RandomLiar::new(10, 1000, false), 0.0, RelayStrategy::Omit);
+        assert_eq!(tampered(&untouched, &current), current);
+    }
+}
@@ -1,41 +1,333 @@
-use anyhow::{bail, Context};
+use anyhow::{anyhow, bail, Context};
 use std::collections::HashMap;
-use std::collections::HashSet;
 use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use text_colorizer::Colorize;
 use tokio::io;
-use tokio::net::TcpStream;
 use tokio::spawn;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, timeout_at, Instant};
 
 use crate::agent_config::AgentConfig;
+use crate::connection::{Connection, ConnectionPool, NetworkOptions};
+use crate::gossip::GossipStore;
+use crate::inference::{Confidence, Vote};
 use crate::keys::Keys;
 use crate::message::Message;
-use crate::network_utils::*;
-use crate::packet::Packet;
+use crate::packet::{Packet, CLIENT_ID, FRESHNESS_WINDOW};
+use crate::suspects::{EquivocationProof, RejectionReason, SuspectList, TamperEvidence};
+
+/// The default time, in milliseconds, that the client waits for a single agent to reply.
+pub const DEFAULT_AGENT_TIMEOUT_MS: u64 = 2000;
+/// The default time, in milliseconds, allowed for a whole round to complete.
+pub const DEFAULT_ROUND_TIMEOUT_MS: u64 = 10000;
+/// The default number of times a failed request to an agent is retried.
+pub const DEFAULT_RETRIES: u32 = 2;
+/// The default delay, in milliseconds, before the first retry of a failed request.
+pub const DEFAULT_BACKOFF_MS: u64 = 100;
+/// The default number of hops a `MsgFetchValues` may travel through relays in an expert round.
+pub const DEFAULT_RELAY_TTL: u8 = 3;
+
+/// Represents the timeouts and the retry policy applied when querying agents during a round.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundPolicy {
+    /// The time allowed for a single attempt to connect to and get a reply from an agent.
+    agent_timeout: Duration,
+    /// The time allowed for the whole round. Agents still pending afterwards are abandoned.
+    round_timeout: Duration,
+    /// The number of times a request that failed to connect, failed to read or timed out is retried.
+    retries: u32,
+    /// The delay before the first retry. The delay doubles on every subsequent retry.
+    backoff: Duration,
+}
+
+impl Default for RoundPolicy {
+    fn default() -> Self {
+        Self::from_millis(
+            DEFAULT_AGENT_TIMEOUT_MS,
+            DEFAULT_ROUND_TIMEOUT_MS,
+            DEFAULT_RETRIES,
+            DEFAULT_BACKOFF_MS,
+        )
+    }
+}
+
+impl RoundPolicy {
+    /// Returns a new instance of `RoundPolicy` from timeouts and a backoff expressed in milliseconds.
+    pub fn from_millis(
+        agent_timeout_ms: u64,
+        round_timeout_ms: u64,
+        retries: u32,
+        backoff_ms: u64,
+    ) -> Self {
+        RoundPolicy {
+            agent_timeout: Duration::from_millis(agent_timeout_ms),
+            round_timeout: Duration::from_millis(round_timeout_ms),
+            retries,
+            backoff: Duration::from_millis(backoff_ms),
+        }
+    }
+
+    /// Returns the delay to wait before retrying a request that has already failed `attempt` + 1
+    /// times, i.e, `backoff` * 2^`attempt`.
+    fn get_backoff(&self, attempt: u32) -> Duration {
+        match 2u32.checked_pow(attempt) {
+            Some(factor) => self.backoff.saturating_mul(factor),
+            None => Duration::MAX,
+        }
+    }
+}
+
+/// Represents the ways in which querying a single agent during a round can fail.
+#[derive(Debug)]
+enum QueryError {
+    /// The agent could not be reached, or the connection failed before a reply was received.
+    Unreachable(anyhow::Error),
+    /// The agent did not reply before the per-agent timeout expired on any attempt.
+    TimedOut,
+    /// The agent replied with bytes that could not be decoded into a packet.
+    Undecodable(anyhow::Error),
+    /// The request failed for any other reason, e.g, the agent's reply was invalid.
+    Failed(anyhow::Error),
+}
+
+/// The values extracted from a `MsgFwdValues` or a `MsgSendValues` relayed to the client, along
+/// with evidence of every forwarded packet that had to be rejected.
+#[derive(Debug, Default)]
+struct RelayedValues {
+    /// The authenticated `MsgSendValue` packets, as signed by the agents they belong to.
+    values: Vec<Packet>,
+    tampering: Vec<TamperEvidence>,
+}
+
+impl RelayedValues {
+    /// Returns the evidence against the relay `relay_id`, whose reply could not be decoded.
+    fn undecodable(relay_id: usize) -> Self {
+        RelayedValues {
+            values: Vec::new(),
+            tampering: vec![TamperEvidence::new(
+                relay_id,
+                &[relay_id],
+                RejectionReason::Undecodable,
+            )],
+        }
+    }
+
+    /// Records that the `forwarded` packet, relayed through the agents in `custody_chain`, was
+    /// rejected for `reason`.
+    fn reject(&mut self, forwarded: &Packet, custody_chain: &[usize], reason: RejectionReason) {
+        self.tampering
+            .push(TamperEvidence::new(forwarded.sender, custody_chain, reason));
+    }
+}
+
+/// Collects the values signed by every agent during a round. The first value received from an
+/// agent is kept, and any different value the agent signed for the same round is kept along with
+/// it as proof that the agent equivocated.
+#[derive(Debug, Default)]
+struct SignedValues {
+    /// The first authenticated value received from each agent and the packet carrying it,
+    /// indexed by agent ID.
+    values: HashMap<usize, (u64, Packet)>,
+    /// A proof for each agent caught signing two different values, indexed by agent ID.
+    equivocations: HashMap<usize, EquivocationProof>,
+}
+
+impl SignedValues {
+    /// Adds the value carried by `packet`, an authenticated `MsgSendValue`. A value that differs
+    /// from the one already received from the same agent is recorded as an equivocation.
+    fn insert(&mut self, packet: &Packet) {
+        let Ok(Message::MsgSendValue { value, .. }) = Message::deserialize_message(&packet.message)
+        else {
+            return;
+        };
+
+        match self.values.get(&packet.sender) {
+            None => {
+                self.values.insert(packet.sender, (value, packet.clone()));
+            }
+            Some((_, first)) if !self.equivocations.contains_key(&packet.sender) => {
+                if let Some(proof) = EquivocationProof::new(first, packet) {
+                    self.equivocations.insert(packet.sender, proof);
+                }
+            }
+            Some(_) => (),
+        }
+    }
+
+    /// Returns whether a value was received from the agent `agent_id`.
+    fn contains(&self, agent_id: usize) -> bool {
+        self.values.contains_key(&agent_id)
+    }
+
+    /// Returns the votes of the agents that did not equivocate, ordered by agent ID. Equivocating
+    /// agents have no vote.
+    fn get_votes(&self) -> Vec<Vote> {
+        let mut votes: Vec<Vote> = self
+            .values
+            .iter()
+            .filter(|(agent_id, _)| !self.equivocations.contains_key(agent_id))
+            .map(|(&agent_id, &(value, _))| Vote::new(agent_id, value))
+            .collect();
+        votes.sort_by_key(Vote::get_agent_id);
+        votes
+    }
+
+    /// Returns a proof for every agent that equivocated, ordered by agent ID.
+    fn get_equivocations(&self) -> Vec<EquivocationProof> {
+        let mut equivocations: Vec<EquivocationProof> =
+            self.equivocations.values().cloned().collect();
+        equivocations.sort_by_key(|proof| proof.get_agent_id());
+        equivocations
+    }
+}
+
+/// Collects the values relayed to the client during an expert round, along with the evidence of
+/// every relayed packet that was rejected.
+#[derive(Debug, Default)]
+struct CollectedValues {
+    values: SignedValues,
+    tampering: Vec<TamperEvidence>,
+}
+
+impl CollectedValues {
+    /// Adds the values and the evidence of tampering of every reply in `fetched`.
+    fn add(&mut self, fetched: Vec<RelayedValues>) {
+        for relayed in fetched {
+            relayed
+                .values
+                .iter()
+                .for_each(|value| self.values.insert(value));
+            Client::merge_evidence(&mut self.tampering, relayed.tampering);
+        }
+    }
+
+    /// Returns a `RoundReport` of the values collected, which records its evidence in `suspects`.
+    /// The evidence is added to the last round recorded if `amend` is set.
+    fn into_report(self, suspects: &SuspectList, amend: bool) -> RoundReport {
+        let equivocations = self.values.get_equivocations();
+        if amend {
+            suspects.amend_last_round(&self.tampering, &equivocations);
+        } else {
+            suspects.record_round(&self.tampering, &equivocations);
+        }
+
+        RoundReport {
+            values: self.values.get_votes(),
+            tampering: self.tampering,
+            equivocations,
+        }
+    }
+}
+
+/// Represents the outcome of an expert round, i.e, the values received from agents that did not
+/// equivocate, the evidence of every relayed packet that was rejected and a proof for every agent
+/// that signed conflicting values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoundReport {
+    values: Vec<Vote>,
+    tampering: Vec<TamperEvidence>,
+    equivocations: Vec<EquivocationProof>,
+}
+
+impl RoundReport {
+    pub fn get_values(&self) -> &Vec<Vote> {
+        &self.values
+    }
+
+    pub fn get_tampering(&self) -> &Vec<TamperEvidence> {
+        &self.tampering
+    }
+
+    pub fn get_equivocations(&self) -> &Vec<EquivocationProof> {
+        &self.equivocations
+    }
+
+    /// Adds the values of `other` from agents that have no vote yet, along with the evidence
+    /// collected in `other`. Returns the number of votes added.
+    pub fn merge(&mut self, other: RoundReport) -> usize {
+        let num_votes = self.values.len();
+        for vote in other.values {
+            if !self
+                .values
+                .iter()
+                .any(|known| known.get_agent_id() == vote.get_agent_id())
+            {
+                self.values.push(vote);
+            }
+        }
+        self.values.sort_by_key(Vote::get_agent_id);
+
+        Client::merge_evidence(&mut self.tampering, other.tampering);
+        for proof in other.equivocations {
+            if !self.equivocations.contains(&proof) {
+                self.equivocations.push(proof);
+            }
+        }
+        self.values.len() - num_votes
+    }
+}
 
 /// Represents a game client.
 ///
 /// Clients are responsible for communicating with deployed agents
 /// and querying for their individual values to determine the network value.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, Clone)]
 pub struct Client {
     /// The client's Ed25519 key pair. Used for message authentication.
     keys: Keys,
     /// A vector containing information that allows the client to communicate with agents.
     peers: Vec<AgentConfig>,
+    /// Persistent connections to agents, shared by every clone of the client and reused
+    /// across rounds.
+    connections: ConnectionPool,
+    /// The number of requests the client has sent to agents, shared by every clone of the client.
+    messages_sent: Arc<AtomicUsize>,
+    /// The evidence of tampering collected over every expert round, shared by every clone of the
+    /// client.
+    suspects: SuspectList,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Client {
-    /// Returns a new instance of `Client` with a key pair for message signing
-    /// and an empty `peers` Vec.
+    /// Returns a new instance of `Client` with a key pair for message signing,
+    /// an empty `peers` Vec and an empty connection pool using plaintext connections.
     pub fn new() -> Self {
+        let keys = Keys::new_key_pair();
+        let connections = ConnectionPool::new(NetworkOptions::default(), keys.clone());
         Client {
-            keys: Keys::new_key_pair(),
+            keys,
             peers: Vec::new(),
+            connections,
+            messages_sent: Arc::new(AtomicUsize::new(0)),
+            suspects: SuspectList::default(),
         }
     }
 
+    /// Sets the limits enforced on frames exchanged with agents, whether connections to agents
+    /// are encrypted and the codec requested from agents. Replaces the client's connection pool,
+    /// so that connections opened from now on use the new settings.
+    pub fn configure_connections(&mut self, network: NetworkOptions) {
+        self.connections = ConnectionPool::new(network, self.keys.clone());
+    }
+
+    /// Returns the number of requests the client has sent to agents, including retries.
+    pub fn get_messages_sent(&self) -> usize {
+        self.messages_sent.load(Ordering::Relaxed)
+    }
+
+    /// Returns the evidence of tampering collected by the client over every expert round.
+    pub fn get_suspects(&self) -> &SuspectList {
+        &self.suspects
+    }
+
     /// Returns the client's keypair for message signing.
     pub fn get_keys(&self) -> &Keys {
         &self.keys
@@ -56,7 +348,7 @@ impl Client {
     /// Receives a string slice containing the data read from `agents.config`
     /// and attempts to deserialize and store it in Client.peers
     pub fn store_agent_config(&mut self, agent_config: &str) -> Result<(), serde_json::Error> {
-        self.peers = serde_json::from_str(&agent_config)?;
+        self.peers = serde_json::from_str(agent_config)?;
         Ok(())
     }
 
@@ -67,21 +359,63 @@ impl Client {
         Ok(())
     }
 
-    /// Receives a `MsgSendValue` from an agent and verifies if it has been correctly signed by the
-    /// agent to whom the client has sent a `MsgQueryValue`.
+    /// Returns a new random nonce to be used by the client throughout a single round.
+    fn gen_round_nonce() -> u64 {
+        rand::random()
+    }
+
+    /// Receives a packet containing a `MsgSendValue` and verifies if it has been correctly signed
+    /// by the agent `sender`, whose public key is `public_key`, and addressed to `recipient`. The
+    /// `nonce` carried by the message and the round of the packet must be equal to `round_nonce`,
+    /// and the packet must be fresh, otherwise the reply is considered a replay.
     fn handle_msg_send_value(
-        message_bytes: &[u8],
-        signature: &Option<Vec<u8>>,
+        packet: &Packet,
+        sender: usize,
         public_key: &str,
+        recipient: usize,
+        nonce: u64,
+        round_nonce: u64,
     ) -> anyhow::Result<()> {
-        if let Some(signature) = signature {
-            Keys::verify(message_bytes, signature, public_key)?;
-        } else {
+        if nonce != round_nonce || packet.round != round_nonce {
             bail!(
-                "[!] error: MsgSendValue requires a signature, but the received packet contains None\n"
+                "[!] error: MsgSendValue nonce does not match the current round; possible replay\n"
             );
         }
-        Ok(())
+
+        if packet.sender != sender {
+            bail!(
+                "[!] error: MsgSendValue was expected from Agent {}, but was sent by Agent {}\n",
+                sender,
+                packet.sender
+            );
+        }
+
+        packet.check_context(recipient, FRESHNESS_WINDOW)?;
+        packet.verify(public_key)
+    }
+
+    /// Verifies that `packet` was sent by the agent `peer` to `recipient` during the round
+    /// `round_nonce`, that it is fresh and that it was signed by `peer`.
+    fn verify_agent_packet(
+        packet: &Packet,
+        peer: &AgentConfig,
+        recipient: usize,
+        round_nonce: u64,
+    ) -> anyhow::Result<()> {
+        if packet.sender != peer.get_id() || packet.round != round_nonce {
+            bail!(
+                "[!] error: reply does not belong to the current round of Agent {}; possible replay\n",
+                peer.get_id()
+            );
+        }
+        packet.check_context(recipient, FRESHNESS_WINDOW)?;
+        packet.verify(peer.get_public_key())
+    }
+
+    /// Receives `agent_id` and searches `Client.peers` for an agent with ID equal to `agent_id`.
+    /// If found, returns the agent's configuration, otherwise returns None.
+    fn get_agent_config(&self, agent_id: usize) -> Option<&AgentConfig> {
+        self.peers.iter().find(|agent| agent.get_id() == agent_id)
     }
 
     /// Receives `agent_id` and searches `Client.peers` for an agent with ID equal to `agent_id`.
@@ -93,45 +427,18 @@ impl Client {
             .map(|agent| agent.get_public_key().to_string())
     }
 
-    /// Receives the values reported by the game's agents and infers the network value from them.
-    /// If multiple values are tied with the most occurrences, return all of them.
-    ///
-    /// For example, given the values below, both 2 and 8 will be returned as the network value.
-    ///     Number 2: 4 votes
-    ///     Number 5: 1 vote  
-    ///     Number 8: 4 votes
-    ///     
-    pub fn infer_network_value(agent_values: &Vec<u64>) -> Option<Vec<u64>> {
-        let mut values_count = HashMap::new();
-
-        // Count the number of occurrences of each different value returned by the agents
-        for &value in agent_values {
-            *values_count.entry(value).or_insert(0) += 1;
-        }
-
-        // Return the maximum number of occurrences out of all the values
-        let max_count = match values_count.values().max() {
-            Some(max_count) => *max_count,
-            None => return None,
-        };
-
-        // Get all the values whose occurrence is equal to the max number of occurrences.
-        // Different values may be tied with the most number of occurrences, in which case
-        // all of them will be returned as the network value.
-        let network_value = values_count
-            .into_iter()
-            .filter(|&(_, value_count)| value_count == max_count)
-            .map(|(value, _)| value)
-            .collect();
-
-        Some(network_value)
-    }
-
     /// Prints the network value inferred after playing a round of the game. Will print
     /// multiple values if there was no majority consensus on a single network value.
     pub fn print_network_value(network_value: &Option<Vec<u64>>) {
         match network_value {
             Some(network_value) => match network_value.len() {
+                // If the inference strategy could not settle on any value
+                0 => println!(
+                    "{}",
+                    "[+] Unable to determine the network value; no value satisfied the inference strategy.\n"
+                        .bold()
+                ),
+
                 // If a single value has the majority of votes
                 1 => println!(
                     "{} {}\n",
@@ -169,47 +476,161 @@ impl Client {
         }
     }
 
-    /// Queries an individual agent for its value by sending a `MsgQueryValue`. Returns the agent's
-    /// value as u64 if successful and `anyhow::Error` otherwise.
+    /// Prints the posterior probability of every value in `network_value`, as given by
+    /// `posterior`.
+    pub fn print_posterior(network_value: &[u64], posterior: &[(u64, f64)]) {
+        for value in network_value {
+            if let Some((_, probability)) = posterior.iter().find(|(other, _)| other == value) {
+                println!(
+                    "{} {}: {:.2}%",
+                    "[+] Posterior probability of".bold(),
+                    value,
+                    probability * 100.0
+                );
+            }
+        }
+        if !network_value.is_empty() {
+            println!();
+        }
+    }
+
+    /// Prints whether the network value is provably or only probably correct according to
+    /// `confidence`, along with its margin over the runner-up.
+    pub fn print_confidence(confidence: &Confidence) {
+        if confidence.is_guaranteed() {
+            println!(
+                "{} {} matching signed votes exceed the fault bound f = {}.",
+                "[+] Provably correct:".bold(),
+                confidence.get_support(),
+                confidence.get_fault_bound()
+            );
+        } else {
+            println!(
+                "{} {} matching signed votes do not exceed the fault bound f = {}; {} more honest \
+                replies would be needed.",
+                "[+] Probably correct:".bold(),
+                confidence.get_support(),
+                confidence.get_fault_bound(),
+                confidence.get_votes_needed()
+            );
+        }
+        println!(
+            "{} {} votes ({} against {}).\n",
+            "[+] Margin over the runner-up:".bold(),
+            confidence.get_margin(),
+            confidence.get_support(),
+            confidence.get_runner_up()
+        );
+    }
+
+    /// Returns a pooled connection to `peer`, opening a new one if necessary.
+    async fn get_connection(&self, peer: &AgentConfig) -> anyhow::Result<Arc<Connection>> {
+        self.connections
+            .get(peer.get_address(), peer.get_port(), peer.get_public_key())
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "[!] error: failed to connect to (Agent ID: {} - {}:{}) - {}\n",
+                    peer.get_id(),
+                    peer.get_address(),
+                    peer.get_port(),
+                    e
+                )
+            })
+    }
+
+    /// Sends `packet` to `peer` over a pooled connection and waits for the reply. Every attempt must
+    /// complete before the per-agent timeout in `policy`. Attempts that fail to connect, fail to
+    /// read the reply or time out are retried up to `policy.retries` times, waiting for an
+    /// exponentially increasing delay between attempts. Packets are encoded with the codec
+    /// negotiated for the connection in use. Returns the reply packet if successful.
+    async fn request_with_retry(
+        &self,
+        peer: &AgentConfig,
+        packet: &Packet,
+        policy: &RoundPolicy,
+    ) -> Result<Packet, QueryError> {
+        let mut attempt = 0;
+        loop {
+            let result = timeout(policy.agent_timeout, async {
+                let connection = self.get_connection(peer).await?;
+                let codec = connection.get_codec();
+                self.messages_sent.fetch_add(1, Ordering::Relaxed);
+                let reply = connection.request(&packet.encode(codec)?).await?;
+                Ok::<_, anyhow::Error>((reply, codec))
+            })
+            .await;
+
+            let error = match result {
+                Ok(Ok((reply, codec))) => {
+                    return Packet::unpack(&reply, codec)
+                        .context("[!] error: unable to decode packet\n")
+                        .map_err(QueryError::Undecodable)
+                }
+                Ok(Err(e)) => {
+                    // The connection may no longer be usable, open a new one on the next attempt
+                    self.connections.remove(peer.get_address(), peer.get_port());
+                    QueryError::Unreachable(e)
+                }
+                // Late replies are discarded by the connection, which remains usable
+                Err(_) => QueryError::TimedOut,
+            };
+
+            if attempt >= policy.retries {
+                return Err(error);
+            }
+            sleep(policy.get_backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    /// Queries an individual agent `peer` for its value by sending a `MsgQueryValue` carrying the
+    /// round's `nonce`, according to `policy`. Returns the agent's value as u64 if successful and
+    /// `QueryError` otherwise.
     async fn send_msg_query_value(
         client: Arc<Self>,
-        socket: &mut TcpStream,
-        agent_pubkey: &str,
-    ) -> anyhow::Result<u64> {
-        let message = Message::build_msg_query_value()
-            .context("[!] error: failed to build MsgQueryValue\n")?;
-
-        // Compute the signature of the serialized message
+        peer: &AgentConfig,
+        round_nonce: u64,
+        policy: &RoundPolicy,
+    ) -> Result<u64, QueryError> {
+        let message = Message::build_msg_query_value(round_nonce)
+            .context("[!] error: failed to build MsgQueryValue\n")
+            .map_err(QueryError::Failed)?;
+
+        // Build a packet addressed to the agent and sign the message along with its context
         // NOTE: For messages composed by large amounts of data, signing the whole message incurs
         // a significant overhead. Ideally, the hash of  the message should be signed instead.
         // Here, given the small sizes of messages, we sign the whole message for simplicity's sake.
-        let message_signature = client.keys.sign(&message)?;
-
-        // Build a packet with the message and message signature
-        let packet = Packet::build_packet(message, Some(message_signature))
-            .context("[!] error: failed to build packet\n")?;
-
-        match send_packet(&packet, socket).await {
-            Ok(()) => (),
-            Err(e) => bail!("[!] error: unable to reach agent - {}", e),
-        }
+        let packet = Packet::new(CLIENT_ID, peer.get_id(), round_nonce, message)
+            .sign(&client.keys)
+            .map_err(QueryError::Failed)?;
 
-        let reply = recv_packet(socket).await?;
-        let reply_packet = Packet::unpack(&reply)?;
+        let reply_packet = client.request_with_retry(peer, &packet, policy).await?;
 
         match Message::deserialize_message(&reply_packet.message) {
-            Ok(Message::MsgSendValue { value, .. }) => {
-                match Self::handle_msg_send_value(
-                    &reply_packet.message,
-                    &reply_packet.msg_sig,
-                    agent_pubkey,
-                ) {
-                    Ok(()) => Ok(value),
-                    Err(e) => Err(e),
-                }
-            }
-            Ok(other) => bail!("[!] error: expected MsgSendValue, received {:?}\n", other),
-            Err(e) => bail!("[!] error: unable to decode message - {}\n", e),
+            Ok(Message::MsgSendValue { value, nonce, .. }) => Self::handle_msg_send_value(
+                &reply_packet,
+                peer.get_id(),
+                peer.get_public_key(),
+                CLIENT_ID,
+                nonce,
+                round_nonce,
+            )
+            .map(|()| value)
+            .map_err(QueryError::Failed),
+            Ok(Message::MsgQueryRefused { reason, .. }) => Err(QueryError::Failed(anyhow!(
+                "[!] error: Agent {} refused the query - {}\n",
+                peer.get_id(),
+                reason
+            ))),
+            Ok(other) => Err(QueryError::Failed(anyhow!(
+                "[!] error: expected MsgSendValue, received {:?}\n",
+                other
+            ))),
+            Err(e) => Err(QueryError::Failed(anyhow!(
+                "[!] error: unable to decode message - {}\n",
+                e
+            ))),
         }
     }
 
@@ -217,234 +638,582 @@ impl Client {
     async fn send_msg_kill_agent(
         client: &Self,
         agent_id: usize,
-        socket: &mut TcpStream,
+        connection: &Connection,
     ) -> anyhow::Result<()> {
         let message = Message::build_msg_kill_agent(agent_id)
             .context("[!] error: failed to build MsgKillAgent\n")?;
 
-        let message_signature = client.keys.sign(&message)?;
-
-        let packet = Packet::build_packet(message, Some(message_signature))
+        let packet = Packet::new(CLIENT_ID, agent_id, 0, message)
+            .sign(&client.keys)?
+            .encode(connection.get_codec())
             .context("[!] error: failed to build packet\n")?;
 
-        match send_packet(&packet, socket).await {
+        match connection.send(&packet).await {
             Ok(()) => Ok(()),
             Err(e) => bail!("[!] error: unable to reach agent {} - {}", agent_id, e),
         }
     }
 
-    /// Receives and processes the contents of `Message::MsgFwdValues`. Returns a `Vec<Message>`
-    /// containing all the valid/authenticated messages extracted from `MsgFwdValues` and
-    /// `anyhow::Error` otherwise.
+    /// Receives and processes the contents of `Message::MsgFwdValues`, or `Message::MsgSendValues`,
+    /// sent in `packet` by the agent `peer`. Returns the valid/authenticated messages extracted
+    /// from the relayed values, along with evidence of any tampering found along the way, and
+    /// `anyhow::Error` otherwise. Forwarded values whose nonce differs from `round_nonce`, or that
+    /// were not addressed to `peer`, are discarded as replays. Values relayed through further
+    /// agents are accepted for up to `ttl` hops, see `Client::extract_relayed_values`.
     fn handle_msg_fwd_values(
         &self,
-        message_bytes: &[u8],
-        signature: &Option<Vec<u8>>,
-        forwarded_replies: &Vec<Packet>,
-        agent_pubkey: &str,
-    ) -> anyhow::Result<Vec<Message>> {
-        if let Some(signature) = signature {
-            Keys::verify(message_bytes, signature, agent_pubkey)?;
-        } else {
-            bail!(
-                "[!] error: MsgFwdValues requires a signature, but the received packet contains None\n"
-            );
-        }
+        packet: &Packet,
+        forwarded_replies: &[Packet],
+        peer: &AgentConfig,
+        round_nonce: u64,
+        ttl: u8,
+    ) -> anyhow::Result<RelayedValues> {
+        Self::verify_agent_packet(packet, peer, CLIENT_ID, round_nonce)?;
+
+        let mut relayed = RelayedValues::default();
+        self.extract_relayed_values(
+            forwarded_replies,
+            &[peer.get_id()],
+            round_nonce,
+            ttl,
+            &mut relayed,
+        );
+        Ok(relayed)
+    }
 
-        let mut received_messages: Vec<Message> = Vec::new();
+    /// Extracts every authenticated `MsgSendValue` from `forwarded_replies` into `relayed`. The
+    /// replies were collected by the last agent of `custody_chain`, which lists every relay that
+    /// signed for them, starting with the agent queried by the client. A forwarded `MsgFwdValues`
+    /// carries the values collected by a neighbour on behalf of the relay; while `ttl` allows for
+    /// another hop, it is accepted iff it was signed by the neighbour and addressed to the relay
+    /// during the current round, and its values are then extracted with the neighbour appended to
+    /// the chain.
+    ///
+    /// Honest relays only sign for replies of the expected type, sent by their known peers during
+    /// the current round, whose signature they verified, and forward each of them once. A forwarded
+    /// packet that has a bad signature, claims to come from an unknown agent, carries an unexpected
+    /// message, was signed for another round or recipient, or is repeated was therefore altered by
+    /// the last relay of the chain, and is recorded in `relayed` as `TamperEvidence`. Forwarded
+    /// packets are decoded along with the reply carrying them, so a relay that sends undecodable
+    /// bytes is held responsible by the caller, for the whole reply.
+    fn extract_relayed_values(
+        &self,
+        forwarded_replies: &[Packet],
+        custody_chain: &[usize],
+        round_nonce: u64,
+        ttl: u8,
+        relayed: &mut RelayedValues,
+    ) {
+        let Some(&relay_id) = custody_chain.last() else {
+            return;
+        };
+
+        let mut seen: Vec<&Packet> = Vec::new();
+        for forwarded in forwarded_replies {
+            if seen.contains(&forwarded) {
+                relayed.reject(forwarded, custody_chain, RejectionReason::Duplicate);
+                continue;
+            }
+            seen.push(forwarded);
+
+            let Some(sender) = self.get_agent_config(forwarded.sender) else {
+                relayed.reject(forwarded, custody_chain, RejectionReason::UnknownAgent);
+                continue;
+            };
+            if forwarded.verify(sender.get_public_key()).is_err() {
+                relayed.reject(forwarded, custody_chain, RejectionReason::BadSignature);
+                continue;
+            }
 
-        for packet in forwarded_replies {
-            match Message::deserialize_message(&packet.message) {
-                Ok(Message::MsgSendValue { agent_id, value }) => {
+            match Message::deserialize_message(&forwarded.message) {
+                Ok(Message::MsgSendValue {
+                    agent_id, nonce, ..
+                }) => {
                     // Retrieve the public key of the agent who sent this `MsgSendValue`
-                    if let Some(agent_pubkey) = self.get_agent_pubkey(agent_id) {
-                        match Self::handle_msg_send_value(
-                            &packet.message,
-                            &packet.msg_sig,
-                            &agent_pubkey,
-                        ) {
-                            // The received MsgSendValue was authenticated sucessfully
-                            Ok(()) => {
-                                received_messages.push(Message::MsgSendValue { agent_id, value })
-                            }
-                            // If the signature of the MsgSendValue is invalid, ignore the value
-                            Err(_) => (),
-                        }
+                    let authenticated = self.get_agent_pubkey(agent_id).is_some_and(|pubkey| {
+                        Self::handle_msg_send_value(
+                            forwarded,
+                            agent_id,
+                            &pubkey,
+                            relay_id,
+                            nonce,
+                            round_nonce,
+                        )
+                        .is_ok()
+                    });
+                    if authenticated {
+                        // The received MsgSendValue was authenticated sucessfully
+                        relayed.values.push(forwarded.clone())
+                    } else {
+                        // The signature is genuine, so the value was lifted from another context
+                        relayed.reject(forwarded, custody_chain, RejectionReason::Replayed)
+                    }
+                }
+                // Values fetched through a neighbour of the relay, signed by that neighbour
+                Ok(Message::MsgFwdValues {
+                    agent_id,
+                    peer_values,
+                }) if ttl > 1 && agent_id == forwarded.sender => {
+                    if let Ok(()) =
+                        Self::verify_agent_packet(forwarded, sender, relay_id, round_nonce)
+                    {
+                        let mut chain = custody_chain.to_vec();
+                        chain.push(agent_id);
+                        self.extract_relayed_values(
+                            &peer_values,
+                            &chain,
+                            round_nonce,
+                            ttl - 1,
+                            relayed,
+                        );
+                    } else {
+                        relayed.reject(forwarded, custody_chain, RejectionReason::Replayed)
                     }
                 }
-                // If the forwarded message is not a MsgSendValue, ignore it
-                Ok(_) => (),
-                // The message could not be deserialized
-                // NOTE: It would be an improvement to log this and other similar types of errors
-                Err(_) => (),
+                // Relays only forward values and, within the hop limit, their neighbours' replies.
+                // Undecodable bytes fail the whole reply, before its packets are extracted.
+                _ => relayed.reject(forwarded, custody_chain, RejectionReason::WrongMessageType),
             }
         }
-        Ok(received_messages)
     }
 
-    /// Builds a `MsgFetchValues`, sends it to the agent at the other end of the `socket`
-    /// TcpStream and expects a `MsgFwdValues` as a reply. Returns a `Vec<Message>` containing the
-    /// messages forwarded by the agent if successful and `anyhow::Error` otherwise.
+    /// Builds a `MsgFetchValues`, sends it to the agent `peer` according to `policy` and expects a
+    /// `MsgFwdValues` as a reply. Peers the agent cannot reach are relayed through its neighbours
+    /// for up to `ttl` hops. Returns the messages forwarded by the agent, along with evidence of
+    /// any tampering, if successful and `QueryError` otherwise.
     async fn send_msg_fetch_values(
         client: Arc<Self>,
-        socket: &mut TcpStream,
-        agent_id: usize,
-        agent_pubkey: &str,
-    ) -> anyhow::Result<Vec<Message>> {
-        let message = Message::build_msg_fetch_values(agent_id, &client.peers)
-            .context("[!] error: failed to build MsgFetchValues\n")?;
+        peer: &AgentConfig,
+        round_nonce: u64,
+        ttl: u8,
+        policy: &RoundPolicy,
+    ) -> Result<RelayedValues, QueryError> {
+        let message =
+            Message::build_msg_fetch_values(peer.get_id(), &client.peers, round_nonce, ttl, &[])
+                .context("[!] error: failed to build MsgFetchValues\n")
+                .map_err(QueryError::Failed)?;
+
+        let packet = Packet::new(CLIENT_ID, peer.get_id(), round_nonce, message)
+            .sign(&client.keys)
+            .map_err(QueryError::Failed)?;
+
+        let reply_packet = match client.request_with_retry(peer, &packet, policy).await {
+            Ok(reply_packet) => reply_packet,
+            // Relays sign every reply they send, so undecodable bytes are evidence against them
+            Err(QueryError::Undecodable(_)) => {
+                return Ok(RelayedValues::undecodable(peer.get_id()))
+            }
+            Err(e) => return Err(e),
+        };
 
-        let message_signature = client.keys.sign(&message)?;
+        match Message::deserialize_message(&reply_packet.message) {
+            Ok(Message::MsgFwdValues { peer_values, .. }) => client
+                .handle_msg_fwd_values(&reply_packet, &peer_values, peer, round_nonce, ttl)
+                .map_err(QueryError::Failed),
+            Ok(other) => Err(QueryError::Failed(anyhow!(
+                "[!] error: expected MsgFwdValues, received {:?}\n",
+                other
+            ))),
+            Err(e) => Err(QueryError::Failed(anyhow!(
+                "[!] error: unable to decode message - {}\n",
+                e
+            ))),
+        }
+    }
 
-        let packet = Packet::build_packet(message, Some(message_signature))
-            .context("[!] error: failed to build packet\n")?;
+    /// Builds a `MsgQueryValues` requesting the values of the agents in `agent_ids`, sends it to the
+    /// relay agent `peer` according to `policy` and expects a `MsgSendValues` as a reply. Returns the
+    /// values relayed by the agent, along with evidence of any tampering, if successful and
+    /// `QueryError` otherwise.
+    async fn send_msg_query_values(
+        client: Arc<Self>,
+        peer: &AgentConfig,
+        agent_ids: &[usize],
+        round_nonce: u64,
+        policy: &RoundPolicy,
+    ) -> Result<RelayedValues, QueryError> {
+        let message = Message::build_msg_query_values(agent_ids, round_nonce)
+            .context("[!] error: failed to build MsgQueryValues\n")
+            .map_err(QueryError::Failed)?;
+
+        let packet = Packet::new(CLIENT_ID, peer.get_id(), round_nonce, message)
+            .sign(&client.keys)
+            .map_err(QueryError::Failed)?;
+
+        let reply_packet = match client.request_with_retry(peer, &packet, policy).await {
+            Ok(reply_packet) => reply_packet,
+            // Relays sign every reply they send, so undecodable bytes are evidence against them
+            Err(QueryError::Undecodable(_)) => {
+                return Ok(RelayedValues::undecodable(peer.get_id()))
+            }
+            Err(e) => return Err(e),
+        };
 
-        match send_packet(&packet, socket).await {
-            Ok(()) => (),
-            Err(e) => bail!("[!] error: unable to reach agent {} - {}", agent_id, e),
+        match Message::deserialize_message(&reply_packet.message) {
+            Ok(Message::MsgSendValues { values, .. }) => client
+                .handle_msg_fwd_values(&reply_packet, &values, peer, round_nonce, 1)
+                .map_err(QueryError::Failed),
+            Ok(other) => Err(QueryError::Failed(anyhow!(
+                "[!] error: expected MsgSendValues, received {:?}\n",
+                other
+            ))),
+            Err(e) => Err(QueryError::Failed(anyhow!(
+                "[!] error: unable to decode message - {}\n",
+                e
+            ))),
         }
+    }
 
-        let reply = recv_packet(socket).await?;
-        let reply_packet = Packet::unpack(&reply)?;
+    /// Receives and processes the contents of a `Message::MsgSendValues` sent in `packet` by the
+    /// agent `peer` as a reply to a `MsgQueryGossip`. Gossiped values are addressed to the client
+    /// and do not belong to any round, so each one is verified against its own sequence number,
+    /// signature and freshness. Returns the authenticated `MsgSendValue` packets and
+    /// `anyhow::Error` if the reply itself is invalid.
+    fn handle_gossip_values(
+        &self,
+        packet: &Packet,
+        values: &[Packet],
+        peer: &AgentConfig,
+        round_nonce: u64,
+    ) -> anyhow::Result<Vec<Packet>> {
+        Self::verify_agent_packet(packet, peer, CLIENT_ID, round_nonce)?;
+
+        let mut received_values: Vec<Packet> = Vec::new();
+        for gossiped in values {
+            if let Some(agent_pubkey) = self.get_agent_pubkey(gossiped.sender) {
+                if let Ok(()) = GossipStore::verify_value(gossiped, &agent_pubkey) {
+                    received_values.push(gossiped.clone())
+                }
+            }
+        }
+        Ok(received_values)
+    }
+
+    /// Builds a `MsgQueryGossip`, sends it to the agent `peer` according to `policy` and expects a
+    /// `MsgSendValues` holding the values the agent gathered through gossip as a reply. Returns the
+    /// authenticated `MsgSendValue` packets if successful and `QueryError` otherwise.
+    async fn send_msg_query_gossip(
+        client: Arc<Self>,
+        peer: &AgentConfig,
+        round_nonce: u64,
+        policy: &RoundPolicy,
+    ) -> Result<Vec<Packet>, QueryError> {
+        let message = Message::build_msg_query_gossip(round_nonce)
+            .context("[!] error: failed to build MsgQueryGossip\n")
+            .map_err(QueryError::Failed)?;
+
+        let packet = Packet::new(CLIENT_ID, peer.get_id(), round_nonce, message)
+            .sign(&client.keys)
+            .map_err(QueryError::Failed)?;
+
+        let reply_packet = client.request_with_retry(peer, &packet, policy).await?;
 
         match Message::deserialize_message(&reply_packet.message) {
-            Ok(Message::MsgFwdValues { peer_values, .. }) => client.handle_msg_fwd_values(
-                &reply_packet.message,
-                &reply_packet.msg_sig,
-                &peer_values,
-                agent_pubkey,
-            ),
-            Ok(other) => bail!("[!] error: expected MsgFwdValues, received {:?}\n", other),
-            Err(e) => bail!("[!] error: unable to decode message - {}\n", e),
+            Ok(Message::MsgSendValues { values, .. }) => client
+                .handle_gossip_values(&reply_packet, &values, peer, round_nonce)
+                .map_err(QueryError::Failed),
+            Ok(Message::MsgQueryRefused { reason, .. }) => Err(QueryError::Failed(anyhow!(
+                "[!] error: Agent {} refused the query - {}\n",
+                peer.get_id(),
+                reason
+            ))),
+            Ok(other) => Err(QueryError::Failed(anyhow!(
+                "[!] error: expected MsgSendValues, received {:?}\n",
+                other
+            ))),
+            Err(e) => Err(QueryError::Failed(anyhow!(
+                "[!] error: unable to decode message - {}\n",
+                e
+            ))),
         }
     }
 
+    /// Waits for the tasks in `handles`, each tagged with the ID of the agent it queries, until
+    /// `deadline`. Tasks that are still running once the deadline expires are aborted. Failures are
+    /// printed as they are collected. Returns the results of the successful tasks along with the
+    /// IDs of the agents that timed out.
+    async fn collect_replies<T>(
+        handles: Vec<(usize, JoinHandle<Result<T, QueryError>>)>,
+        deadline: Instant,
+        policy: &RoundPolicy,
+    ) -> (Vec<T>, Vec<usize>) {
+        let mut replies = Vec::new();
+        let mut timed_out = Vec::new();
+
+        for (agent_id, mut handle) in handles {
+            match timeout_at(deadline, &mut handle).await {
+                Ok(Ok(Ok(reply))) => replies.push(reply),
+                Ok(Ok(Err(QueryError::TimedOut))) => {
+                    println!(
+                        "[!] error: agent {} timed out after {} attempt(s) of {} ms",
+                        agent_id,
+                        policy.retries + 1,
+                        policy.agent_timeout.as_millis()
+                    );
+                    timed_out.push(agent_id);
+                }
+                Ok(Ok(Err(QueryError::Unreachable(e)))) => {
+                    println!("[!] error: unable to reach agent {} - {}", agent_id, e)
+                }
+                Ok(Ok(Err(QueryError::Failed(e) | QueryError::Undecodable(e)))) => {
+                    println!("{}", e)
+                }
+                Ok(Err(e)) => println!("[!] error: task panicked - {}\n", e),
+                Err(_) => {
+                    handle.abort();
+                    println!(
+                        "[!] error: agent {} did not reply before the round deadline of {} ms",
+                        agent_id,
+                        policy.round_timeout.as_millis()
+                    );
+                    timed_out.push(agent_id);
+                }
+            }
+        }
+
+        if !timed_out.is_empty() {
+            let agent_ids: Vec<String> = timed_out.iter().map(|id| id.to_string()).collect();
+            println!(
+                "\n{} {}\n",
+                "[+] The following agents timed out:".bold(),
+                agent_ids.join(", ")
+            );
+        }
+
+        (replies, timed_out)
+    }
+
     /// Plays a standard round of the game. The game's client connects to the agents loaded
-    /// from the `agents.config` file, queries them individually for their values and
-    /// returns a Vec<u64> containing all valid agent replies. A reply is valid iff
+    /// from the `agents.config` file, reusing pooled connections opened in previous rounds
+    /// whenever possible, queries them individually for their values and
+    /// returns a vote for every valid agent reply. A reply is valid iff
     /// the received message is not corrupted and it has been signed by the agent to which
-    /// the query was sent.
-    pub async fn play_standard_round(&self) -> anyhow::Result<Vec<u64>> {
+    /// the query was sent. Agents are queried according to the timeouts and retries in `policy`.
+    pub async fn play_standard_round(&self, policy: &RoundPolicy) -> anyhow::Result<Vec<Vote>> {
+        self.query_agents(&self.peers, policy).await
+    }
+
+    /// Queries each agent in `peers` directly for its value, in a new round, according to
+    /// `policy`. Returns a vote for every valid agent reply.
+    pub async fn query_agents(
+        &self,
+        peers: &[AgentConfig],
+        policy: &RoundPolicy,
+    ) -> anyhow::Result<Vec<Vote>> {
         let mut agent_conn_handles = Vec::new();
-        let mut agent_values = Vec::new();
         let client_arc = Arc::new(self.clone());
+        let round_nonce = Self::gen_round_nonce();
+        let deadline = Instant::now() + policy.round_timeout;
 
-        for peer in &self.peers {
-            let address = peer.get_address();
-            let port = peer.get_port();
-            let mut socket = match connect(address, port).await {
-                Ok(socket) => socket,
-                Err(e) => {
-                    println!(
-                        "[!] error: failed to connect to (Agent ID: {} - {}:{}) - {}\n",
-                        peer.get_id(),
-                        address,
-                        port,
-                        e
-                    );
-                    continue;
-                }
-            };
-
-            let agent_pubkey = peer.get_public_key().to_owned();
+        for peer in peers {
+            let agent_id = peer.get_id();
+            let peer = peer.clone();
             let client = client_arc.clone();
+            let policy = *policy;
             let handle = spawn(async move {
-                Self::send_msg_query_value(client, &mut socket, &agent_pubkey).await
+                Self::send_msg_query_value(client, &peer, round_nonce, &policy)
+                    .await
+                    .map(|value| Vote::new(agent_id, value))
             });
-            agent_conn_handles.push(handle);
+            agent_conn_handles.push((agent_id, handle));
         }
 
-        for handle in agent_conn_handles {
-            match handle.await {
-                Ok(Ok(agent_value)) => {
-                    agent_values.push(agent_value);
-                }
-                Ok(Err(e)) => println!("{}", e),
-                Err(e) => println!("[!] error: task panicked - {}\n", e),
-            }
-        }
+        let (votes, _) = Self::collect_replies(agent_conn_handles, deadline, policy).await;
 
-        Ok(agent_values)
+        Ok(votes)
     }
 
     /// Plays an expert round of the game. The game's client connects to a subset of the agents
     /// loaded from the `agents.config` file and queries them for both their values and the values of
-    /// other agents that are not in the subset and cannot be reached directly. This function returns
-    /// a `Vec<u64>` containing all the valid unique values received from agents. A message containing
-    /// a value is only valid if the client can verify that it was signed by the sending agent.
+    /// other agents that are not in the subset and cannot be reached directly. Connections to the
+    /// subset are taken from the client's connection pool. This function returns a `RoundReport`
+    /// containing a single valid value for every agent, the evidence of every relayed packet that
+    /// was rejected, naming the relay that altered it, and a proof for every agent that signed two
+    /// different values during the round. Equivocating agents have no vote. The evidence is also
+    /// kept in the client's suspect list. A message containing a value is only valid if the client
+    /// can verify that it was signed by the sending agent.
+    /// Agents in the subset are queried according to the timeouts and retries in `policy`. Values
+    /// that are still missing once every agent in the subset has replied are requested again, with
+    /// a `MsgQueryValues`, from one agent of the subset at a time. Agents that a relay cannot reach
+    /// are fetched through its neighbours, for up to `ttl` hops.
     pub async fn play_expert_round(
         &self,
         expert_subset: &Vec<AgentConfig>,
-    ) -> anyhow::Result<Vec<u64>> {
+        ttl: u8,
+        policy: &RoundPolicy,
+    ) -> anyhow::Result<RoundReport> {
         let mut agent_conn_handles = Vec::new();
         let client_arc = Arc::new(self.clone());
+        let round_nonce = Self::gen_round_nonce();
+        let deadline = Instant::now() + policy.round_timeout;
 
-        let mut agent_values: HashSet<(usize, u64)> = HashSet::new();
+        let mut collected = CollectedValues::default();
 
         for peer in expert_subset {
-            let address = peer.get_address();
-            let port = peer.get_port();
-            let mut socket = match connect(address, port).await {
-                Ok(socket) => socket,
-                Err(e) => {
-                    println!(
-                        "[!] error: failed to connect to (Agent ID: {} - {}:{}) - {}\n",
-                        peer.get_id(),
-                        address,
-                        port,
-                        e
-                    );
-                    continue;
-                }
-            };
-
-            let client = client_arc.clone();
-            let agent_pubkey = peer.get_public_key().to_owned();
             let agent_id = peer.get_id();
+            let peer = peer.clone();
+            let client = client_arc.clone();
+            let policy = *policy;
             let handle = spawn(async move {
-                Self::send_msg_fetch_values(client, &mut socket, agent_id, &agent_pubkey).await
+                Self::send_msg_fetch_values(client, &peer, round_nonce, ttl, &policy).await
             });
-            agent_conn_handles.push(handle);
+            agent_conn_handles.push((agent_id, handle));
         }
 
-        for handle in agent_conn_handles {
-            match handle.await {
-                Ok(Ok(fetched_messages)) => {
-                    // Keep only the previously unknown values contained in the `MsgFwdValues`
-                    Self::filter_unique_values(&mut agent_values, &fetched_messages)
-                }
-                Ok(Err(e)) => println!("{}", e),
-                Err(e) => println!("[!] error: task panicked - {}\n", e),
+        let (fetched, _) = Self::collect_replies(agent_conn_handles, deadline, policy).await;
+        // Keep the first value signed by each agent, and any conflicting value as proof
+        collected.add(fetched);
+
+        // Ask the relays, one at a time, only for the values that are still missing, e.g, because
+        // they were tampered with. Relays serve the values cached while handling `MsgFetchValues`.
+        let missing = self.get_missing_agents(&collected.values);
+        self.query_missing_values(
+            expert_subset,
+            &missing,
+            &mut collected,
+            round_nonce,
+            deadline,
+            policy,
+        )
+        .await;
+
+        Ok(collected.into_report(&self.suspects, false))
+    }
+
+    /// Asks the `relays`, one at a time, for the values of the agents in `missing` in a new round,
+    /// according to `policy`, e.g, to break a tie between values once an expert round is over.
+    /// Relays fetch the values from their neighbours. Returns a `RoundReport` of the values
+    /// received, whose evidence is added to the last round in the client's suspect list.
+    pub async fn requery_missing_values(
+        &self,
+        relays: &[AgentConfig],
+        missing: &[usize],
+        policy: &RoundPolicy,
+    ) -> anyhow::Result<RoundReport> {
+        let round_nonce = Self::gen_round_nonce();
+        let deadline = Instant::now() + policy.round_timeout;
+        let mut collected = CollectedValues::default();
+
+        self.query_missing_values(
+            relays,
+            missing,
+            &mut collected,
+            round_nonce,
+            deadline,
+            policy,
+        )
+        .await;
+
+        Ok(collected.into_report(&self.suspects, true))
+    }
+
+    /// Asks the `relays`, one at a time, for the values of the agents in `wanted` that are not in
+    /// `collected` yet, until every value is received or `deadline` passes. Relays serve the
+    /// values cached during the round `round_nonce`, and fetch any other value from their
+    /// neighbours.
+    async fn query_missing_values(
+        &self,
+        relays: &[AgentConfig],
+        wanted: &[usize],
+        collected: &mut CollectedValues,
+        round_nonce: u64,
+        deadline: Instant,
+        policy: &RoundPolicy,
+    ) {
+        let client_arc = Arc::new(self.clone());
+
+        for relay in relays {
+            let missing: Vec<usize> = wanted
+                .iter()
+                .copied()
+                .filter(|&agent_id| !collected.values.contains(agent_id))
+                .collect();
+            if missing.is_empty() || Instant::now() >= deadline {
+                break;
             }
+
+            println!(
+                "{}{}{}{}\n",
+                "[+] Requesting ".bold(),
+                missing.len(),
+                " missing values from Agent ".bold(),
+                relay.get_id()
+            );
+
+            let relay_id = relay.get_id();
+            let relay = relay.clone();
+            let client = client_arc.clone();
+            let policy = *policy;
+            let handle = spawn(async move {
+                Self::send_msg_query_values(client, &relay, &missing, round_nonce, &policy).await
+            });
+
+            let (fetched, _) =
+                Self::collect_replies(vec![(relay_id, handle)], deadline, &policy).await;
+            collected.add(fetched);
+        }
+    }
+
+    /// Plays a gossip round of the game. Instead of collecting values itself, the client queries
+    /// the single agent `peer` for the collection of signed values it gathered through gossip,
+    /// according to `policy`. Returns a vote carrying a single valid value for every agent that did
+    /// not equivocate.
+    pub async fn play_gossip_round(
+        &self,
+        peer: &AgentConfig,
+        policy: &RoundPolicy,
+    ) -> anyhow::Result<Vec<Vote>> {
+        let client_arc = Arc::new(self.clone());
+        let round_nonce = Self::gen_round_nonce();
+        let deadline = Instant::now() + policy.round_timeout;
+
+        let agent_id = peer.get_id();
+        let peer = peer.clone();
+        let handle_policy = *policy;
+        let handle = spawn(async move {
+            Self::send_msg_query_gossip(client_arc, &peer, round_nonce, &handle_policy).await
+        });
+
+        let mut agent_values = SignedValues::default();
+        let (fetched, _) = Self::collect_replies(vec![(agent_id, handle)], deadline, policy).await;
+        for gossiped in fetched {
+            gossiped.iter().for_each(|value| agent_values.insert(value));
         }
 
-        let agent_values: Vec<u64> = agent_values.iter().map(|&(_, value)| value).collect();
+        Ok(agent_values.get_votes())
+    }
 
-        Ok(agent_values)
+    /// Returns the IDs of the agents in `Client.peers` whose values are not in `received_values`.
+    fn get_missing_agents(&self, received_values: &SignedValues) -> Vec<usize> {
+        self.peers
+            .iter()
+            .map(|peer| peer.get_id())
+            .filter(|&agent_id| !received_values.contains(agent_id))
+            .collect()
     }
 
-    /// Receives a vector of messages `&Vec<Message>`, extracts all `MsgSendValue` it contains and
-    /// uses a HashSet to store only the tuples (agent_id, value) which were not yet known.
-    fn filter_unique_values(received_values: &mut HashSet<(usize, u64)>, messages: &Vec<Message>) {
-        for message in messages {
-            match message {
-                Message::MsgSendValue { agent_id, value } => {
-                    let _ = received_values.insert((*agent_id, *value));
-                }
-                _ => (),
+    /// Appends the items of `evidence` that are not yet known to `known_evidence`.
+    fn merge_evidence(known_evidence: &mut Vec<TamperEvidence>, evidence: Vec<TamperEvidence>) {
+        for item in evidence {
+            if !known_evidence.contains(&item) {
+                known_evidence.push(item);
             }
         }
     }
 
-    /// Connects to `address`:`port` and sends a `MsgKillAgent` addressed to `agent_id`.
-    pub async fn kill_agent(
-        &self,
-        agent_id: usize,
-        address: &str,
-        port: usize,
-    ) -> anyhow::Result<String> {
-        let mut socket = match connect(address, port).await {
-            Ok(socket) => socket,
+    /// Connects to the agent described by `agent` and sends it a `MsgKillAgent`. The connection to
+    /// the killed agent is then removed from the client's connection pool.
+    pub async fn kill_agent(&self, agent: &AgentConfig) -> anyhow::Result<String> {
+        let agent_id = agent.get_id();
+        let address = agent.get_address();
+        let port = agent.get_port();
+
+        let connection = match self
+            .connections
+            .get(address, port, agent.get_public_key())
+            .await
+        {
+            Ok(connection) => connection,
             Err(e) => {
                 bail!(
                     "[!] error: failed to connect to {}:{} - {}\n",
@@ -457,7 +1226,8 @@ impl Client {
 
         let client = self.clone();
         let handle =
-            spawn(async move { Self::send_msg_kill_agent(&client, agent_id, &mut socket).await });
+            spawn(async move { Self::send_msg_kill_agent(&client, agent_id, &connection).await });
+        self.connections.remove(address, port);
 
         match handle.await {
             Ok(Ok(())) => Ok(format!(
@@ -480,6 +1250,36 @@ impl Client {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agent::{Agent, LiarOptions};
+    use crate::behavior::RelayStrategy;
+    use crate::connection::NetworkOptions;
+    use crate::connection::{recv_frame, send_frame};
+    use crate::gossip::GossipOptions;
+    use crate::handshake::{accept_handshake, Capabilities};
+    use crate::network_utils::FrameConfig;
+    use std::collections::BTreeSet;
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    /// Returns the messages carried by the signed `values`.
+    fn signed_messages(values: &[Packet]) -> Vec<Message> {
+        values
+            .iter()
+            .map(|packet| Message::deserialize_message(&packet.message).unwrap())
+            .collect()
+    }
+
+    /// Informs every agent in `agents` of the peers known to `client`, then starts them and waits
+    /// until all of them are listening.
+    async fn spawn_agents(client: &Client, agents: &[&Agent]) {
+        for agent in agents {
+            agent.set_known_peers(&client.peers);
+            let (ready_signal, ready) = oneshot::channel();
+            let agent = (*agent).clone();
+            spawn(async move { agent.start_agent(ready_signal).await });
+            ready.await.unwrap();
+        }
+    }
 
     #[test]
     fn test_load_agent_config_ok() {
@@ -525,28 +1325,561 @@ mod tests {
     }
 
     #[test]
-    fn test_infer_network_value_ok() {
-        // A single network value should be returned
-        let agent_values = vec![1, 1, 2, 3, 4, 5, 6, 7];
-        let mut network_values = Client::infer_network_value(&agent_values).unwrap();
-        network_values.sort();
-        assert_eq!(network_values, vec![1]);
-
-        // Two network values should be returned
-        let agent_values = vec![1, 1, 2, 3, 4, 4, 5, 6, 7];
-        let mut network_values = Client::infer_network_value(&agent_values).unwrap();
-        network_values.sort();
-        assert_eq!(network_values, vec![1, 4]);
-
-        // Multiple network values should be returned
-        let agent_values = vec![1, 2, 3, 4, 5, 6, 7];
-        let mut network_values = Client::infer_network_value(&agent_values).unwrap();
-        network_values.sort();
-        assert_eq!(network_values, vec![1, 2, 3, 4, 5, 6, 7]);
-
-        // Should return `None`
-        let agent_values = vec![];
-        let network_values = Client::infer_network_value(&agent_values);
-        assert!(network_values.is_none());
+    fn test_handle_msg_send_value_nonce() {
+        let keys = Keys::new_key_pair();
+        let message = Message::build_msg_send_value(10, 1, 5).unwrap();
+        let packet = Packet::new(1, CLIENT_ID, 5, message).sign(&keys).unwrap();
+        let pubkey = keys.get_public_key();
+
+        // A validly signed reply carrying the round's nonce is accepted
+        assert!(Client::handle_msg_send_value(&packet, 1, pubkey, CLIENT_ID, 5, 5).is_ok());
+
+        // The same signed reply is rejected as a replay in any other round
+        assert!(Client::handle_msg_send_value(&packet, 1, pubkey, CLIENT_ID, 5, 6).is_err());
+
+        // ...and when it is presented as coming from, or addressed to, someone else
+        assert!(Client::handle_msg_send_value(&packet, 2, pubkey, CLIENT_ID, 5, 5).is_err());
+        assert!(Client::handle_msg_send_value(&packet, 1, pubkey, 3, 5, 5).is_err());
+    }
+
+    #[test]
+    fn test_handle_msg_fwd_values_rejects_replays() {
+        let mut client = Client::new();
+        let relay_keys = Keys::new_key_pair();
+        let peer_keys = Keys::new_key_pair();
+        let relay = AgentConfig::new(1, "127.0.0.1", 5000, relay_keys.get_public_key());
+
+        client.peers = vec![AgentConfig::new(
+            2,
+            "127.0.0.1",
+            5001,
+            peer_keys.get_public_key(),
+        )];
+
+        let fresh = Message::build_msg_send_value(10, 2, 1).unwrap();
+        let stale = Message::build_msg_send_value(10, 2, 0).unwrap();
+        let forwarded = vec![
+            Packet::new(2, 1, 1, fresh.clone())
+                .sign(&peer_keys)
+                .unwrap(),
+            Packet::new(2, 1, 0, stale).sign(&peer_keys).unwrap(),
+            // A fresh value that was requested by a different agent and lifted by the relay
+            Packet::new(2, 3, 1, fresh.clone())
+                .sign(&peer_keys)
+                .unwrap(),
+        ];
+        let duplicate = forwarded[0].clone();
+        let rejected = vec![
+            // A value claiming to come from an agent that is not part of the game
+            Packet::new(9, 1, 1, fresh).sign(&peer_keys).unwrap(),
+            // A message that relays never forward
+            Packet::new(2, 1, 1, Message::build_msg_query_value(1).unwrap())
+                .sign(&peer_keys)
+                .unwrap(),
+            // A value that was already forwarded
+            duplicate,
+        ];
+        let forwarded = [forwarded, rejected].concat();
+
+        let message = Message::build_msg_fwd_values(1, &forwarded).unwrap();
+        let packet = Packet::new(1, CLIENT_ID, 1, message)
+            .sign(&relay_keys)
+            .unwrap();
+
+        let received = client
+            .handle_msg_fwd_values(&packet, &forwarded, &relay, 1, 1)
+            .unwrap();
+
+        let reasons: Vec<RejectionReason> = received
+            .tampering
+            .iter()
+            .map(|evidence| evidence.get_reason())
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                RejectionReason::Replayed,
+                RejectionReason::Replayed,
+                RejectionReason::UnknownAgent,
+                RejectionReason::WrongMessageType,
+                RejectionReason::Duplicate,
+            ]
+        );
+        assert!(received
+            .tampering
+            .iter()
+            .all(|evidence| evidence.get_culprit() == 1));
+        assert_eq!(
+            signed_messages(&received.values),
+            vec![Message::MsgSendValue {
+                agent_id: 2,
+                value: 10,
+                nonce: 1
+            }]
+        );
+
+        // The relay's own reply must also belong to the current round
+        assert!(client
+            .handle_msg_fwd_values(&packet, &forwarded, &relay, 2, 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_get_missing_agents() {
+        let mut client = Client::new();
+        client.peers = (1..=3)
+            .map(|id| AgentConfig::new(id, "127.0.0.1", 5000 + id, client.keys.get_public_key()))
+            .collect();
+
+        let mut received = SignedValues::default();
+        for (agent_id, value) in [(1, 10), (3, 12)] {
+            let message = Message::build_msg_send_value(value, agent_id, 1).unwrap();
+            received.insert(&Packet::new(agent_id, CLIENT_ID, 1, message));
+        }
+        assert_eq!(client.get_missing_agents(&received), vec![2]);
+    }
+
+    #[test]
+    fn test_round_report_merge() {
+        let forged = TamperEvidence::new(1, &[4], RejectionReason::BadSignature);
+        let mut report = RoundReport {
+            values: vec![Vote::new(1, 5), Vote::new(3, 9)],
+            tampering: vec![forged.clone()],
+            equivocations: vec![],
+        };
+        let requery = RoundReport {
+            values: vec![Vote::new(2, 5), Vote::new(3, 7)],
+            tampering: vec![forged],
+            equivocations: vec![],
+        };
+
+        // Agents that already voted keep their first vote, and evidence is not duplicated
+        assert_eq!(report.merge(requery), 1);
+        assert_eq!(
+            report.get_values(),
+            &vec![Vote::new(1, 5), Vote::new(2, 5), Vote::new(3, 9)]
+        );
+        assert_eq!(report.get_tampering().len(), 1);
+        assert_eq!(report.merge(RoundReport::default()), 0);
+    }
+
+    // An agent that signs two different values for the same round loses its vote
+    #[test]
+    fn test_signed_values_detect_equivocation() {
+        let keys = Keys::new_key_pair();
+        let signed = |agent_id: usize, value: u64, recipient: usize| {
+            let message = Message::build_msg_send_value(value, agent_id, 1).unwrap();
+            Packet::new(agent_id, recipient, 1, message)
+                .sign(&keys)
+                .unwrap()
+        };
+
+        let mut received = SignedValues::default();
+        received.insert(&signed(1, 5, 3));
+        // The same value relayed twice is counted once
+        received.insert(&signed(1, 5, 4));
+        received.insert(&signed(2, 3, 3));
+        received.insert(&signed(2, 7, 4));
+        received.insert(&signed(2, 8, 5));
+
+        assert_eq!(received.get_votes(), vec![Vote::new(1, 5)]);
+        let equivocations = received.get_equivocations();
+        assert_eq!(equivocations.len(), 1);
+        assert_eq!(equivocations[0].get_agent_id(), 2);
+        assert!(equivocations[0].verify(keys.get_public_key()).is_ok());
+        assert!(equivocations[0]
+            .verify(Keys::new_key_pair().get_public_key())
+            .is_err());
+
+        // Values signed for different rounds are not conflicting
+        let message = Message::build_msg_send_value(9, 2, 2).unwrap();
+        let next_round = Packet::new(2, 3, 2, message).sign(&keys).unwrap();
+        assert!(EquivocationProof::new(&signed(2, 3, 3), &next_round).is_none());
+    }
+
+    // Values received by a relay are cached, so they can still be served once the peer is gone
+    #[tokio::test]
+    async fn test_query_values_served_from_cache() {
+        let mut client = Client::new();
+        let client_pubkey = client.keys.get_public_key().to_owned();
+        let relay = Agent::new_honest(5, client_pubkey.clone(), NetworkOptions::default());
+        let peer = Agent::new_honest(5, client_pubkey, NetworkOptions::default());
+        client.peers = vec![relay.to_config(), peer.to_config()];
+
+        spawn_agents(&client, &[&relay, &peer]).await;
+
+        let client = Arc::new(client);
+        let policy = RoundPolicy::default();
+        let expected = vec![Message::MsgSendValue {
+            agent_id: peer.get_id(),
+            value: 5,
+            nonce: 7,
+        }];
+        let agent_ids = [peer.get_id()];
+        let query = |nonce| {
+            Client::send_msg_query_values(
+                client.clone(),
+                &client.peers[0],
+                &agent_ids,
+                nonce,
+                &policy,
+            )
+        };
+
+        assert_eq!(signed_messages(&query(7).await.unwrap().values), expected);
+
+        // Once the peer is killed, the relay still serves its cached value for the same round
+        client.kill_agent(&peer.to_config()).await.unwrap();
+        assert_eq!(signed_messages(&query(7).await.unwrap().values), expected);
+
+        // ...but cannot provide one for any other round
+        assert!(query(8).await.unwrap().values.is_empty());
+    }
+
+    // Agents refuse requests from agents they are not linked to, but never from the client
+    #[tokio::test]
+    async fn test_topology_refuses_unlinked_agents() {
+        let mut client = Client::new();
+        let client_pubkey = client.keys.get_public_key().to_owned();
+        let relay = Agent::new_honest(5, client_pubkey.clone(), NetworkOptions::default());
+        let peer = Agent::new_honest(5, client_pubkey, NetworkOptions::default());
+        client.peers = vec![relay.to_config(), peer.to_config()];
+
+        spawn_agents(&client, &[&relay, &peer]).await;
+        peer.set_neighbours(Some(&BTreeSet::new()));
+
+        let client = Arc::new(client);
+        let policy = RoundPolicy::default();
+
+        let relayed =
+            Client::send_msg_fetch_values(client.clone(), &client.peers[0], 1, 1, &policy)
+                .await
+                .unwrap();
+        assert_eq!(
+            signed_messages(&relayed.values),
+            vec![Message::MsgSendValue {
+                agent_id: relay.get_id(),
+                value: 5,
+                nonce: 1
+            }]
+        );
+
+        let value = Client::send_msg_query_value(client.clone(), &client.peers[1], 2, &policy)
+            .await
+            .unwrap();
+        assert_eq!(value, 5);
+    }
+
+    // Agents left out of an expert round refuse the client, which can still reach them via relays
+    #[tokio::test]
+    async fn test_client_lockout_refuses_direct_queries() {
+        let mut client = Client::new();
+        let client_pubkey = client.keys.get_public_key().to_owned();
+        let relay = Agent::new_honest(5, client_pubkey.clone(), NetworkOptions::default());
+        let peer = Agent::new_honest(5, client_pubkey, NetworkOptions::default());
+        client.peers = vec![relay.to_config(), peer.to_config()];
+
+        spawn_agents(&client, &[&relay, &peer]).await;
+        peer.set_client_lockout(true);
+
+        let client = Arc::new(client);
+        let policy = RoundPolicy::default();
+
+        assert!(
+            Client::send_msg_query_value(client.clone(), &client.peers[1], 1, &policy)
+                .await
+                .is_err()
+        );
+        let relayed =
+            Client::send_msg_fetch_values(client.clone(), &client.peers[0], 1, 1, &policy)
+                .await
+                .unwrap();
+        assert_eq!(relayed.values.len(), 2);
+        assert_eq!(peer.take_lockout_violations(), 1);
+        assert_eq!(relay.take_lockout_violations(), 0);
+
+        peer.set_client_lockout(false);
+        let value = Client::send_msg_query_value(client.clone(), &client.peers[1], 2, &policy)
+            .await
+            .unwrap();
+        assert_eq!(value, 5);
+        assert_eq!(peer.take_lockout_violations(), 0);
+    }
+
+    // Gossip spreads every honest value, while values forged by a liar are dropped along the way
+    #[tokio::test]
+    async fn test_query_gossip_collects_signed_values() {
+        let mut client = Client::new();
+        let client_pubkey = client.keys.get_public_key().to_owned();
+        let mut agents: Vec<Agent> = (0..3)
+            .map(|_| Agent::new_honest(5, client_pubkey.clone(), NetworkOptions::default()))
+            .collect();
+        agents.push(Agent::new_liar(
+            5,
+            9,
+            None,
+            client_pubkey,
+            &LiarOptions::new(1.0, false, &[], 1),
+            RelayStrategy::Forge,
+            NetworkOptions::default(),
+        ));
+        client.peers = agents.iter().map(|agent| agent.to_config()).collect();
+
+        for agent in &agents {
+            agent.set_gossip(Some(GossipOptions::from_millis(20, 2)));
+        }
+        spawn_agents(&client, &agents.iter().collect::<Vec<_>>()).await;
+
+        let client = Arc::new(client);
+        let policy = RoundPolicy::default();
+        let honest_ids: Vec<usize> = agents[..3].iter().map(Agent::get_id).collect();
+
+        let mut collected = Vec::new();
+        for round_nonce in 1..=50 {
+            sleep(Duration::from_millis(50)).await;
+            let values = Client::send_msg_query_gossip(
+                client.clone(),
+                &client.peers[0],
+                round_nonce,
+                &policy,
+            )
+            .await
+            .unwrap();
+            collected = signed_messages(&values)
+                .iter()
+                .filter_map(|message| match message {
+                    Message::MsgSendValue { agent_id, .. } => Some(*agent_id),
+                    _ => None,
+                })
+                .collect();
+            if collected.len() == honest_ids.len() {
+                break;
+            }
+        }
+
+        assert_eq!(collected, honest_ids);
+    }
+
+    // A peer that refuses the relay is reached through a neighbour, within the hop limit
+    #[tokio::test]
+    async fn test_fetch_values_relayed_through_neighbours() {
+        let mut client = Client::new();
+        let client_pubkey = client.keys.get_public_key().to_owned();
+        let agents: Vec<Agent> = (0..3)
+            .map(|_| Agent::new_honest(5, client_pubkey.clone(), NetworkOptions::default()))
+            .collect();
+        client.peers = agents.iter().map(|agent| agent.to_config()).collect();
+        let (relay, unreachable) = (&agents[0], &agents[2]);
+
+        spawn_agents(&client, &agents.iter().collect::<Vec<_>>()).await;
+        // The last agent does not know the relay, so it refuses to answer its queries
+        unreachable.set_known_peers(&client.peers[1..]);
+
+        let client = Arc::new(client);
+        let policy = RoundPolicy::default();
+        let fetch = |nonce, ttl| {
+            Client::send_msg_fetch_values(client.clone(), &client.peers[0], nonce, ttl, &policy)
+        };
+        let relayed_ids = |messages: Vec<Message>| {
+            let mut ids: Vec<usize> = messages
+                .iter()
+                .filter_map(|message| match message {
+                    Message::MsgSendValue { agent_id, .. } => Some(*agent_id),
+                    _ => None,
+                })
+                .collect();
+            ids.sort();
+            ids
+        };
+
+        let direct = relayed_ids(signed_messages(&fetch(1, 1).await.unwrap().values));
+        assert!(!direct.contains(&unreachable.get_id()));
+        assert!(direct.contains(&relay.get_id()));
+
+        let relayed = relayed_ids(signed_messages(&fetch(2, 2).await.unwrap().values));
+        assert!(relayed.contains(&unreachable.get_id()));
+    }
+
+    // Values altered by a liar deep in the chain of relays are attributed to the liar, not to the
+    // honest relay that forwarded them to the client
+    #[tokio::test]
+    async fn test_fetch_values_attributes_tampering() {
+        let mut client = Client::new();
+        let client_pubkey = client.keys.get_public_key().to_owned();
+        let network = NetworkOptions::default();
+        let relay = Agent::new_honest(5, client_pubkey.clone(), network);
+        let liar = Agent::new_liar(
+            5,
+            10,
+            None,
+            client_pubkey.clone(),
+            &LiarOptions::new(1.0, false, &[], 1),
+            RelayStrategy::Forge,
+            network,
+        );
+        let unreachable = Agent::new_honest(5, client_pubkey, network);
+        let agents = [&relay, &liar, &unreachable];
+        client.peers = agents.iter().map(|agent| agent.to_config()).collect();
+
+        spawn_agents(&client, &agents).await;
+        // The last agent does not know the relay, so it can only be reached through the liar
+        unreachable.set_known_peers(&client.peers[1..]);
+
+        let client = Arc::new(client);
+        let relayed = Client::send_msg_fetch_values(
+            client.clone(),
+            &client.peers[0],
+            1,
+            2,
+            &RoundPolicy::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(relayed.tampering.iter().any(|evidence| {
+            evidence.get_claimed_sender() == unreachable.get_id()
+                && evidence.get_culprit() == liar.get_id()
+                && evidence.get_custody_chain() == [relay.get_id(), liar.get_id()]
+        }));
+        assert!(relayed
+            .tampering
+            .iter()
+            .all(|evidence| evidence.get_culprit() == liar.get_id()));
+        assert!(!signed_messages(&relayed.values)
+            .iter()
+            .any(|message| matches!(
+                message,
+                Message::MsgSendValue { agent_id, .. } if *agent_id == unreachable.get_id()
+            )));
+    }
+
+    // A liar signing a different value for each relay is caught and left out of the vote
+    #[tokio::test]
+    async fn test_expert_round_ignores_equivocators() {
+        let mut client = Client::new();
+        let client_pubkey = client.keys.get_public_key().to_owned();
+        let network = NetworkOptions::default();
+        let mut agents: Vec<Agent> = (0..3)
+            .map(|_| Agent::new_honest(5, client_pubkey.clone(), network))
+            .collect();
+        // A wide range of values makes it unlikely for the liar to sign the same value twice
+        let liar = Agent::new_liar(
+            5,
+            1_000_000,
+            None,
+            client_pubkey,
+            &LiarOptions::new(0.0, true, &[], 1),
+            RelayStrategy::Forge,
+            network,
+        );
+        agents.push(liar.clone());
+        client.peers = agents.iter().map(|agent| agent.to_config()).collect();
+
+        spawn_agents(&client, &agents.iter().collect::<Vec<_>>()).await;
+
+        let subset = client.peers[..2].to_vec();
+        let report = client
+            .play_expert_round(&subset, 1, &RoundPolicy::default())
+            .await
+            .unwrap();
+
+        let values: Vec<u64> = report.get_values().iter().map(Vote::get_value).collect();
+        assert_eq!(values, vec![5, 5, 5]);
+        assert_eq!(report.get_equivocations().len(), 1);
+        assert_eq!(report.get_equivocations()[0].get_agent_id(), liar.get_id());
+        assert_eq!(
+            client.get_suspects().get_suspects()[0].get_agent_id(),
+            liar.get_id()
+        );
+    }
+
+    #[test]
+    fn test_round_policy_backoff() {
+        let policy = RoundPolicy::from_millis(1000, 5000, 3, 100);
+        assert_eq!(policy.get_backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.get_backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.get_backoff(2), Duration::from_millis(400));
+        // Very large attempt counts saturate instead of overflowing
+        assert_eq!(policy.get_backoff(u32::MAX), Duration::MAX);
+    }
+
+    // An agent that accepts connections but never replies must be reported as timed out, while
+    // retries keep using the pooled connection
+    #[tokio::test]
+    async fn test_request_with_retry_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port().into();
+        let accepted = Arc::new(AtomicUsize::new(0));
+
+        let counter = accepted.clone();
+        spawn(async move {
+            let config = FrameConfig::default();
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, Ordering::Relaxed);
+                spawn(async move {
+                    accept_handshake(
+                        &mut socket,
+                        &config,
+                        Capabilities::supported(),
+                        Capabilities::NONE,
+                    )
+                    .await
+                    .unwrap();
+                    while recv_frame(&mut socket, &config).await.is_ok() {}
+                });
+            }
+        });
+
+        let client = Client::new();
+        let peer = AgentConfig::new(1, "127.0.0.1", port, client.keys.get_public_key());
+        let policy = RoundPolicy::from_millis(200, 1000, 1, 10);
+        let packet = Packet::new(CLIENT_ID, 1, 1, Message::build_msg_query_value(1).unwrap());
+
+        assert!(matches!(
+            client.request_with_retry(&peer, &packet, &policy).await,
+            Err(QueryError::TimedOut)
+        ));
+        assert_eq!(accepted.load(Ordering::Relaxed), 1);
+    }
+
+    // A relay replying with bytes that cannot be decoded is held responsible for its reply
+    #[tokio::test]
+    async fn test_fetch_values_attributes_undecodable_replies() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port().into();
+
+        spawn(async move {
+            let config = FrameConfig::default();
+            let (mut socket, _) = listener.accept().await.unwrap();
+            accept_handshake(
+                &mut socket,
+                &config,
+                Capabilities::supported(),
+                Capabilities::NONE,
+            )
+            .await
+            .unwrap();
+            while let Ok(frame) = recv_frame(&mut socket, &config).await {
+                send_frame(frame.request_id, &[0xff; 16], &mut socket, &config)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let mut client = Client::new();
+        let relay_keys = Keys::new_key_pair();
+        let relay = AgentConfig::new(1, "127.0.0.1", port, relay_keys.get_public_key());
+        client.peers = vec![relay.clone()];
+        let policy = RoundPolicy::from_millis(1000, 2000, 0, 10);
+
+        let relayed = Client::send_msg_fetch_values(Arc::new(client), &relay, 1, 1, &policy)
+            .await
+            .unwrap();
+        assert!(relayed.values.is_empty());
+        assert_eq!(
+            relayed.tampering,
+            vec![TamperEvidence::new(1, &[1], RejectionReason::Undecodable)]
+        );
     }
 }
@@ -0,0 +1,54 @@
+use std::fmt;
+
+/// Represents a group of liars that collude by reporting the same false value, so that they can
+/// outvote the honest agents instead of scattering their votes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coalition {
+    /// An identifier for each coalition of the game, starting at 1.
+    coalition_id: usize,
+    /// The false value reported by every member of the coalition.
+    value: u64,
+    /// The IDs of the liars in the coalition, in the order in which they joined it.
+    members: Vec<usize>,
+}
+
+impl fmt::Display for Coalition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let members: Vec<String> = self.members.iter().map(usize::to_string).collect();
+        write!(
+            f,
+            "Coalition {} reports {} (members: {})",
+            self.coalition_id,
+            self.value,
+            members.join(", ")
+        )
+    }
+}
+
+impl Coalition {
+    /// Returns a new instance of `Coalition` with no members, whose members report `value`.
+    pub fn new(coalition_id: usize, value: u64) -> Self {
+        Coalition {
+            coalition_id,
+            value,
+            members: Vec::new(),
+        }
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.coalition_id
+    }
+
+    pub fn get_value(&self) -> u64 {
+        self.value
+    }
+
+    pub fn get_members(&self) -> &[usize] {
+        &self.members
+    }
+
+    /// Adds the liar `agent_id` to the coalition.
+    pub fn add_member(&mut self, agent_id: usize) {
+        self.members.push(agent_id);
+    }
+}
@@ -0,0 +1,175 @@
+use clap::ValueEnum;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+
+use crate::handshake::Capabilities;
+
+/// Represents the formats in which packets can be encoded on the wire.
+///
+/// The codec only governs how a `Packet` is laid out in a frame. Signatures are always computed
+/// over the canonical bincode encoding of a `Message`, so a packet can be re-encoded with a
+/// different codec, e.g, when it is forwarded, without invalidating its signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Codec {
+    /// A compact binary format. Used by every peer that does not negotiate another codec.
+    #[default]
+    Bincode,
+    /// A human-readable format, convenient when inspecting traffic or scripting test agents.
+    Json,
+    /// A compact, self-describing binary format.
+    Cbor,
+}
+
+/// Represents the ways in which encoding or decoding a value with a `Codec` can fail.
+#[derive(Debug)]
+pub enum CodecError {
+    Bincode(bincode::Error),
+    Json(serde_json::Error),
+    Cbor(String),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Bincode(e) => write!(f, "bincode - {}", e),
+            CodecError::Json(e) => write!(f, "json - {}", e),
+            CodecError::Cbor(e) => write!(f, "cbor - {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Codec::Bincode => write!(f, "bincode"),
+            Codec::Json => write!(f, "json"),
+            Codec::Cbor => write!(f, "cbor"),
+        }
+    }
+}
+
+impl Codec {
+    /// Encodes `value` in the format of this codec.
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        match self {
+            Codec::Bincode => bincode::serialize(value).map_err(CodecError::Bincode),
+            Codec::Json => serde_json::to_vec(value).map_err(CodecError::Json),
+            Codec::Cbor => {
+                let mut data = Vec::new();
+                ciborium::into_writer(value, &mut data)
+                    .map_err(|e| CodecError::Cbor(e.to_string()))?;
+                Ok(data)
+            }
+        }
+    }
+
+    /// Decodes `data`, expected to be in the format of this codec, into an instance of `T`.
+    pub fn decode<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T, CodecError> {
+        match self {
+            Codec::Bincode => bincode::deserialize(data).map_err(CodecError::Bincode),
+            Codec::Json => serde_json::from_slice(data).map_err(CodecError::Json),
+            Codec::Cbor => ciborium::from_reader(data).map_err(|e| CodecError::Cbor(e.to_string())),
+        }
+    }
+
+    /// Returns the capability advertised during the handshake to request this codec. Bincode is
+    /// understood by every peer and therefore needs no capability.
+    pub fn capability(&self) -> Capabilities {
+        match self {
+            Codec::Bincode => Capabilities::NONE,
+            Codec::Json => Capabilities::CODEC_JSON,
+            Codec::Cbor => Capabilities::CODEC_CBOR,
+        }
+    }
+
+    /// Returns the capabilities of every codec that this build is able to decode.
+    pub fn supported_capabilities() -> Capabilities {
+        Capabilities::CODEC_JSON | Capabilities::CODEC_CBOR
+    }
+
+    /// Returns the codec selected by a set of negotiated `capabilities`. Falls back to bincode if
+    /// no other codec was agreed upon.
+    pub fn from_capabilities(capabilities: Capabilities) -> Self {
+        if capabilities.contains(Capabilities::CODEC_CBOR) {
+            Codec::Cbor
+        } else if capabilities.contains(Capabilities::CODEC_JSON) {
+            Codec::Json
+        } else {
+            Codec::Bincode
+        }
+    }
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::Keys;
+    use crate::message::Message;
+    use crate::packet::{Packet, CLIENT_ID};
+
+    #[test]
+    fn test_codec_round_trip() {
+        let message = Message::MsgSendValue {
+            agent_id: 3,
+            value: u64::MAX,
+            nonce: 7,
+        };
+
+        for codec in [Codec::Bincode, Codec::Json, Codec::Cbor] {
+            let data = codec.encode(&message).unwrap();
+            assert_eq!(codec.decode::<Message>(&data).unwrap(), message);
+        }
+
+        // JSON is readable on the wire
+        let data = Codec::Json.encode(&message).unwrap();
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            r#"{"MsgSendValue":{"agent_id":3,"value":18446744073709551615,"nonce":7}}"#
+        );
+    }
+
+    // Signed packets, including the packets nested in a MsgFwdValues, still verify after being
+    // re-encoded with any codec
+    #[test]
+    fn test_codec_signed_packet_round_trip() {
+        let peer_keys = Keys::new_key_pair();
+        let relay_keys = Keys::new_key_pair();
+
+        let value = Message::build_msg_send_value(10, 2, 7).unwrap();
+        let reply = Packet::new(2, 1, 7, value).sign(&peer_keys).unwrap();
+        let forwarded = Message::build_msg_fwd_values(1, std::slice::from_ref(&reply)).unwrap();
+        let packet = Packet::new(1, CLIENT_ID, 7, forwarded)
+            .sign(&relay_keys)
+            .unwrap();
+
+        for codec in [Codec::Bincode, Codec::Json, Codec::Cbor] {
+            let decoded = Packet::unpack(&packet.encode(codec).unwrap(), codec).unwrap();
+            assert_eq!(decoded, packet);
+            assert!(decoded.verify(relay_keys.get_public_key()).is_ok());
+
+            let Ok(Message::MsgFwdValues { peer_values, .. }) =
+                Message::deserialize_message(&decoded.message)
+            else {
+                panic!("expected MsgFwdValues");
+            };
+            assert_eq!(peer_values, vec![reply.clone()]);
+            assert!(peer_values[0].verify(peer_keys.get_public_key()).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_codec_from_capabilities() {
+        for codec in [Codec::Bincode, Codec::Json, Codec::Cbor] {
+            let negotiated = codec
+                .capability()
+                .intersection(Codec::supported_capabilities());
+            assert_eq!(Codec::from_capabilities(negotiated), codec);
+        }
+    }
+}
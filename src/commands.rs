@@ -1,5 +1,17 @@
 use clap::Subcommand;
 
+use crate::access::AccessPolicy;
+use crate::behavior::RelayStrategy;
+use crate::client::{
+    DEFAULT_AGENT_TIMEOUT_MS, DEFAULT_BACKOFF_MS, DEFAULT_RELAY_TTL, DEFAULT_RETRIES,
+    DEFAULT_ROUND_TIMEOUT_MS,
+};
+use crate::codec::Codec;
+use crate::gossip::{DEFAULT_GOSSIP_FANOUT, DEFAULT_GOSSIP_INTERVAL_MS};
+use crate::inference::InferenceKind;
+use crate::network_utils::DEFAULT_MAX_FRAME_SIZE;
+use crate::topology::{TopologyKind, DEFAULT_DEGREE, DEFAULT_LINK_PROBABILITY};
+
 /// Represents an user issued command along with its associated arguments.
 /// All arguments must be passed using the long format, e.g, --value.
 #[derive(Subcommand, Debug, Clone, PartialEq)]
@@ -26,9 +38,72 @@ pub enum Commands {
         /// forwarding it.
         #[arg(long)]
         tamper_chance: f32,
+
+        /// Makes liars sign a different false value for every query they answer
+        #[arg(long)]
+        equivocate: bool,
+
+        /// The relay strategies assigned in turn to liars, as a comma-separated list (forge if empty)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        liar_strategy: Vec<RelayStrategy>,
+
+        /// The number of liars that collude by sharing each false value (1 for independent liars)
+        #[arg(long, default_value_t = 1)]
+        coalition_size: u16,
+
+        /// The maximum size, in bytes, of a frame exchanged between the client and agents
+        #[arg(long, default_value_t = DEFAULT_MAX_FRAME_SIZE)]
+        max_frame_size: usize,
+
+        /// Encrypts and mutually authenticates every connection between the client and agents
+        #[arg(long)]
+        encrypted: bool,
+
+        /// The wire format requested on connections opened by the client and agents
+        #[arg(long, value_enum, default_value_t = Codec::Bincode)]
+        codec: Codec,
+
+        /// Who agents answer queries from: only the client, the client and known peers, or anyone
+        #[arg(long, value_enum, default_value_t = AccessPolicy::KnownPeers)]
+        access_policy: AccessPolicy,
     },
     /// Plays a round of the game on standard mode
-    Play,
+    Play {
+        /// The time, in milliseconds, to wait for each agent to reply before retrying
+        #[arg(long, default_value_t = DEFAULT_AGENT_TIMEOUT_MS)]
+        agent_timeout: u64,
+
+        /// The time, in milliseconds, allowed for the whole round to complete
+        #[arg(long, default_value_t = DEFAULT_ROUND_TIMEOUT_MS)]
+        round_timeout: u64,
+
+        /// The number of times a request that failed to connect, failed to read or timed out is retried
+        #[arg(long, default_value_t = DEFAULT_RETRIES)]
+        retries: u32,
+
+        /// The delay, in milliseconds, before the first retry. Doubles on every subsequent retry
+        #[arg(long, default_value_t = DEFAULT_BACKOFF_MS)]
+        backoff: u64,
+
+        /// The rule used to infer the network value from the values received
+        #[arg(long, value_enum, default_value_t = InferenceKind::Plurality)]
+        strategy: InferenceKind,
+
+        /// The assumed maximum number of liars among the agents that replied. Derived from the
+        /// proportion of liars in the game if not given
+        #[arg(long)]
+        fault_bound: Option<usize>,
+
+        /// Keeps querying agents until a single network value is inferred or no more values can
+        /// be learned
+        #[arg(long)]
+        adaptive: bool,
+
+        /// The expected ratio (0.0 to 1.0) of liars among the agents that replied, used by the
+        /// bayesian strategy. Defaults to the proportion of liars in the game
+        #[arg(long)]
+        liar_prior: Option<f32>,
+    },
     /// Extends the set of available agents (requires additional arguments)
     Extend {
         /// The number of new agents to be spawned
@@ -38,6 +113,19 @@ pub enum Commands {
         /// The ratio of liars (0.0 to 1.0) among the newly spawned agents
         #[arg(long)]
         liar_ratio: f32,
+
+        /// The relay strategies assigned in turn to the new liars (those chosen at start if empty)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        liar_strategy: Vec<RelayStrategy>,
+
+        /// The number of new liars that collude by sharing each false value (as chosen at start if
+        /// not given)
+        #[arg(long)]
+        coalition_size: Option<u16>,
+
+        /// The ID of an existing coalition that every new liar joins
+        #[arg(long, conflicts_with = "coalition_size")]
+        coalition: Option<usize>,
     },
     /// Plays a round of the game on expert mode (requires additional arguments)
     PlayExpert {
@@ -48,6 +136,50 @@ pub enum Commands {
         /// The ratio (0.0 to 1.0) of dishonest agents among the specified number of agents
         #[arg(long)]
         liar_ratio: f32,
+
+        /// The time, in milliseconds, to wait for each agent to reply before retrying
+        #[arg(long, default_value_t = DEFAULT_AGENT_TIMEOUT_MS)]
+        agent_timeout: u64,
+
+        /// The time, in milliseconds, allowed for the whole round to complete
+        #[arg(long, default_value_t = DEFAULT_ROUND_TIMEOUT_MS)]
+        round_timeout: u64,
+
+        /// The number of times a request that failed to connect, failed to read or timed out is retried
+        #[arg(long, default_value_t = DEFAULT_RETRIES)]
+        retries: u32,
+
+        /// The delay, in milliseconds, before the first retry. Doubles on every subsequent retry
+        #[arg(long, default_value_t = DEFAULT_BACKOFF_MS)]
+        backoff: u64,
+
+        /// The rule used to infer the network value from the values received
+        #[arg(long, value_enum, default_value_t = InferenceKind::Plurality)]
+        strategy: InferenceKind,
+
+        /// The assumed maximum number of liars among the agents that replied. Derived from the
+        /// proportion of liars in the game if not given
+        #[arg(long)]
+        fault_bound: Option<usize>,
+
+        /// Keeps querying agents until a single network value is inferred or no more values can
+        /// be learned
+        #[arg(long)]
+        adaptive: bool,
+
+        /// The expected ratio (0.0 to 1.0) of liars among the agents that replied, used by the
+        /// bayesian strategy. Defaults to the proportion of liars in the game
+        #[arg(long)]
+        liar_prior: Option<f32>,
+
+        /// The maximum number of relays a request for values may travel through, including the
+        /// agent queried by the client. Agents it cannot reach are fetched through its neighbours
+        #[arg(long, default_value_t = DEFAULT_RELAY_TTL)]
+        ttl: u8,
+
+        /// Prefers the agents with the best reputation when forming the expert subset
+        #[arg(long)]
+        by_reputation: bool,
     },
     /// Stops the game and quits the program
     Stop,
@@ -57,6 +189,55 @@ pub enum Commands {
         #[arg(long = "id")]
         agent_id: usize,
     },
+    /// Shows the network topology, or replaces it when a kind of topology is given
+    Topology {
+        /// The kind of graph that determines which agents may connect to which
+        #[arg(long, value_enum)]
+        kind: Option<TopologyKind>,
+
+        /// The number of neighbours of each agent in random-regular and small-world topologies
+        #[arg(long, default_value_t = DEFAULT_DEGREE)]
+        degree: usize,
+
+        /// The probability (0.0 to 1.0) of linking two agents in erdos-renyi topologies, or of
+        /// rewiring a link in small-world topologies
+        #[arg(long, default_value_t = DEFAULT_LINK_PROBABILITY)]
+        probability: f32,
+    },
+    /// Makes agents gossip their collection of signed values to random neighbours
+    Gossip {
+        /// The time, in milliseconds, between two pushes of an agent's collection. 0 stops gossip
+        #[arg(long, default_value_t = DEFAULT_GOSSIP_INTERVAL_MS)]
+        interval: u64,
+
+        /// The number of random neighbours an agent pushes its collection to on every interval
+        #[arg(long, default_value_t = DEFAULT_GOSSIP_FANOUT)]
+        fanout: usize,
+    },
+    /// Shows the agents suspected of tampering with relayed values, and the evidence against them
+    Suspects,
+    /// Plays a round of the game by querying a single agent for the values it gathered through gossip
+    PlayGossip {
+        /// The ID of the agent to be queried. A random agent is queried if omitted
+        #[arg(long = "id")]
+        agent_id: Option<usize>,
+
+        /// The time, in milliseconds, to wait for the agent to reply before retrying
+        #[arg(long, default_value_t = DEFAULT_AGENT_TIMEOUT_MS)]
+        agent_timeout: u64,
+
+        /// The time, in milliseconds, allowed for the whole round to complete
+        #[arg(long, default_value_t = DEFAULT_ROUND_TIMEOUT_MS)]
+        round_timeout: u64,
+
+        /// The number of times a request that failed to connect, failed to read or timed out is retried
+        #[arg(long, default_value_t = DEFAULT_RETRIES)]
+        retries: u32,
+
+        /// The delay, in milliseconds, before the first retry. Doubles on every subsequent retry
+        #[arg(long, default_value_t = DEFAULT_BACKOFF_MS)]
+        backoff: u64,
+    },
 }
 
 impl Commands {
@@ -67,7 +248,13 @@ impl Commands {
             Commands::Start { liar_ratio, .. }
             | Commands::Extend { liar_ratio, .. }
             | Commands::PlayExpert { liar_ratio, .. } => Some(*liar_ratio),
-            Commands::Play { .. } | Commands::Stop { .. } | Commands::Kill { .. } => None,
+            Commands::Play { .. }
+            | Commands::Stop
+            | Commands::Suspects
+            | Commands::Kill { .. }
+            | Commands::Topology { .. }
+            | Commands::Gossip { .. }
+            | Commands::PlayGossip { .. } => None,
         }
     }
 
@@ -81,8 +268,12 @@ impl Commands {
             Commands::Play { .. }
             | Commands::Extend { .. }
             | Commands::PlayExpert { .. }
-            | Commands::Stop { .. }
-            | Commands::Kill { .. } => None,
+            | Commands::Stop
+            | Commands::Suspects
+            | Commands::Kill { .. }
+            | Commands::Topology { .. }
+            | Commands::Gossip { .. }
+            | Commands::PlayGossip { .. } => None,
         }
     }
 
@@ -93,7 +284,13 @@ impl Commands {
             Commands::Start { num_agents, .. }
             | Commands::Extend { num_agents, .. }
             | Commands::PlayExpert { num_agents, .. } => Some(*num_agents),
-            Commands::Play { .. } | Commands::Stop { .. } | Commands::Kill { .. } => None,
+            Commands::Play { .. }
+            | Commands::Stop
+            | Commands::Suspects
+            | Commands::Kill { .. }
+            | Commands::Topology { .. }
+            | Commands::Gossip { .. }
+            | Commands::PlayGossip { .. } => None,
         }
     }
 
@@ -104,9 +301,145 @@ impl Commands {
             Commands::Start { tamper_chance, .. } => Some(*tamper_chance),
             Commands::Play { .. }
             | Commands::Extend { .. }
-            | Commands::Stop { .. }
+            | Commands::Stop
+            | Commands::Suspects
             | Commands::PlayExpert { .. }
-            | Commands::Kill { .. } => None,
+            | Commands::Kill { .. }
+            | Commands::Topology { .. }
+            | Commands::Gossip { .. }
+            | Commands::PlayGossip { .. } => None,
+        }
+    }
+
+    /// Receives a variant of `Commands` and checks whether it contains the `liar_prior` field or
+    /// not. If it does, and a prior was given, returns the value contained in `liar_prior`.
+    fn has_liar_prior(&self) -> Option<f32> {
+        match self {
+            Commands::Play { liar_prior, .. } | Commands::PlayExpert { liar_prior, .. } => {
+                *liar_prior
+            }
+            Commands::Start { .. }
+            | Commands::Extend { .. }
+            | Commands::Stop
+            | Commands::Suspects
+            | Commands::Kill { .. }
+            | Commands::Topology { .. }
+            | Commands::Gossip { .. }
+            | Commands::PlayGossip { .. } => None,
+        }
+    }
+
+    /// Receives a variant of `Commands` and checks whether it contains the `coalition_size` field
+    /// or not. If it does, and a size was given, returns the value contained in `coalition_size`.
+    fn has_coalition_size(&self) -> Option<u16> {
+        match self {
+            Commands::Start { coalition_size, .. } => Some(*coalition_size),
+            Commands::Extend { coalition_size, .. } => *coalition_size,
+            Commands::Play { .. }
+            | Commands::Stop
+            | Commands::Suspects
+            | Commands::PlayExpert { .. }
+            | Commands::Kill { .. }
+            | Commands::Topology { .. }
+            | Commands::Gossip { .. }
+            | Commands::PlayGossip { .. } => None,
+        }
+    }
+
+    /// Receives a variant of `Commands` and checks whether it contains the `max_frame_size` field
+    /// or not. If it does, returns the value contained in `max_frame_size`.
+    fn has_max_frame_size(&self) -> Option<usize> {
+        match self {
+            Commands::Start { max_frame_size, .. } => Some(*max_frame_size),
+            Commands::Play { .. }
+            | Commands::Extend { .. }
+            | Commands::Stop
+            | Commands::Suspects
+            | Commands::PlayExpert { .. }
+            | Commands::Kill { .. }
+            | Commands::Topology { .. }
+            | Commands::Gossip { .. }
+            | Commands::PlayGossip { .. } => None,
+        }
+    }
+
+    /// Receives a variant of `Commands` and checks whether it contains the `agent_timeout` and
+    /// `round_timeout` fields or not. If it does, returns `(agent_timeout, round_timeout)`.
+    fn has_timeouts(&self) -> Option<(u64, u64)> {
+        match self {
+            Commands::Play {
+                agent_timeout,
+                round_timeout,
+                ..
+            }
+            | Commands::PlayExpert {
+                agent_timeout,
+                round_timeout,
+                ..
+            }
+            | Commands::PlayGossip {
+                agent_timeout,
+                round_timeout,
+                ..
+            } => Some((*agent_timeout, *round_timeout)),
+            Commands::Start { .. }
+            | Commands::Extend { .. }
+            | Commands::Stop
+            | Commands::Suspects
+            | Commands::Kill { .. }
+            | Commands::Topology { .. }
+            | Commands::Gossip { .. } => None,
+        }
+    }
+
+    /// Receives a variant of `Commands` and checks whether it contains the `ttl` field or not.
+    /// If it does, returns the value contained in `ttl`.
+    fn has_ttl(&self) -> Option<u8> {
+        match self {
+            Commands::PlayExpert { ttl, .. } => Some(*ttl),
+            Commands::Start { .. }
+            | Commands::Play { .. }
+            | Commands::Extend { .. }
+            | Commands::Stop
+            | Commands::Suspects
+            | Commands::Kill { .. }
+            | Commands::Topology { .. }
+            | Commands::Gossip { .. }
+            | Commands::PlayGossip { .. } => None,
+        }
+    }
+
+    /// Receives a variant of `Commands` and checks whether it contains the `probability` field or
+    /// not. If it does, returns the value contained in `probability`.
+    fn has_probability(&self) -> Option<f32> {
+        match self {
+            Commands::Topology { probability, .. } => Some(*probability),
+            Commands::Start { .. }
+            | Commands::Play { .. }
+            | Commands::Extend { .. }
+            | Commands::PlayExpert { .. }
+            | Commands::Stop
+            | Commands::Suspects
+            | Commands::Kill { .. }
+            | Commands::Gossip { .. }
+            | Commands::PlayGossip { .. } => None,
+        }
+    }
+
+    /// Receives a variant of `Commands` and checks whether it contains the `fanout` field or not.
+    /// If it does, returns the value contained in `fanout`.
+    fn has_fanout(&self) -> Option<usize> {
+        match self {
+            Commands::Gossip { fanout, .. } => Some(*fanout),
+            Commands::Start { .. }
+            | Commands::Play { .. }
+            | Commands::Extend { .. }
+            | Commands::PlayExpert { .. }
+            | Commands::Stop
+            | Commands::Suspects
+            | Commands::Kill { .. }
+            | Commands::Topology { .. }
+            | Commands::PlayGossip { .. } => None,
         }
     }
 
@@ -114,9 +447,16 @@ impl Commands {
     /// that they satisfy the program's constraints.
     pub fn validate_args_values(&self) -> Result<(), String> {
         self.validate_liar_ratio()?;
+        self.validate_liar_prior()?;
         self.validate_agent_values()?;
         self.validate_num_agents()?;
         self.validate_tamper_chance()?;
+        self.validate_coalition_size()?;
+        self.validate_max_frame_size()?;
+        self.validate_timeouts()?;
+        self.validate_ttl()?;
+        self.validate_probability()?;
+        self.validate_fanout()?;
         Ok(())
     }
 
@@ -133,6 +473,19 @@ impl Commands {
         }
     }
 
+    /// Receives a variant of `Commands` and, if it contains the `liar_prior` field, checks if the
+    /// value of `liar_prior` is within the range [0.0, 1.0].
+    fn validate_liar_prior(&self) -> Result<(), String> {
+        match self.has_liar_prior() {
+            Some(liar_prior) if (0.0..=1.0).contains(&liar_prior) => Ok(()),
+            Some(_) => Err(
+                "[!] error: --liar-prior must be within the range of 0.0 to 1.0 (inclusive)\n"
+                    .to_string(),
+            ),
+            None => Ok(()),
+        }
+    }
+
     /// Receives a variant of `Commands` and, if it contains the `value` and `max_value` fields,
     /// checks if their values satisfy the program's constraints:
     ///
@@ -143,7 +496,7 @@ impl Commands {
     ///
     /// * `max_value` cannot be equal to 1, since this would cause both `value`
     ///   (which cannot be 0) and `liar_value` (which cannot be 0 or equal to `value`) to
-    ///    be equal to 1.
+    ///   be equal to 1.
     fn validate_agent_values(&self) -> Result<(), String> {
         let (value, max_value) = match self.has_agent_values() {
             Some((value, max_value)) => (value, max_value),
@@ -187,6 +540,65 @@ impl Commands {
             None => Ok(()),
         }
     }
+
+    /// Receives a variant of `Commands` and, if it contains the `coalition_size` field,
+    /// checks if `coalition_size` > 0
+    fn validate_coalition_size(&self) -> Result<(), String> {
+        match self.has_coalition_size() {
+            Some(0) => Err("[!] error: --coalition-size must be greater than 0\n".to_owned()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Receives a variant of `Commands` and, if it contains the `max_frame_size` field,
+    /// checks if `max_frame_size` > 0
+    fn validate_max_frame_size(&self) -> Result<(), String> {
+        match self.has_max_frame_size() {
+            Some(max_frame_size) if max_frame_size > 0 => Ok(()),
+            Some(_) => Err("[!] error: --max-frame-size must be greater than 0\n".to_owned()),
+            None => Ok(()),
+        }
+    }
+
+    /// Receives a variant of `Commands` and, if it contains the `agent_timeout` and
+    /// `round_timeout` fields, checks if both are > 0
+    fn validate_timeouts(&self) -> Result<(), String> {
+        match self.has_timeouts() {
+            Some((0, _)) => Err("[!] error: --agent-timeout must be greater than 0\n".to_owned()),
+            Some((_, 0)) => Err("[!] error: --round-timeout must be greater than 0\n".to_owned()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Receives a variant of `Commands` and, if it contains the `probability` field, checks if the
+    /// value of `probability` is within the range [0.0, 1.0].
+    fn validate_probability(&self) -> Result<(), String> {
+        match self.has_probability() {
+            Some(probability) if (0.0..=1.0).contains(&probability) => Ok(()),
+            Some(_) => Err(
+                "[!] error: --probability must be within the range of 0.0 to 1.0 (inclusive)\n"
+                    .to_string(),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Receives a variant of `Commands` and, if it contains the `fanout` field, checks if
+    /// `fanout` > 0
+    fn validate_fanout(&self) -> Result<(), String> {
+        match self.has_fanout() {
+            Some(0) => Err("[!] error: --fanout must be greater than 0\n".to_owned()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Receives a variant of `Commands` and, if it contains the `ttl` field, checks if `ttl` > 0
+    fn validate_ttl(&self) -> Result<(), String> {
+        match self.has_ttl() {
+            Some(0) => Err("[!] error: --ttl must be greater than 0\n".to_owned()),
+            _ => Ok(()),
+        }
+    }
 }
 
 // ******************************************************************************************
@@ -205,6 +617,13 @@ mod tests {
             num_agents: 5,
             liar_ratio: 2.0,
             tamper_chance: 0.5,
+            equivocate: false,
+            liar_strategy: vec![],
+            coalition_size: 1,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            encrypted: false,
+            codec: Codec::Bincode,
+            access_policy: AccessPolicy::KnownPeers,
         };
         assert!(case1.validate_liar_ratio().is_err());
 
@@ -214,10 +633,45 @@ mod tests {
             num_agents: 5,
             liar_ratio: -0.1,
             tamper_chance: 0.5,
+            equivocate: false,
+            liar_strategy: vec![],
+            coalition_size: 1,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            encrypted: false,
+            codec: Codec::Bincode,
+            access_policy: AccessPolicy::KnownPeers,
         };
         assert!(case2.validate_liar_ratio().is_err());
     }
 
+    #[test]
+    fn rejects_invalid_liar_prior() {
+        let case1 = Commands::Play {
+            agent_timeout: DEFAULT_AGENT_TIMEOUT_MS,
+            round_timeout: DEFAULT_ROUND_TIMEOUT_MS,
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_BACKOFF_MS,
+            strategy: InferenceKind::Bayesian,
+            fault_bound: None,
+            adaptive: false,
+            liar_prior: Some(1.5),
+        };
+        assert!(case1.validate_liar_prior().is_err());
+
+        // Should be Ok, the proportion of liars in the game is used instead
+        let case2 = Commands::Play {
+            agent_timeout: DEFAULT_AGENT_TIMEOUT_MS,
+            round_timeout: DEFAULT_ROUND_TIMEOUT_MS,
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_BACKOFF_MS,
+            strategy: InferenceKind::Bayesian,
+            fault_bound: None,
+            adaptive: false,
+            liar_prior: None,
+        };
+        assert!(case2.validate_liar_prior().is_ok());
+    }
+
     #[test]
     fn rejects_invalid_num_agents() {
         let command = Commands::Start {
@@ -226,6 +680,13 @@ mod tests {
             num_agents: 0,
             liar_ratio: 0.5,
             tamper_chance: 0.5,
+            equivocate: false,
+            liar_strategy: vec![],
+            coalition_size: 1,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            encrypted: false,
+            codec: Codec::Bincode,
+            access_policy: AccessPolicy::KnownPeers,
         };
         assert!(command.validate_num_agents().is_err());
     }
@@ -239,6 +700,13 @@ mod tests {
             num_agents: 5,
             liar_ratio: 0.5,
             tamper_chance: 0.5,
+            equivocate: false,
+            liar_strategy: vec![],
+            coalition_size: 1,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            encrypted: false,
+            codec: Codec::Bincode,
+            access_policy: AccessPolicy::KnownPeers,
         };
         assert!(case1.validate_agent_values().is_err());
 
@@ -249,6 +717,13 @@ mod tests {
             num_agents: 5,
             liar_ratio: 0.5,
             tamper_chance: 0.5,
+            equivocate: false,
+            liar_strategy: vec![],
+            coalition_size: 1,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            encrypted: false,
+            codec: Codec::Bincode,
+            access_policy: AccessPolicy::KnownPeers,
         };
         assert!(case2.validate_agent_values().is_err());
 
@@ -259,6 +734,13 @@ mod tests {
             num_agents: 5,
             liar_ratio: 0.5,
             tamper_chance: 0.5,
+            equivocate: false,
+            liar_strategy: vec![],
+            coalition_size: 1,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            encrypted: false,
+            codec: Codec::Bincode,
+            access_policy: AccessPolicy::KnownPeers,
         };
         assert!(case3.validate_agent_values().is_err());
     }
@@ -272,6 +754,13 @@ mod tests {
             num_agents: 5,
             liar_ratio: 0.5,
             tamper_chance: 1.001,
+            equivocate: false,
+            liar_strategy: vec![],
+            coalition_size: 1,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            encrypted: false,
+            codec: Codec::Bincode,
+            access_policy: AccessPolicy::KnownPeers,
         };
         assert!(case1.validate_agent_values().is_err());
 
@@ -282,7 +771,118 @@ mod tests {
             num_agents: 5,
             liar_ratio: 0.5,
             tamper_chance: -0.2,
+            equivocate: false,
+            liar_strategy: vec![],
+            coalition_size: 1,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            encrypted: false,
+            codec: Codec::Bincode,
+            access_policy: AccessPolicy::KnownPeers,
         };
         assert!(case2.validate_agent_values().is_err());
     }
+
+    #[test]
+    fn rejects_invalid_timeouts() {
+        // Should throw an error because agent_timeout = 0
+        let case1 = Commands::Play {
+            agent_timeout: 0,
+            round_timeout: DEFAULT_ROUND_TIMEOUT_MS,
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_BACKOFF_MS,
+            strategy: InferenceKind::Plurality,
+            fault_bound: None,
+            adaptive: false,
+            liar_prior: None,
+        };
+        assert!(case1.validate_timeouts().is_err());
+
+        // Should throw an error because round_timeout = 0
+        let case2 = Commands::PlayExpert {
+            num_agents: 2,
+            liar_ratio: 0.5,
+            agent_timeout: DEFAULT_AGENT_TIMEOUT_MS,
+            round_timeout: 0,
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_BACKOFF_MS,
+            strategy: InferenceKind::Plurality,
+            fault_bound: None,
+            adaptive: false,
+            liar_prior: None,
+            ttl: DEFAULT_RELAY_TTL,
+            by_reputation: false,
+        };
+        assert!(case2.validate_timeouts().is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_ttl() {
+        // Should throw an error because the request could not even reach the first relay
+        let case1 = Commands::PlayExpert {
+            num_agents: 2,
+            liar_ratio: 0.5,
+            agent_timeout: DEFAULT_AGENT_TIMEOUT_MS,
+            round_timeout: DEFAULT_ROUND_TIMEOUT_MS,
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_BACKOFF_MS,
+            strategy: InferenceKind::Plurality,
+            fault_bound: None,
+            adaptive: false,
+            liar_prior: None,
+            ttl: 0,
+            by_reputation: false,
+        };
+        assert!(case1.validate_ttl().is_err());
+
+        // Should be Ok, the relays queried by the client do not relay any further
+        let case2 = Commands::PlayExpert {
+            num_agents: 2,
+            liar_ratio: 0.5,
+            agent_timeout: DEFAULT_AGENT_TIMEOUT_MS,
+            round_timeout: DEFAULT_ROUND_TIMEOUT_MS,
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_BACKOFF_MS,
+            strategy: InferenceKind::Plurality,
+            fault_bound: None,
+            adaptive: false,
+            liar_prior: None,
+            ttl: 1,
+            by_reputation: false,
+        };
+        assert!(case2.validate_ttl().is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_probability() {
+        let case1 = Commands::Topology {
+            kind: Some(TopologyKind::ErdosRenyi),
+            degree: DEFAULT_DEGREE,
+            probability: 1.5,
+        };
+        assert!(case1.validate_probability().is_err());
+
+        // Showing the topology is always valid
+        let case2 = Commands::Topology {
+            kind: None,
+            degree: DEFAULT_DEGREE,
+            probability: DEFAULT_LINK_PROBABILITY,
+        };
+        assert!(case2.validate_probability().is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_fanout() {
+        let case1 = Commands::Gossip {
+            interval: DEFAULT_GOSSIP_INTERVAL_MS,
+            fanout: 0,
+        };
+        assert!(case1.validate_fanout().is_err());
+
+        // Should be Ok, an interval of 0 stops gossip
+        let case2 = Commands::Gossip {
+            interval: 0,
+            fanout: DEFAULT_GOSSIP_FANOUT,
+        };
+        assert!(case2.validate_fanout().is_ok());
+    }
 }
@@ -0,0 +1,639 @@
+use anyhow::anyhow;
+use bincode::{deserialize, serialize};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::spawn;
+use tokio::sync::{oneshot, Mutex as AsyncMutex};
+use tokio::task::JoinHandle;
+
+use crate::access::AccessPolicy;
+use crate::codec::Codec;
+use crate::handshake::{initiate_handshake, Capabilities, Session};
+use crate::keys::Keys;
+use crate::network_utils::*;
+use crate::secure_channel::{initiate_secure_channel, Opener, Sealer, SecureChannel, Transport};
+
+/// Represents the options, chosen when a game is started, that control how the game's client and
+/// agents communicate.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NetworkOptions {
+    /// The limits enforced on frames exchanged between the game's client and agents.
+    pub frame_config: FrameConfig,
+    /// Whether connections between the game's client and agents, or between agents, are encrypted.
+    pub transport: Transport,
+    /// The codec requested when opening a connection. Incoming connections accept any codec.
+    pub codec: Codec,
+    /// The participants agents are willing to answer queries from.
+    pub access_policy: AccessPolicy,
+}
+
+impl NetworkOptions {
+    /// Returns a new instance of `NetworkOptions` limiting frames to `max_frame_size` bytes, using
+    /// encrypted connections if `encrypted` is `true`, requesting `codec` on new connections and
+    /// having agents answer queries according to `access_policy`.
+    pub fn new(
+        max_frame_size: usize,
+        encrypted: bool,
+        codec: Codec,
+        access_policy: AccessPolicy,
+    ) -> Self {
+        NetworkOptions {
+            frame_config: FrameConfig::default().with_max_frame_size(max_frame_size),
+            transport: if encrypted {
+                Transport::Encrypted
+            } else {
+                Transport::Plaintext
+            },
+            codec,
+            access_policy,
+        }
+    }
+
+    /// Returns the capabilities offered when opening a connection.
+    pub fn initiator_capabilities(&self) -> Capabilities {
+        self.transport.offered_capabilities() | self.codec.capability()
+    }
+
+    /// Returns the capabilities offered when accepting a connection.
+    pub fn acceptor_capabilities(&self) -> Capabilities {
+        self.transport.offered_capabilities() | Codec::supported_capabilities()
+    }
+
+    /// Returns the capabilities that the remote end of a connection must support.
+    pub fn required_capabilities(&self) -> Capabilities {
+        self.transport.required_capabilities()
+    }
+}
+
+/// Encapsulates a serialized `Packet` sent over a persistent connection.
+///
+/// Many requests may be in flight on the same connection at once, therefore every request is
+/// tagged with a `request_id` which must be echoed by the `Frame` carrying its reply.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Frame {
+    /// The ID of the request this frame belongs to.
+    pub request_id: u64,
+    /// A serialized `Packet`.
+    pub packet: Vec<u8>,
+}
+
+impl Frame {
+    /// Builds a new instance of `Frame` containing `request_id` and `packet` and returns it
+    /// serialized into binary format.
+    pub fn build_frame(request_id: u64, packet: &[u8]) -> Result<Vec<u8>, bincode::Error> {
+        serialize(&Frame {
+            request_id,
+            packet: packet.to_vec(),
+        })
+    }
+
+    /// Receives a byte array `data`, expected to be in binary format, and attempts to deserialize
+    /// it into an instance of `Frame`. Returns `bincode::Error` if the format of `data` is invalid.
+    pub fn unpack(data: &[u8]) -> Result<Self, bincode::Error> {
+        deserialize(data)
+    }
+}
+
+/// Wraps `packet` in a `Frame` tagged with `request_id` and writes it to `socket`.
+pub async fn send_frame<W>(
+    request_id: u64,
+    packet: &[u8],
+    socket: &mut W,
+    config: &FrameConfig,
+) -> Result<(), FrameError>
+where
+    W: AsyncWrite + Unpin,
+{
+    let frame = Frame::build_frame(request_id, packet).map_err(FrameError::Malformed)?;
+    send_packet(&frame, socket, config).await
+}
+
+/// Waits for the next `Frame` on the persistent connection `socket`. Returns
+/// `FrameError::PeerClosed` if the peer closed the connection between frames and
+/// `FrameError::Malformed` if the frame could not be decoded.
+pub async fn recv_frame<R>(socket: &mut R, config: &FrameConfig) -> Result<Frame, FrameError>
+where
+    R: AsyncRead + Unpin,
+{
+    let frame = wait_packet(socket, config).await?;
+    Frame::unpack(&frame).map_err(FrameError::Malformed)
+}
+
+/// Writes frames to the write half of a connection, encrypting them if the connection is protected
+/// by a secure channel.
+#[derive(Debug)]
+pub struct FrameWriter<W> {
+    socket: W,
+    sealer: Option<Sealer>,
+}
+
+impl<W> FrameWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    /// Wraps `packet` in a `Frame` tagged with `request_id`, encrypts it if necessary and writes
+    /// it to the connection.
+    pub async fn send_frame(
+        &mut self,
+        request_id: u64,
+        packet: &[u8],
+        config: &FrameConfig,
+    ) -> Result<(), FrameError> {
+        match &mut self.sealer {
+            Some(sealer) => {
+                let frame =
+                    Frame::build_frame(request_id, packet).map_err(FrameError::Malformed)?;
+                send_packet(&sealer.seal(&frame)?, &mut self.socket, config).await
+            }
+            None => send_frame(request_id, packet, &mut self.socket, config).await,
+        }
+    }
+}
+
+/// Reads frames from the read half of a connection, decrypting them if the connection is protected
+/// by a secure channel.
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    socket: R,
+    opener: Option<Opener>,
+}
+
+impl<R> FrameReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Waits for the next `Frame` on the connection, decrypting it if necessary.
+    pub async fn recv_frame(&mut self, config: &FrameConfig) -> Result<Frame, FrameError> {
+        match &mut self.opener {
+            Some(opener) => {
+                let frame = opener.open(wait_packet(&mut self.socket, config).await?)?;
+                Frame::unpack(&frame).map_err(FrameError::Malformed)
+            }
+            None => recv_frame(&mut self.socket, config).await,
+        }
+    }
+}
+
+/// Splits `socket` into a `FrameReader` and a `FrameWriter`. Frames are encrypted with the keys of
+/// `channel`, if there is one, and sent in plaintext otherwise.
+pub fn split_frames(
+    socket: TcpStream,
+    channel: Option<SecureChannel>,
+) -> (FrameReader<OwnedReadHalf>, FrameWriter<OwnedWriteHalf>) {
+    let (reader, writer) = socket.into_split();
+    let (sealer, opener) = match channel {
+        Some(channel) => {
+            let (sealer, opener) = channel.split();
+            (Some(sealer), Some(opener))
+        }
+        None => (None, None),
+    };
+
+    (
+        FrameReader {
+            socket: reader,
+            opener,
+        },
+        FrameWriter {
+            socket: writer,
+            sealer,
+        },
+    )
+}
+
+/// Replies that are still awaited on a connection, indexed by request ID.
+type PendingReplies = Arc<Mutex<HashMap<u64, oneshot::Sender<Vec<u8>>>>>;
+
+/// Unregisters a request from the replies pending on a connection when dropped, so that requests
+/// which fail or are cancelled before their reply arrives do not linger in the map.
+struct PendingRequest<'a> {
+    pending: &'a PendingReplies,
+    request_id: u64,
+}
+
+impl Drop for PendingRequest<'_> {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().remove(&self.request_id);
+    }
+}
+
+/// Represents a persistent, multiplexed connection to an agent.
+///
+/// A `Connection` performs the protocol handshake once, when opened, and can then carry any number
+/// of concurrent requests. Replies are read by a background task and routed back to the request
+/// with the matching request ID.
+#[derive(Debug)]
+pub struct Connection {
+    /// The network address of the remote agent.
+    address: String,
+    /// The network port of the remote agent.
+    port: usize,
+    /// The protocol version and capabilities negotiated during the handshake.
+    session: Session,
+    /// The limits enforced when reading and writing frames.
+    frame_config: FrameConfig,
+    /// The write half of the underlying TcpStream, shared by all requests.
+    writer: AsyncMutex<FrameWriter<OwnedWriteHalf>>,
+    /// Senders used to deliver replies to the requests that are awaiting them.
+    pending: PendingReplies,
+    /// The ID to be assigned to the next request sent over this connection.
+    next_request_id: AtomicU64,
+    /// A flag to indicate whether the remote end has closed the connection.
+    closed: Arc<AtomicBool>,
+    /// The background task reading replies from the connection.
+    reader_handle: JoinHandle<()>,
+}
+
+impl Connection {
+    /// Connects to `address`:`port`, performs the protocol handshake and spawns a task to read
+    /// replies from the new connection. Every frame is subject to the limits in `network`.
+    /// If the transport in `network` is encrypted, a secure channel is established in which the
+    /// remote end must prove ownership of `public_key` and the local end proves ownership of
+    /// `identity`.
+    pub async fn open(
+        address: &str,
+        port: usize,
+        public_key: &str,
+        network: &NetworkOptions,
+        identity: &Keys,
+    ) -> anyhow::Result<Self> {
+        let frame_config = network.frame_config;
+        let mut socket = connect(address, port).await?;
+        let session = initiate_handshake(
+            &mut socket,
+            &frame_config,
+            network.initiator_capabilities(),
+            network.required_capabilities(),
+        )
+        .await?;
+
+        let channel = if session
+            .get_capabilities()
+            .contains(Capabilities::ENCRYPTION)
+        {
+            Some(initiate_secure_channel(&mut socket, &frame_config, identity, public_key).await?)
+        } else {
+            None
+        };
+
+        let (reader, writer) = split_frames(socket, channel);
+        let pending: PendingReplies = Arc::new(Mutex::new(HashMap::new()));
+        let closed = Arc::new(AtomicBool::new(false));
+
+        let reader_handle = spawn(Self::read_replies(
+            reader,
+            frame_config,
+            pending.clone(),
+            closed.clone(),
+        ));
+
+        Ok(Connection {
+            address: address.to_owned(),
+            port,
+            session,
+            frame_config,
+            writer: AsyncMutex::new(writer),
+            pending,
+            next_request_id: AtomicU64::new(1),
+            closed,
+            reader_handle,
+        })
+    }
+
+    /// Reads frames from `reader` until the connection is closed or a frame violates the limits
+    /// in `frame_config`, delivering each one to the pending request with a matching ID. Frames
+    /// that match no pending request are discarded.
+    async fn read_replies(
+        mut reader: FrameReader<OwnedReadHalf>,
+        frame_config: FrameConfig,
+        pending: PendingReplies,
+        closed: Arc<AtomicBool>,
+    ) {
+        while let Ok(frame) = reader.recv_frame(&frame_config).await {
+            let sender = pending.lock().unwrap().remove(&frame.request_id);
+            if let Some(sender) = sender {
+                let _ = sender.send(frame.packet);
+            }
+        }
+
+        // Dropping the senders of every pending request wakes them up with an error
+        closed.store(true, Ordering::SeqCst);
+        pending.lock().unwrap().clear();
+    }
+
+    /// Returns the protocol version and capabilities negotiated for this connection.
+    pub fn get_session(&self) -> Session {
+        self.session
+    }
+
+    /// Returns the codec negotiated for the packets exchanged over this connection.
+    pub fn get_codec(&self) -> Codec {
+        self.session.get_codec()
+    }
+
+    /// Returns `true` if the remote end has closed the connection.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Sends `packet` tagged with a new request ID and waits for the reply carrying the same ID.
+    /// Returns the serialized reply packet if successful and `anyhow::Error` otherwise. The
+    /// returned future may be safely dropped, e.g, when a timeout expires, before it completes.
+    pub async fn request(&self, packet: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let (reply_transmitter, reply_receiver) = oneshot::channel();
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(request_id, reply_transmitter);
+        let _pending_request = PendingRequest {
+            pending: &self.pending,
+            request_id,
+        };
+
+        // If the connection was closed before the request was registered, no reply will ever
+        // be delivered to it
+        if self.is_closed() {
+            return Err(self.closed_error());
+        }
+
+        self.write(request_id, packet).await?;
+
+        reply_receiver.await.map_err(|_| self.closed_error())
+    }
+
+    /// Sends `packet` tagged with a new request ID without waiting for a reply.
+    pub async fn send(&self, packet: &[u8]) -> anyhow::Result<()> {
+        let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        self.write(request_id, packet).await
+    }
+
+    /// Writes `packet` to the connection, tagged with `request_id`.
+    async fn write(&self, request_id: u64, packet: &[u8]) -> anyhow::Result<()> {
+        let mut writer = self.writer.lock().await;
+        writer
+            .send_frame(request_id, packet, &self.frame_config)
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "[!] error: unable to write to {}:{} - {}\n",
+                    self.address,
+                    self.port,
+                    e
+                )
+            })
+    }
+
+    /// Returns the error reported to requests that cannot be answered because the connection closed.
+    fn closed_error(&self) -> anyhow::Error {
+        anyhow!(
+            "[!] error: connection to {}:{} was closed before a reply was received\n",
+            self.address,
+            self.port
+        )
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.reader_handle.abort();
+    }
+}
+
+/// Open connections, indexed by the address and port of the remote end.
+type PooledConnections = HashMap<(String, usize), Arc<Connection>>;
+
+/// Represents a pool of persistent connections, indexed by the address and port of the remote end.
+///
+/// Cloning a `ConnectionPool` is cheap and every clone shares the same connections, which allows
+/// connections to be reused across rounds of the game.
+#[derive(Debug, Clone)]
+pub struct ConnectionPool {
+    connections: Arc<Mutex<PooledConnections>>,
+    /// The limits, transport and codec of every connection opened by the pool.
+    network: NetworkOptions,
+    /// The key pair used to prove the local identity on encrypted connections.
+    identity: Keys,
+}
+
+impl ConnectionPool {
+    /// Returns a new, empty instance of `ConnectionPool` whose connections are opened according
+    /// to `network`. On encrypted connections, the local end proves ownership of `identity`.
+    pub fn new(network: NetworkOptions, identity: Keys) -> Self {
+        ConnectionPool {
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            network,
+            identity,
+        }
+    }
+
+    /// Returns an open connection to `address`:`port`, whose owner holds `public_key`. A pooled
+    /// connection is reused if one is available, otherwise a new connection is opened and added
+    /// to the pool.
+    pub async fn get(
+        &self,
+        address: &str,
+        port: usize,
+        public_key: &str,
+    ) -> anyhow::Result<Arc<Connection>> {
+        let key = (address.to_owned(), port);
+
+        let pooled = self.connections.lock().unwrap().get(&key).cloned();
+        if let Some(connection) = pooled {
+            if !connection.is_closed() {
+                return Ok(connection);
+            }
+        }
+
+        let connection = Arc::new(
+            Connection::open(address, port, public_key, &self.network, &self.identity).await?,
+        );
+        self.connections
+            .lock()
+            .unwrap()
+            .insert(key, connection.clone());
+
+        Ok(connection)
+    }
+
+    /// Removes the connection to `address`:`port` from the pool, if there is one. The connection
+    /// is closed once every request using it has completed.
+    pub fn remove(&self, address: &str, port: usize) {
+        self.connections
+            .lock()
+            .unwrap()
+            .remove(&(address.to_owned(), port));
+    }
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handshake::accept_handshake;
+    use crate::secure_channel::accept_secure_channel;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_build_frame_ok() {
+        let frame = Frame::build_frame(7, &[1, 2, 3]).unwrap();
+        assert_eq!(
+            Frame::unpack(&frame).unwrap(),
+            Frame {
+                request_id: 7,
+                packet: vec![1, 2, 3]
+            }
+        );
+    }
+
+    /// Accepts a single connection on `listener` and echoes the first two requests it receives in
+    /// reverse order, over a secure channel if the transport in `network` is encrypted.
+    async fn echo_reversed(listener: TcpListener, network: NetworkOptions, identity: Keys) {
+        let config = network.frame_config;
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let session = accept_handshake(
+            &mut socket,
+            &config,
+            network.acceptor_capabilities(),
+            network.required_capabilities(),
+        )
+        .await
+        .unwrap();
+
+        let channel = if session
+            .get_capabilities()
+            .contains(Capabilities::ENCRYPTION)
+        {
+            Some(
+                accept_secure_channel(&mut socket, &config, &identity)
+                    .await
+                    .unwrap(),
+            )
+        } else {
+            None
+        };
+        let (mut reader, mut writer) = split_frames(socket, channel);
+
+        let first = reader.recv_frame(&config).await.unwrap();
+        let second = reader.recv_frame(&config).await.unwrap();
+        writer
+            .send_frame(second.request_id, &second.packet, &config)
+            .await
+            .unwrap();
+        writer
+            .send_frame(first.request_id, &first.packet, &config)
+            .await
+            .unwrap();
+
+        // Keep the connection open until the client closes it
+        let _ = reader.recv_frame(&config).await;
+    }
+
+    // Replies sent out of order must be delivered to the requests with the matching IDs
+    #[tokio::test]
+    async fn test_concurrent_requests() {
+        for (transport, codec) in [
+            (Transport::Plaintext, Codec::Bincode),
+            (Transport::Encrypted, Codec::Json),
+            (Transport::Plaintext, Codec::Cbor),
+        ] {
+            let network = NetworkOptions {
+                transport,
+                codec,
+                ..Default::default()
+            };
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port().into();
+            let agent_keys = Keys::new_key_pair();
+            let agent_pubkey = agent_keys.get_public_key().to_owned();
+            spawn(echo_reversed(listener, network, agent_keys));
+
+            let pool = ConnectionPool::new(network, Keys::new_key_pair());
+            let connection = pool.get("127.0.0.1", port, &agent_pubkey).await.unwrap();
+            assert_eq!(
+                connection
+                    .get_session()
+                    .get_capabilities()
+                    .contains(Capabilities::ENCRYPTION),
+                transport == Transport::Encrypted
+            );
+            assert_eq!(connection.get_codec(), codec);
+
+            let (first, second) = tokio::join!(connection.request(&[1]), connection.request(&[2]));
+            assert_eq!(first.unwrap(), vec![1]);
+            assert_eq!(second.unwrap(), vec![2]);
+
+            // The same connection is handed out while it remains open
+            let pooled = pool.get("127.0.0.1", port, &agent_pubkey).await.unwrap();
+            assert!(Arc::ptr_eq(&connection, &pooled));
+        }
+    }
+
+    // An encrypted connection must not be opened to an agent holding a different key
+    #[tokio::test]
+    async fn test_encrypted_connection_wrong_identity() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port().into();
+        let network = NetworkOptions {
+            transport: Transport::Encrypted,
+            ..Default::default()
+        };
+        spawn(echo_reversed(listener, network, Keys::new_key_pair()));
+
+        let pool = ConnectionPool::new(network, Keys::new_key_pair());
+        let expected = Keys::new_key_pair();
+        assert!(pool
+            .get("127.0.0.1", port, expected.get_public_key())
+            .await
+            .is_err());
+    }
+
+    // A request abandoned before its reply arrives must not remain registered on the connection
+    #[tokio::test]
+    async fn test_cancelled_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        spawn(async move {
+            let config = FrameConfig::default();
+            let (mut socket, _) = listener.accept().await.unwrap();
+            accept_handshake(
+                &mut socket,
+                &config,
+                Capabilities::supported(),
+                Capabilities::NONE,
+            )
+            .await
+            .unwrap();
+
+            // Never reply to any request
+            while recv_frame(&mut socket, &config).await.is_ok() {}
+        });
+
+        let keys = Keys::new_key_pair();
+        let connection = Connection::open(
+            "127.0.0.1",
+            address.port().into(),
+            keys.get_public_key(),
+            &NetworkOptions::default(),
+            &keys,
+        )
+        .await
+        .unwrap();
+
+        let request = tokio::time::timeout(
+            std::time::Duration::from_millis(50),
+            connection.request(&[1]),
+        );
+        assert!(request.await.is_err());
+        assert!(connection.pending.lock().unwrap().is_empty());
+    }
+}
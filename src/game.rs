@@ -1,14 +1,26 @@
-use crate::agent::{Agent, AgentStatus};
+use crate::agent::{Agent, AgentStatus, LiarOptions};
 use crate::agent_config::AgentConfig;
-use crate::client::Client;
+use crate::behavior::{random_liar_value, RelayStrategy};
+use crate::client::{Client, RoundPolicy, RoundReport};
+use crate::coalition::Coalition;
+use crate::connection::NetworkOptions;
+use crate::gossip::GossipOptions;
+use crate::inference::{Confidence, InferenceOptions, InferenceStrategy, Vote};
+use crate::reputation::{ReputationBook, REPUTATION_FILE};
+use crate::secure_channel::Transport;
+use crate::suspects::{EquivocationProof, TamperEvidence};
+use crate::topology::{Topology, TopologyKind};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
+use std::collections::HashMap;
 use std::io::{self, Write};
+use std::time::Instant;
 use text_colorizer::Colorize;
 use tokio::spawn;
 use tokio::sync::oneshot;
+
 /// Represents the configuration for a game of Liars Lie.
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub struct Game {
     /// Represents the state of the game. Should be set to `false` if the game is
     /// not ready to be played.
@@ -17,13 +29,32 @@ pub struct Game {
     value: Option<u64>,
     /// The maximum value that can be assigned to a liar.
     max_value: Option<u64>,
-    /// How likely it is for a liar to tamper with a message when forwarding it.
-    tamper_chance: Option<f32>,
+    /// How liars misbehave, e.g, how likely it is for a liar to tamper with a message when
+    /// forwarding it.
+    liar_options: Option<LiarOptions>,
+    /// The coalitions of liars that share a false value, ordered by coalition ID.
+    coalitions: Vec<Coalition>,
+    /// Controls how the game's client and agents communicate.
+    network: NetworkOptions,
     /// A vector to store instances of `Agent` that are deployed and ready
     /// to participate in a round of the game.
     active_agents: Vec<Agent>,
+    /// Determines which agents in `Game.active_agents` may connect to which.
+    topology: Topology,
+    /// How agents gossip their values, or None if gossip is disabled.
+    gossip: Option<GossipOptions>,
+    /// When gossip was enabled, along with the number of messages sent in the game until then.
+    gossip_started: Option<(Instant, usize)>,
     /// The game's client. Used to communicate with agents.
     game_client: Client,
+    /// The reputation of every agent the client has seen, indexed by public key.
+    reputation: ReputationBook,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
@@ -32,9 +63,15 @@ impl Game {
             is_ready: false,
             value: None,
             max_value: None,
-            tamper_chance: None,
+            liar_options: None,
+            coalitions: Vec::new(),
+            network: NetworkOptions::default(),
             active_agents: Vec::new(),
+            topology: Topology::default(),
+            gossip: None,
+            gossip_started: None,
             game_client: Client::new(),
+            reputation: ReputationBook::default(),
         }
     }
 
@@ -52,10 +89,10 @@ impl Game {
     }
 
     fn print_ready(&self) {
-        if self.value == None
-            || self.max_value == None
-            || self.active_agents.len() == 0
-            || self.tamper_chance == None
+        if self.value.is_none()
+            || self.max_value.is_none()
+            || self.active_agents.is_empty()
+            || self.liar_options.is_none()
         {
             panic!("Game cannot be started! Missing game values or active agents.\n");
         }
@@ -63,7 +100,7 @@ impl Game {
     }
 
     /// Prints the ID's of the agents that compose `expert_subset`.
-    fn print_expert_subset(expert_subset: &Vec<AgentConfig>) {
+    fn print_expert_subset(expert_subset: &[AgentConfig]) {
         let subset_ids: Vec<String> = expert_subset
             .iter()
             .map(|agent| agent.get_id().to_string())
@@ -75,6 +112,58 @@ impl Game {
         )
     }
 
+    /// Prints the packets rejected by the client during an expert round, along with the agent that
+    /// relayed each of them and its chain of custody.
+    fn print_tampering(tampering: &[TamperEvidence]) {
+        if tampering.is_empty() {
+            return;
+        }
+        println!("{}", "[!] Tampering detected:".bold());
+        for evidence in tampering {
+            println!("    {}", evidence);
+        }
+        println!();
+    }
+
+    /// Prints a proof for every agent that signed conflicting values during an expert round. The
+    /// votes of these agents are left out of the network value.
+    fn print_equivocations(equivocations: &[EquivocationProof]) {
+        if equivocations.is_empty() {
+            return;
+        }
+        println!(
+            "{}",
+            "[!] Equivocation detected, ignoring the votes of:".bold()
+        );
+        for proof in equivocations {
+            println!("    {}", proof);
+        }
+        println!();
+    }
+
+    /// Prints the agents outside the expert subset that the client attempted to contact directly
+    /// during an expert round, given as (agent ID, attempts) in `violations`.
+    fn print_lockout_violations(violations: &[(usize, usize)]) {
+        if violations.is_empty() {
+            println!(
+                "{}\n",
+                "[+] The client only contacted agents in the expert subset.".bold()
+            );
+            return;
+        }
+        println!(
+            "{}",
+            "[!] The client attempted to contact agents outside the expert subset:".bold()
+        );
+        for (agent_id, attempts) in violations {
+            println!(
+                "    Agent {} refused {} direct request(s)",
+                agent_id, attempts
+            );
+        }
+        println!();
+    }
+
     /// Resets all the fields of `Game` to their default values as specified by `Game::new()`.
     fn reset_game(&mut self) {
         *self = Game::new();
@@ -126,29 +215,86 @@ impl Game {
             self.active_agents.push(Agent::new_honest(
                 value,
                 self.game_client.get_keys().get_public_key().to_owned(),
+                self.network,
             ));
         }
     }
 
     /// Creates `num_liars` instances of liars and push those instances
-    /// into `Game.active_agents`.
-    fn add_liar_agents(&mut self, value: u64, max_value: u64, num_liars: u16, tamper_chance: f32) {
-        for _ in 1..=num_liars {
-            self.active_agents.push(Agent::new_liar(
+    /// into `Game.active_agents`. Liars misbehave according to `liar`, and are assigned its
+    /// relay strategies in turn. If `coalition` is the ID of one of `Game.coalitions`, every liar
+    /// joins it. Otherwise, liars fill the last coalition up to `liar.coalition_size` members and
+    /// then form new coalitions of that size.
+    fn add_liar_agents(
+        &mut self,
+        value: u64,
+        max_value: u64,
+        num_liars: u16,
+        liar: &LiarOptions,
+        coalition: Option<usize>,
+    ) {
+        let coalition_size = usize::from(liar.coalition_size.max(1));
+
+        for index in 0..usize::from(num_liars) {
+            let joined = match coalition {
+                Some(coalition_id) => self
+                    .coalitions
+                    .iter()
+                    .position(|coalition| coalition.get_id() == coalition_id),
+                None if coalition_size > 1 => {
+                    // Liars added by an earlier call may have left the last coalition partial
+                    let is_full = self
+                        .coalitions
+                        .last()
+                        .is_none_or(|coalition| coalition.get_members().len() >= coalition_size);
+                    if is_full {
+                        let coalition_id = self.coalitions.len() + 1;
+                        let shared_value = random_liar_value(value, max_value);
+                        self.coalitions
+                            .push(Coalition::new(coalition_id, shared_value));
+                    }
+                    Some(self.coalitions.len() - 1)
+                }
+                None => None,
+            };
+
+            let agent = Agent::new_liar(
                 value,
                 max_value,
+                joined.map(|position| self.coalitions[position].get_value()),
                 self.game_client.get_keys().get_public_key().to_owned(),
-                tamper_chance,
-            ));
+                liar,
+                liar.get_strategy(index),
+                self.network,
+            );
+            if let Some(position) = joined {
+                self.coalitions[position].add_member(agent.get_id());
+            }
+            self.active_agents.push(agent);
+        }
+    }
+
+    /// Prints every coalition of liars in `coalitions`, if any.
+    fn print_coalitions(coalitions: &[Coalition]) {
+        if coalitions.is_empty() {
+            return;
         }
+        println!(
+            "{}",
+            "[+] Liars collude in the following coalitions:".bold()
+        );
+        for coalition in coalitions {
+            println!("    {}", coalition);
+        }
+        println!();
     }
 
     /// Sets the `Game.value` and `Game.max_value` fields to be used as a reference
     /// when creating new agents. Also sets the `Game.is_ready` to `true`.
-    fn init_game(&mut self, value: u64, max_value: u64, tamper_chance: f32) {
+    fn init_game(&mut self, value: u64, max_value: u64, liar: LiarOptions) {
         self.set_value(value);
         self.set_max_value(max_value);
-        self.set_tamper_chance(tamper_chance);
+        self.set_liar_options(liar);
         self.set_ready();
     }
 
@@ -165,14 +311,22 @@ impl Game {
         self.max_value = Some(max_value);
     }
 
+    /// Sets the limits enforced on frames, whether connections are encrypted and the preferred
+    /// wire codec for the game's client and for every agent spawned from now on.
+    fn set_network(&mut self, network: NetworkOptions) {
+        self.network = network;
+        self.game_client.configure_connections(network);
+    }
+
     /// Sets `Game.is_ready` to `true`, indicating that the game is ready to be played.
     fn set_ready(&mut self) {
         self.is_ready = true;
     }
 
-    /// Sets the `tamper_chance` field, determining the probability that liars will modify messages.
-    fn set_tamper_chance(&mut self, tamper_chance: f32) {
-        self.tamper_chance = Some(tamper_chance);
+    /// Sets the `liar_options` field, determining how liars misbehave, e.g, the probability that
+    /// liars will modify messages.
+    fn set_liar_options(&mut self, liar: LiarOptions) {
+        self.liar_options = Some(liar);
     }
 
     /// Returns all the agents in the game's list of active agents, regardless of their status.
@@ -227,6 +381,66 @@ impl Game {
         );
     }
 
+    /// Informs every agent in `Game.active_agents` of the IDs and public keys of all agents in the
+    /// game, so that agents can authenticate the queries they receive from their peers.
+    fn share_known_peers(&self) {
+        let peers: Vec<AgentConfig> = self.active_agents.iter().map(Agent::to_config).collect();
+        for agent in &self.active_agents {
+            agent.set_known_peers(&peers);
+        }
+    }
+
+    /// Informs every agent in `Game.active_agents` of the agents it may connect to according to
+    /// `Game.topology`.
+    fn share_topology(&self) {
+        for agent in &self.active_agents {
+            agent.set_neighbours(self.topology.get_neighbours(agent.get_id()));
+        }
+    }
+
+    /// Informs every agent in `Game.active_agents` of how to gossip, according to `Game.gossip`.
+    fn share_gossip(&self) {
+        for agent in &self.active_agents {
+            agent.set_gossip(self.gossip);
+        }
+    }
+
+    /// Returns the number of messages sent by the game's client and every agent so far.
+    fn get_messages_sent(&self) -> usize {
+        self.game_client.get_messages_sent()
+            + self
+                .active_agents
+                .iter()
+                .map(Agent::get_messages_sent)
+                .sum::<usize>()
+    }
+
+    /// Prints how long a round took since `started` and how many messages were sent during the
+    /// round, given the number of messages sent before it, `messages_before`.
+    fn print_round_cost(&self, started: Instant, messages_before: usize) {
+        println!(
+            "{}{} ms, {} messages\n",
+            "[+] Round cost: ".bold(),
+            started.elapsed().as_millis(),
+            self.get_messages_sent() - messages_before
+        );
+    }
+
+    /// Returns the IDs of every agent in `Game.active_agents`.
+    fn get_agent_ids(&self) -> Vec<usize> {
+        self.active_agents.iter().map(Agent::get_id).collect()
+    }
+
+    /// Prints the kind of `Game.topology` and the neighbours of every agent in it.
+    fn print_topology(&self) {
+        println!(
+            "{}{}\n{}",
+            "[+] Network topology: ".bold(),
+            self.topology.get_kind(),
+            self.topology
+        );
+    }
+
     /// Executes the `start` command. The `start` command launches a number of independent
     /// agents and produces the `agents.config` file containing information that can be used
     /// to communicate with those agents. It then displays a message to indicate that the
@@ -237,7 +451,8 @@ impl Game {
         max_value: u64,
         num_agents: u16,
         liar_ratio: f32,
-        tamper_chance: f32,
+        liar: LiarOptions,
+        network: NetworkOptions,
     ) {
         if self.is_ready() {
             Game::print_started();
@@ -246,6 +461,25 @@ impl Game {
 
         println!("{}", "[+] Starting game!\n".bold());
 
+        self.set_network(network);
+        if network.transport == Transport::Encrypted {
+            println!(
+                "{}",
+                "[+] Connections are encrypted and mutually authenticated.\n".bold()
+            );
+        }
+        println!(
+            "{}{}\n",
+            "[+] Agents answer queries under the access policy: ".bold(),
+            network.access_policy
+        );
+        if liar.equivocate {
+            println!(
+                "{}",
+                "[+] Liars sign a different value for every query they answer.\n".bold()
+            );
+        }
+
         let (num_honest, num_liars) = Self::get_agent_distribution(num_agents, liar_ratio);
 
         // NOTE: An improvement would be to shuffle the values or ids of agents in
@@ -254,9 +488,11 @@ impl Game {
         // in an increasing order, the first half of agents all have the same value (honest)
         // and the second half all have different values (liars).
         self.add_honest_agents(value, num_honest);
-        self.add_liar_agents(value, max_value, num_liars, tamper_chance);
+        self.add_liar_agents(value, max_value, num_liars, &liar, None);
+        Self::print_coalitions(&self.coalitions);
 
         self.start_game_agents().await;
+        self.share_known_peers();
 
         let agent_config = match self.gen_agent_config() {
             Ok(agent_config) => agent_config,
@@ -269,17 +505,14 @@ impl Game {
         if let Err(e) = Self::write_agent_config(&agent_config) {
             // Could not write config to a file, kill spawned agents as they will be unreachable
             for agent in &self.active_agents {
-                let _ = self
-                    .game_client
-                    .kill_agent(agent.get_id(), agent.get_address(), agent.get_port())
-                    .await;
+                let _ = self.game_client.kill_agent(&agent.to_config()).await;
             }
             self.reset_game();
             println!("[!] error: failed to write agents.config file - {}", e);
             return;
         }
 
-        self.init_game(value, max_value, tamper_chance);
+        self.init_game(value, max_value, liar);
         self.print_ready();
     }
 
@@ -288,8 +521,9 @@ impl Game {
     /// about the currently deployed agents. By using the information obtained from
     /// the file, the client must then directly query each individuaal agent for their
     /// value. After collecting the value from every agent, the client must determine
-    /// the network value and print it.
-    pub async fn play(&mut self) {
+    /// the network value and print it. Agents are queried according to `policy`, and the network
+    /// value is inferred according to `inference`.
+    pub async fn play(&mut self, policy: RoundPolicy, inference: InferenceOptions) {
         if !self.is_ready() {
             Game::print_not_started();
             return;
@@ -312,9 +546,13 @@ impl Game {
             " agents for their values...".bold()
         );
 
-        match self.game_client.play_standard_round().await {
-            Ok(agent_values) => {
-                Client::print_network_value(&Client::infer_network_value(&agent_values))
+        match self.game_client.play_standard_round(&policy).await {
+            Ok(mut votes) => {
+                if inference.is_adaptive() {
+                    votes = self.settle_standard_round(votes, inference, &policy).await;
+                }
+                let accepted = self.report_network_value(inference, &votes);
+                self.update_reputation(&votes, accepted, &[], &[]);
             }
             Err(e) => println!("{}", e),
         };
@@ -333,11 +571,7 @@ impl Game {
             agents_to_kill.retain(|agent| agent.get_status() == AgentStatus::Ready);
 
             for agent in &agents_to_kill {
-                match self
-                    .game_client
-                    .kill_agent(agent.get_id(), agent.get_address(), agent.get_port())
-                    .await
-                {
+                match self.game_client.kill_agent(&agent.to_config()).await {
                     Ok(_) => (),
                     Err(e) => println!("{}", e),
                 }
@@ -364,10 +598,9 @@ impl Game {
             .iter()
             .position(|agent| agent.get_id() == target_id)
         {
-            let address = self.active_agents[index].get_address();
-            let port = self.active_agents[index].get_port();
+            let agent = self.active_agents[index].to_config();
 
-            match self.game_client.kill_agent(target_id, address, port).await {
+            match self.game_client.kill_agent(&agent).await {
                 Ok(success_msg) => {
                     println!("{}", success_msg);
                     self.active_agents[index].set_killed();
@@ -379,34 +612,79 @@ impl Game {
                 "[!] error: the ID '{}' does not correspond to any active agent\n",
                 target_id
             );
-            return;
         }
     }
 
     /// Executes the `extend` command. The `extend` command checks for the existence of
-    /// the `agents.config` file, and if present, extends it by launching new agents.
-    pub async fn extend(&mut self, num_agents: u16, liar_ratio: f32) {
+    /// the `agents.config` file, and if present, extends it by launching new agents. New liars
+    /// are assigned `strategies` in turn, or the relay strategies chosen at `start` if empty.
+    /// They join the existing `coalition`, if given, or form new coalitions of `coalition_size`
+    /// members, or of the size chosen at `start` if None.
+    pub async fn extend(
+        &mut self,
+        num_agents: u16,
+        liar_ratio: f32,
+        strategies: &[RelayStrategy],
+        coalition_size: Option<u16>,
+        coalition: Option<usize>,
+    ) {
         if !self.is_ready() || !Self::agent_config_exists() {
             Game::print_not_started();
             return;
         }
 
+        if let Some(coalition_id) = coalition {
+            if !self
+                .coalitions
+                .iter()
+                .any(|coalition| coalition.get_id() == coalition_id)
+            {
+                println!(
+                    "[!] error: there is no coalition with ID {}\n",
+                    coalition_id
+                );
+                return;
+            }
+        }
+
         let (num_honest, num_liars) = Self::get_agent_distribution(num_agents, liar_ratio);
 
         // Backup and revert to current agents if something goes wrong after new agents are added
         let agents_backup = self.active_agents.clone();
+        let coalitions_backup = self.coalitions.clone();
 
         // self.value and self.max_value should not be None since self.is_ready() == true,
-        if let (Some(value), Some(max_value), Some(tamper_chance)) =
-            (self.value, self.max_value, self.tamper_chance)
+        if let (Some(value), Some(max_value), Some(mut liar)) =
+            (self.value, self.max_value, self.liar_options.clone())
         {
+            if !strategies.is_empty() {
+                liar.strategies = strategies.to_vec();
+            }
+            if let Some(coalition_size) = coalition_size {
+                liar.coalition_size = coalition_size;
+            }
             self.add_honest_agents(value, num_honest);
-            self.add_liar_agents(value, max_value, num_liars, tamper_chance);
+            self.add_liar_agents(value, max_value, num_liars, &liar, coalition);
+            if self.coalitions != coalitions_backup {
+                Self::print_coalitions(&self.coalitions);
+            }
         } else {
             panic!("[!] Unable to extend game; missing game settings.");
         }
 
         self.start_game_agents().await;
+        self.share_known_peers();
+
+        // New agents are placed in a freshly generated topology of the same kind
+        match self.topology.regenerate(&self.get_agent_ids()) {
+            Ok(topology) => self.topology = topology,
+            Err(e) => {
+                println!("{}[+] Falling back to a complete topology.\n", e);
+                self.topology = Topology::default();
+            }
+        }
+        self.share_topology();
+        self.share_gossip();
 
         let agent_config = match self.gen_agent_config() {
             Ok(agent_config) => agent_config,
@@ -433,25 +711,94 @@ impl Game {
                     .iter()
                     .any(|old_agent| old_agent.get_id() == agent.get_id())
                 {
-                    let _ = self
-                        .game_client
-                        .kill_agent(agent.get_id(), agent.get_address(), agent.get_port())
-                        .await;
+                    let _ = self.game_client.kill_agent(&agent.to_config()).await;
                 }
             }
             // Reset `active_agents` to its previous state, before extension
             self.active_agents = agents_backup;
+            self.coalitions = coalitions_backup;
+        }
+    }
 
+    /// Executes the `topology` command. If `kind` is None, prints the current network topology.
+    /// Otherwise, generates a topology of the given `kind` over the active agents, using `degree`
+    /// and `probability` as required by `kind`, and informs agents of their new neighbours.
+    pub fn topology(&mut self, kind: Option<TopologyKind>, degree: usize, probability: f32) {
+        if !self.is_ready() {
+            Game::print_not_started();
+            return;
+        }
+
+        if let Some(kind) = kind {
+            match Topology::generate(kind, &self.get_agent_ids(), degree, probability) {
+                Ok(topology) => self.topology = topology,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            }
+            self.share_topology();
+        }
+
+        self.print_topology();
+    }
+
+    /// Executes the `suspects` command. The `suspects` command prints the agents that relayed
+    /// packets the client had to reject in any expert round played so far, most suspicious first,
+    /// along with the evidence collected against each of them.
+    pub fn suspects(&self) {
+        if !self.is_ready() {
+            Game::print_not_started();
+            return;
+        }
+
+        let suspect_list = self.game_client.get_suspects();
+        let suspects = suspect_list.get_suspects();
+        if suspects.is_empty() {
+            println!(
+                "{}{}{}\n",
+                "[+] No tampering was detected in ".bold(),
+                suspect_list.get_num_rounds(),
+                " expert round(s).".bold()
+            );
             return;
         }
+
+        println!(
+            "{}{}{}",
+            "[!] Suspected liars after ".bold(),
+            suspect_list.get_num_rounds(),
+            " expert round(s):".bold()
+        );
+        for suspect in suspects {
+            println!("    {}", suspect);
+            for evidence in suspect.get_evidence() {
+                println!("        {}", evidence);
+            }
+            for proof in suspect.get_equivocations() {
+                println!("        {}", proof);
+            }
+        }
+        println!();
     }
 
     /// Executes the `playexpert` command. The `playexpert` command plays a round of the
     /// the game in expert mode. Expert mode is similar to the standard mode implemented by
     /// the `play` command, however unlike in standard mode, the client can only directly
     /// query a subset of the currently deployed agents, the size of which is taken as
-    /// an argument by `fn play_expert()`.
-    pub async fn play_expert(&mut self, num_agents: u16, liar_ratio: f32) {
+    /// an argument by `fn play_expert()`. Agents in the subset are queried according to `policy`,
+    /// and relay requests for values through their neighbours for up to `ttl` hops. The network
+    /// value is inferred according to `inference`. If `by_reputation` is set, the agents with the
+    /// best reputation are preferred when forming the subset.
+    pub async fn play_expert(
+        &mut self,
+        num_agents: u16,
+        liar_ratio: f32,
+        ttl: u8,
+        policy: RoundPolicy,
+        inference: InferenceOptions,
+        by_reputation: bool,
+    ) {
         if !self.is_ready() {
             Game::print_not_started();
             return;
@@ -470,44 +817,483 @@ impl Game {
 
         if req_honest > game_honest {
             println!(
-                "{} {}\n",
-                "[!] error: not enough honest agents to form the requested subset.",
-                "Choose a smaller number or extend the game."
+                "[!] error: not enough honest agents to form the requested subset. \
+                Choose a smaller number or extend the game.\n"
             );
             return;
         }
 
         if req_liars > game_liars {
             println!(
-                "{} {}\n",
-                "[!] error: not enough liars to form the requested subset.",
-                "Choose a smaller number or extend the game."
+                "[!] error: not enough liars to form the requested subset. \
+                Choose a smaller number or extend the game.\n"
             );
             return;
         }
 
-        let expert_subset: Vec<AgentConfig> = self.get_expert_subset(req_honest, req_liars);
+        let mut expert_subset: Vec<AgentConfig> =
+            self.get_expert_subset(req_honest, req_liars, by_reputation);
         Self::print_expert_subset(&expert_subset);
+        self.lock_out_client(&expert_subset);
 
-        match self.game_client.play_expert_round(&expert_subset).await {
-            Ok(agent_values) => {
+        let (started, messages_before) = (Instant::now(), self.get_messages_sent());
+        match self
+            .game_client
+            .play_expert_round(&expert_subset, ttl, &policy)
+            .await
+        {
+            Ok(mut report) => {
+                if inference.is_adaptive() {
+                    report = self
+                        .settle_expert_round(report, &mut expert_subset, inference, &policy)
+                        .await;
+                }
                 println!(
                     "{} {} {}\n",
                     "[+] Received valid, signed replies from".bold(),
-                    agent_values.len(),
+                    report.get_values().len(),
                     "agents!".bold(),
                 );
-                Client::print_network_value(&Client::infer_network_value(&agent_values));
+                Self::print_tampering(report.get_tampering());
+                Self::print_equivocations(report.get_equivocations());
+                let accepted = self.report_network_value(inference, report.get_values());
+                self.update_reputation(
+                    report.get_values(),
+                    accepted,
+                    report.get_tampering(),
+                    report.get_equivocations(),
+                );
             }
             Err(e) => println!("{}", e),
         }
+        Self::print_lockout_violations(&self.lift_client_lockout());
+        self.print_round_cost(started, messages_before);
+    }
+
+    /// Infers the network value from `votes` according to `inference` and prints it, along with
+    /// its posterior probability if the strategy estimates one. Votes are weighted by the
+    /// reputation of each agent, if required. If a single value is inferred, also
+    /// prints whether it is provably correct given the assumed fault bound, or a bound derived
+    /// from the proportion of liars in the game, and returns it as the accepted value.
+    fn report_network_value(&self, inference: InferenceOptions, votes: &[Vote]) -> Option<u64> {
+        let fault_bound = inference.get_fault_bound();
+        let strategy = self.build_strategy(inference);
+        let network_value = strategy.infer(votes);
+        Client::print_network_value(&network_value);
+        if let (Some(network_value), Some(posterior)) = (&network_value, strategy.posterior(votes))
+        {
+            Client::print_posterior(network_value, &posterior);
+        }
+
+        let Some(&[value]) = network_value.as_deref() else {
+            return None;
+        };
+        let fault_bound = fault_bound.unwrap_or_else(|| {
+            Confidence::fault_bound_from_ratio(votes.len(), self.get_liar_ratio())
+        });
+        Client::print_confidence(&Confidence::assess(votes, value, fault_bound));
+        Some(value)
+    }
+
+    /// Returns the inference strategy selected in `inference`, which weighs votes by the
+    /// reputation of each agent, or expects the proportion of liars in the game if no prior on
+    /// the liar ratio was given, if required.
+    fn build_strategy(&self, inference: InferenceOptions) -> Box<dyn InferenceStrategy> {
+        let liar_prior = inference
+            .get_liar_prior()
+            .unwrap_or_else(|| self.get_liar_ratio());
+        inference.get_strategy().build(
+            self.get_reputation_weights(),
+            inference.get_fault_bound(),
+            self.max_value.unwrap_or_default(),
+            f64::from(liar_prior),
+        )
+    }
+
+    /// Infers the network value from `votes` according to `inference`.
+    fn infer(&self, inference: InferenceOptions, votes: &[Vote]) -> Option<Vec<u64>> {
+        self.build_strategy(inference).infer(votes)
+    }
+
+    /// Returns whether `inference` settles on a single network value from `votes`.
+    fn is_settled(&self, inference: InferenceOptions, votes: &[Vote]) -> bool {
+        matches!(self.infer(inference, votes).as_deref(), Some([_]))
+    }
+
+    /// Returns the agents known to the game's client that have no vote in `votes`, except for
+    /// the agents in `excluded`.
+    fn get_missing_agents(&self, votes: &[Vote], excluded: &[usize]) -> Vec<AgentConfig> {
+        self.game_client
+            .get_peers()
+            .iter()
+            .filter(|peer| {
+                !excluded.contains(&peer.get_id())
+                    && !votes
+                        .iter()
+                        .any(|vote| vote.get_agent_id() == peer.get_id())
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Queries the agents that failed to reply during a standard round again, until `inference`
+    /// settles on a single network value from `votes` or no new value is received. Agents are
+    /// queried according to `policy`. Returns every vote received.
+    async fn settle_standard_round(
+        &self,
+        mut votes: Vec<Vote>,
+        inference: InferenceOptions,
+        policy: &RoundPolicy,
+    ) -> Vec<Vote> {
+        let queries_before = self.game_client.get_messages_sent();
+
+        while !self.is_settled(inference, &votes) {
+            let missing = self.get_missing_agents(&votes, &[]);
+            if missing.is_empty() {
+                break;
+            }
+            println!(
+                "{}{}{}\n",
+                "[+] Querying ".bold(),
+                missing.len(),
+                " agents that failed to reply again...".bold()
+            );
+            match self.game_client.query_agents(&missing, policy).await {
+                Ok(new_votes) if !new_votes.is_empty() => votes.extend(new_votes),
+                Ok(_) => break,
+                Err(e) => {
+                    println!("{}", e);
+                    break;
+                }
+            }
+        }
+        votes.sort_by_key(Vote::get_agent_id);
+
+        Self::print_settlement(
+            self.is_settled(inference, &votes),
+            self.game_client.get_messages_sent() - queries_before,
+        );
+        votes
+    }
+
+    /// Asks for the values that are missing from `report` again, until `inference` settles on a
+    /// single network value or no more values can be learned. The relays in `expert_subset` are
+    /// asked first, and then running agents outside of it are added to the subset as extra
+    /// relays, one at a time. Agents are queried according to `policy`. Returns `report` along
+    /// with every value and piece of evidence collected.
+    async fn settle_expert_round(
+        &self,
+        mut report: RoundReport,
+        expert_subset: &mut Vec<AgentConfig>,
+        inference: InferenceOptions,
+        policy: &RoundPolicy,
+    ) -> RoundReport {
+        let queries_before = self.game_client.get_messages_sent();
+        let mut extra_relays: Vec<AgentConfig> = self
+            .active_agents
+            .iter()
+            .filter(|agent| {
+                agent.get_status() == AgentStatus::Ready
+                    && !expert_subset
+                        .iter()
+                        .any(|member| member.get_id() == agent.get_id())
+            })
+            .map(Agent::to_config)
+            .collect();
+        extra_relays.shuffle(&mut thread_rng());
+        let mut relays = expert_subset.clone();
+
+        while !self.is_settled(inference, report.get_values()) {
+            // Equivocating agents lost their vote for the round, and cannot be given another one
+            let equivocators: Vec<usize> = report
+                .get_equivocations()
+                .iter()
+                .map(EquivocationProof::get_agent_id)
+                .collect();
+            let missing: Vec<usize> = self
+                .get_missing_agents(report.get_values(), &equivocators)
+                .iter()
+                .map(AgentConfig::get_id)
+                .collect();
+            if missing.is_empty() {
+                break;
+            }
+
+            let requery = match self
+                .game_client
+                .requery_missing_values(&relays, &missing, policy)
+                .await
+            {
+                Ok(requery) => requery,
+                Err(e) => {
+                    println!("{}", e);
+                    break;
+                }
+            };
+            if report.merge(requery) > 0 {
+                continue;
+            }
+
+            // The relays asked so far cannot provide any more values, so ask a new one
+            let Some(relay) = extra_relays.pop() else {
+                break;
+            };
+            println!(
+                "{}{}{}\n",
+                "[+] Adding Agent ".bold(),
+                relay.get_id(),
+                " to the expert subset as an extra relay...".bold()
+            );
+            expert_subset.push(relay.clone());
+            self.lock_out_client(expert_subset);
+            relays = vec![relay];
+        }
+
+        Self::print_settlement(
+            self.is_settled(inference, report.get_values()),
+            self.game_client.get_messages_sent() - queries_before,
+        );
+        report
+    }
+
+    /// Prints whether adaptive re-querying managed to settle on a single network value, and the
+    /// number of additional `queries` it spent.
+    fn print_settlement(settled: bool, queries: usize) {
+        if queries == 0 && settled {
+            return;
+        }
+        if settled {
+            println!(
+                "{} {} {}\n",
+                "[+] Settled on a single network value after".bold(),
+                queries,
+                "additional queries.".bold()
+            );
+        } else {
+            println!(
+                "{} {} {}\n",
+                "[+] The tie cannot be broken, no more values can be learned. Spent".bold(),
+                queries,
+                "additional queries.".bold()
+            );
+        }
+    }
+
+    /// Loads the reputation of agents saved by previous sessions to the `reputation.json` file,
+    /// which will be kept up to date with the outcome of every round from now on.
+    pub fn load_reputation(&mut self) {
+        match ReputationBook::open(REPUTATION_FILE) {
+            Ok(reputation) => self.reputation = reputation,
+            Err(e) => println!(
+                "[!] error: failed to load data from {} - {:#}\n",
+                REPUTATION_FILE, e
+            ),
+        }
+    }
+
+    /// Returns the score of every agent known to the game's client, indexed by agent ID.
+    fn get_reputation_weights(&self) -> HashMap<usize, f64> {
+        self.game_client
+            .get_peers()
+            .iter()
+            .map(|peer| {
+                (
+                    peer.get_id(),
+                    self.reputation.get_score(peer.get_public_key()),
+                )
+            })
+            .collect()
+    }
+
+    /// Updates the reputation of the agents that took part in a round, given the `votes` cast
+    /// during the round, the `accepted` network value, if any, and the evidence of `tampering` and
+    /// `equivocations` collected. Agents that agree with the accepted value gain reputation, while
+    /// agents that disagree with it, relay rejected packets or equivocate lose it.
+    fn update_reputation(
+        &mut self,
+        votes: &[Vote],
+        accepted: Option<u64>,
+        tampering: &[TamperEvidence],
+        equivocations: &[EquivocationProof],
+    ) {
+        let public_keys: HashMap<usize, String> = self
+            .game_client
+            .get_peers()
+            .iter()
+            .map(|peer| (peer.get_id(), peer.get_public_key().to_owned()))
+            .collect();
+
+        if let Some(accepted) = accepted {
+            for vote in votes {
+                if let Some(public_key) = public_keys.get(&vote.get_agent_id()) {
+                    self.reputation
+                        .record_vote(public_key, vote.get_value() == accepted);
+                }
+            }
+        }
+        for evidence in tampering {
+            if let Some(public_key) = public_keys.get(&evidence.get_culprit()) {
+                self.reputation.record_tampering(public_key);
+            }
+        }
+        for proof in equivocations {
+            if let Some(public_key) = public_keys.get(&proof.get_agent_id()) {
+                self.reputation.record_equivocation(public_key);
+            }
+        }
+
+        if let Err(e) = self.reputation.save() {
+            println!(
+                "[!] error: failed to save the reputation of agents - {:#}\n",
+                e
+            );
+        }
+    }
+
+    /// Returns the proportion of liars among the agents currently running.
+    fn get_liar_ratio(&self) -> f32 {
+        let (honest, liars) = self.get_num_spawned();
+        match honest + liars {
+            0 => 0.0,
+            total => liars as f32 / total as f32,
+        }
+    }
+
+    /// Bars the game's client from contacting any active agent outside `expert_subset` directly,
+    /// so that the values of those agents can only reach it through relays.
+    fn lock_out_client(&self, expert_subset: &[AgentConfig]) {
+        for agent in &self.active_agents {
+            let in_subset = expert_subset
+                .iter()
+                .any(|member| member.get_id() == agent.get_id());
+            agent.set_client_lockout(!in_subset);
+        }
+    }
+
+    /// Lifts the lockout set up by `lock_out_client`. Returns the ID of every agent that the
+    /// client attempted to contact directly during the round, along with the number of attempts.
+    fn lift_client_lockout(&self) -> Vec<(usize, usize)> {
+        self.active_agents
+            .iter()
+            .filter_map(|agent| {
+                agent.set_client_lockout(false);
+                match agent.take_lockout_violations() {
+                    0 => None,
+                    attempts => Some((agent.get_id(), attempts)),
+                }
+            })
+            .collect()
+    }
+
+    /// Executes the `gossip` command. The `gossip` command makes every agent push the signed
+    /// values it holds to `fanout` random neighbours every `interval` milliseconds, or stops
+    /// gossip if `interval` is 0. Agents spawned later on gossip with the same settings.
+    pub fn gossip(&mut self, interval: u64, fanout: usize) {
+        if !self.is_ready() {
+            Game::print_not_started();
+            return;
+        }
+
+        if interval == 0 {
+            self.gossip = None;
+            self.gossip_started = None;
+            println!("{}", "[+] Agents stopped gossiping.\n".bold());
+        } else {
+            self.gossip = Some(GossipOptions::from_millis(interval, fanout));
+            self.gossip_started = Some((Instant::now(), self.get_messages_sent()));
+            println!(
+                "{}{}{}{}\n",
+                "[+] Agents gossip their values every ".bold(),
+                interval,
+                " ms to a number of random neighbours: ".bold(),
+                fanout
+            );
+        }
+        self.share_gossip();
+    }
+
+    /// Executes the `play-gossip` command. The `play-gossip` command plays a round of the game in
+    /// which the client only queries the agent `agent_id`, or a random agent if None, for the
+    /// values it gathered through gossip, according to `policy`. Along with the network value, it
+    /// prints for how long agents have been gossiping and how many messages were sent meanwhile,
+    /// so that gossip can be compared with the pull model of expert rounds.
+    pub async fn play_gossip(&mut self, agent_id: Option<usize>, policy: RoundPolicy) {
+        if !self.is_ready() {
+            Game::print_not_started();
+            return;
+        }
+
+        if let Err(e) = self.game_client.load_agent_config() {
+            println!(
+                "[!] error: failed to load data from agents.config - {}\n",
+                e
+            );
+            return;
+        }
+
+        let mut candidates: Vec<&Agent> = self
+            .active_agents
+            .iter()
+            .filter(|agent| agent.get_status() == AgentStatus::Ready)
+            .filter(|agent| agent_id.is_none_or(|agent_id| agent.get_id() == agent_id))
+            .collect();
+        candidates.shuffle(&mut thread_rng());
+        let peer = match candidates.first() {
+            Some(agent) => agent.to_config(),
+            None => {
+                println!("[!] error: there are no active agents to be queried\n");
+                return;
+            }
+        };
+
+        println!(
+            "{}{}{}\n",
+            "[+] Querying Agent ".bold(),
+            peer.get_id(),
+            " for the values it gathered through gossip...".bold()
+        );
+
+        let (started, messages_before) = (Instant::now(), self.get_messages_sent());
+        match self.game_client.play_gossip_round(&peer, &policy).await {
+            Ok(votes) => {
+                println!(
+                    "{} {} {}\n",
+                    "[+] Received valid, signed replies from".bold(),
+                    votes.len(),
+                    "agents!".bold(),
+                );
+                let accepted = self.report_network_value(InferenceOptions::default(), &votes);
+                self.update_reputation(&votes, accepted, &[], &[]);
+            }
+            Err(e) => println!("{}", e),
+        }
+        self.print_round_cost(started, messages_before);
+
+        match self.gossip_started {
+            Some((gossip_started, messages_before)) => println!(
+                "{}{} ms, {} messages\n",
+                "[+] Gossip cost so far: ".bold(),
+                gossip_started.elapsed().as_millis(),
+                self.get_messages_sent() - messages_before
+            ),
+            None => println!(
+                "{}",
+                "[+] Agents are not gossiping; start gossip with the gossip command.\n".bold()
+            ),
+        }
     }
 
     /// This method selects a random set of agents containing the requested number of honest agents
     /// `num_honest` and number of liars `num_liars`. It ensures the set is composed only of agents
     /// that are currently spawned and reachable. The method returns a `Vec<AgentConfig>` containing
-    /// information about the agents included in the set.
-    fn get_expert_subset(&self, num_honest: u16, num_liars: u16) -> Vec<AgentConfig> {
+    /// information about the agents included in the set. If `by_reputation` is set, the agents with
+    /// the best reputation are chosen first, and ties are broken at random.
+    fn get_expert_subset(
+        &self,
+        num_honest: u16,
+        num_liars: u16,
+        by_reputation: bool,
+    ) -> Vec<AgentConfig> {
         // Create a clone of the active_agents vector and remove all the agents whose status is
         // not equal to `AgentStatus::Ready`. Shuffle the resulting vector and use it to select
         // agents for the expert subset. This prevents the same subset of agents from being chosen
@@ -520,6 +1306,14 @@ impl Game {
         let mut rng = thread_rng();
         shuffled_agents.shuffle(&mut rng);
 
+        if by_reputation {
+            // The sort is stable, so agents with the same reputation remain shuffled
+            shuffled_agents.sort_by(|a, b| {
+                let score = |agent: &Agent| self.reputation.get_score(agent.get_public_key());
+                score(b).total_cmp(&score(a))
+            });
+        }
+
         // Get `num_honest` honest agents
         let mut honest_agents: Vec<AgentConfig> = shuffled_agents
             .iter()
@@ -537,9 +1331,8 @@ impl Game {
             .collect();
 
         honest_agents.append(&mut liars.clone());
-        let expert_subset = honest_agents;
 
-        expert_subset
+        honest_agents
     }
 
     /// Returns a tuple containing the number of honest agents and liars that are currently spawned.
@@ -588,13 +1381,51 @@ mod tests {
         game.is_ready = true;
         game.value = Some(5);
         game.max_value = Some(10);
-        game.tamper_chance = Some(0.1);
+        game.liar_options = Some(LiarOptions::new(0.1, true, &[RelayStrategy::Omit], 2));
         game.reset_game();
 
-        assert_ne!(game.is_ready, true);
+        assert!(!game.is_ready);
         assert_ne!(game.value, Some(5));
         assert_ne!(game.max_value, Some(10));
-        assert_ne!(game.tamper_chance, Some(0.1));
+        assert!(game.liar_options.is_none());
+    }
+
+    #[test]
+    fn test_liars_form_coalitions() {
+        let mut game = Game::new();
+        let liar = LiarOptions::new(0.0, false, &[], 2);
+
+        game.add_liar_agents(5, 10, 5, &liar, None);
+        let sizes: Vec<usize> = game
+            .coalitions
+            .iter()
+            .map(|coalition| coalition.get_members().len())
+            .collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        assert!(game
+            .coalitions
+            .iter()
+            .all(|coalition| coalition.get_value() != 5));
+
+        // New liars may join an existing coalition instead of forming new ones
+        game.add_liar_agents(5, 10, 2, &liar, Some(3));
+        assert_eq!(game.coalitions.len(), 3);
+        assert_eq!(game.coalitions[2].get_members().len(), 3);
+
+        // Independent liars do not join any coalition
+        game.add_liar_agents(5, 10, 2, &LiarOptions::new(0.0, false, &[], 1), None);
+        assert_eq!(game.coalitions.len(), 3);
+        assert_eq!(game.active_agents.len(), 9);
+
+        // Liars added later fill the last coalition before forming new ones
+        game.add_liar_agents(5, 10, 3, &liar, None);
+        game.add_liar_agents(5, 10, 1, &liar, None);
+        let sizes: Vec<usize> = game
+            .coalitions
+            .iter()
+            .map(|coalition| coalition.get_members().len())
+            .collect();
+        assert_eq!(sizes, vec![2, 2, 3, 2, 2]);
     }
 
     #[test]
@@ -0,0 +1,184 @@
+use anyhow::bail;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::agent_config::AgentConfig;
+use crate::message::Message;
+use crate::packet::{Packet, CLIENT_ID, FRESHNESS_WINDOW};
+
+/// The default time, in milliseconds, between two pushes of an agent's collection to its peers.
+pub const DEFAULT_GOSSIP_INTERVAL_MS: u64 = 500;
+/// The default number of random neighbours an agent pushes its collection to on every interval.
+pub const DEFAULT_GOSSIP_FANOUT: usize = 2;
+
+/// Represents how often, and to how many neighbours, agents push the values they hold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GossipOptions {
+    /// The time between two pushes of an agent's collection.
+    interval: Duration,
+    /// The number of random neighbours the collection is pushed to on every interval.
+    fanout: usize,
+}
+
+impl GossipOptions {
+    /// Returns a new instance of `GossipOptions` from an `interval` in milliseconds and a `fanout`.
+    pub fn from_millis(interval: u64, fanout: usize) -> Self {
+        GossipOptions {
+            interval: Duration::from_millis(interval),
+            fanout,
+        }
+    }
+
+    pub fn get_interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn get_fanout(&self) -> usize {
+        self.fanout
+    }
+}
+
+/// Holds the freshest signed value an agent has received from every other agent through gossip.
+///
+/// Every value is a `MsgSendValue` signed by the agent it belongs to and addressed to the game's
+/// client, so that it can be handed to the client untouched. Gossiped values do not belong to any
+/// round of the client, so each agent numbers the values it signs instead, using the sequence
+/// number as both the round and the nonce of the value. Clones share the same collection.
+#[derive(Debug, Clone, Default)]
+pub struct GossipStore {
+    values: Arc<Mutex<HashMap<usize, Packet>>>,
+    /// The sequence number of the last value the agent signed for gossip.
+    sequence: Arc<AtomicU64>,
+}
+
+impl GossipStore {
+    /// Returns the sequence number of the next value the agent signs for gossip. Sequence numbers
+    /// start at 1 and only ever increase.
+    pub fn next_sequence(&self) -> u64 {
+        self.sequence.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Verifies that `packet` carries a fresh, gossiped `MsgSendValue` signed by the agent it
+    /// belongs to, whose public key is `public_key`. Returns Ok(()) if the value can be stored.
+    pub fn verify_value(packet: &Packet, public_key: &str) -> anyhow::Result<()> {
+        match Message::deserialize_message(&packet.message) {
+            Ok(Message::MsgSendValue {
+                agent_id, nonce, ..
+            }) if agent_id == packet.sender && nonce == packet.round => {}
+            _ => bail!(
+                "[!] error: gossip from Agent {} does not carry a gossiped MsgSendValue\n",
+                packet.sender
+            ),
+        }
+
+        packet.check_context(CLIENT_ID, FRESHNESS_WINDOW)?;
+        packet.verify(public_key)
+    }
+
+    /// Stores `packet`, one of the agent's own values, replacing the previous one.
+    pub fn insert(&self, packet: Packet) {
+        self.values.lock().unwrap().insert(packet.sender, packet);
+    }
+
+    /// Merges the gossiped `values` into the collection. Values that cannot be verified with the
+    /// public keys of `known_peers` are discarded, and a stored value is only replaced by one with
+    /// a higher sequence number, so that older values cannot be replayed. Returns the number of
+    /// values that were added or replaced.
+    pub fn merge(&self, values: &[Packet], known_peers: &HashMap<usize, AgentConfig>) -> usize {
+        let mut stored = self.values.lock().unwrap();
+        let mut merged = 0;
+
+        for packet in values {
+            let Some(peer) = known_peers.get(&packet.sender) else {
+                continue;
+            };
+            if stored
+                .get(&packet.sender)
+                .is_some_and(|current| current.round >= packet.round)
+            {
+                continue;
+            }
+            if Self::verify_value(packet, peer.get_public_key()).is_ok() {
+                stored.insert(packet.sender, packet.clone());
+                merged += 1;
+            }
+        }
+
+        merged
+    }
+
+    /// Returns every value in the collection that is still fresh, ordered by agent ID. Stale
+    /// values are dropped, since neither peers nor the client would accept them.
+    pub fn snapshot(&self) -> Vec<Packet> {
+        let mut stored = self.values.lock().unwrap();
+        stored.retain(|_, packet| packet.check_context(CLIENT_ID, FRESHNESS_WINDOW).is_ok());
+
+        let mut values: Vec<Packet> = stored.values().cloned().collect();
+        values.sort_by_key(|packet| packet.sender);
+        values
+    }
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::Keys;
+
+    #[test]
+    fn test_gossip_store_merge() {
+        let keys = Keys::new_key_pair();
+        let stranger_keys = Keys::new_key_pair();
+        let known_peers = HashMap::from([(
+            2,
+            AgentConfig::new(2, "127.0.0.1", 5001, keys.get_public_key()),
+        )]);
+
+        let gossiped = |sender: usize, value: u64, sequence: u64, keys: &Keys| {
+            let message = Message::build_msg_send_value(value, sender, sequence).unwrap();
+            Packet::new(sender, CLIENT_ID, sequence, message)
+                .sign(keys)
+                .unwrap()
+        };
+
+        let older = gossiped(2, 10, 1, &keys);
+        let newer = gossiped(2, 11, 2, &keys);
+
+        let store = GossipStore::default();
+        // Unknown senders and forged signatures are discarded
+        assert_eq!(
+            store.merge(&[gossiped(3, 10, 1, &stranger_keys)], &known_peers),
+            0
+        );
+        assert_eq!(
+            store.merge(&[gossiped(2, 10, 1, &stranger_keys)], &known_peers),
+            0
+        );
+
+        assert_eq!(store.merge(std::slice::from_ref(&older), &known_peers), 1);
+        assert_eq!(store.merge(std::slice::from_ref(&newer), &known_peers), 1);
+        // An older value never replaces a more recent one, even if it was signed later
+        let replayed = gossiped(2, 10, 1, &keys);
+        assert_eq!(store.merge(&[older, replayed], &known_peers), 0);
+        assert_eq!(store.snapshot(), vec![newer]);
+
+        // A value whose nonce differs from its sequence number cannot be gossiped
+        let message = Message::build_msg_send_value(10, 2, 7).unwrap();
+        let answer = Packet::new(2, CLIENT_ID, 3, message).sign(&keys).unwrap();
+        assert!(GossipStore::verify_value(&answer, keys.get_public_key()).is_err());
+    }
+
+    #[test]
+    fn test_gossip_sequence_increases() {
+        let store = GossipStore::default();
+        let clone = store.clone();
+        assert_eq!(store.next_sequence(), 1);
+        assert_eq!(clone.next_sequence(), 2);
+        assert_eq!(store.next_sequence(), 3);
+    }
+}
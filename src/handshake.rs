@@ -0,0 +1,398 @@
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::BitOr;
+use tokio::net::TcpStream;
+
+use crate::codec::Codec;
+use crate::message::Message;
+use crate::network_utils::*;
+use crate::packet::Packet;
+
+/// The newest version of the wire protocol spoken by this build.
+pub const PROTOCOL_VERSION: u16 = 1;
+/// The oldest version of the wire protocol that this build is still able to speak.
+pub const MIN_PROTOCOL_VERSION: u16 = 1;
+
+/// Represents a set of optional protocol features advertised during the handshake.
+///
+/// `Capabilities` is encoded as a bit mask rather than as a list of enum variants, so that a peer
+/// running an older build can still decode a `MsgHello` containing capabilities it does not know
+/// about. Unknown bits are simply dropped when computing the capabilities shared by both sides.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// An empty set of capabilities.
+    pub const NONE: Self = Capabilities(0);
+    /// Replies to value queries are bound to a nonce chosen by the requester.
+    pub const NONCES: Self = Capabilities(1 << 0);
+    /// Frames are exchanged over an encrypted, mutually authenticated channel.
+    pub const ENCRYPTION: Self = Capabilities(1 << 1);
+
+    /// Packets are encoded as JSON rather than bincode.
+    pub const CODEC_JSON: Self = Capabilities(1 << 2);
+    /// Packets are encoded as CBOR rather than bincode.
+    pub const CODEC_CBOR: Self = Capabilities(1 << 3);
+    /// A `MsgFetchValues` carries a hop limit and the relays it went through, and may be answered
+    /// with values fetched through neighbours, nested in `MsgFwdValues`.
+    pub const MULTI_HOP: Self = Capabilities(1 << 4);
+
+    /// Every capability known to this build, along with its human-readable name.
+    const NAMES: [(Self, &'static str); 5] = [
+        (Self::NONCES, "nonces"),
+        (Self::ENCRYPTION, "encryption"),
+        (Self::CODEC_JSON, "json"),
+        (Self::CODEC_CBOR, "cbor"),
+        (Self::MULTI_HOP, "multi-hop"),
+    ];
+
+    /// Returns the set of capabilities supported by this build.
+    pub fn supported() -> Self {
+        Self::NONCES | Self::ENCRYPTION | Self::CODEC_JSON | Self::CODEC_CBOR | Self::MULTI_HOP
+    }
+
+    /// Returns `true` if every capability in `other` is also contained in `self`.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the capabilities that are contained in both `self` and `other`.
+    pub fn intersection(&self, other: Self) -> Self {
+        Capabilities(self.0 & other.0)
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Capabilities(self.0 | other.0)
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = Self::NAMES
+            .iter()
+            .filter(|(capability, _)| self.contains(*capability))
+            .map(|(_, name)| *name)
+            .collect();
+
+        if names.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", names.join(", "))
+        }
+    }
+}
+
+/// Represents the outcome of a successful handshake, i.e, the protocol version and the set of
+/// capabilities that both ends of a connection agreed to use.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Session {
+    /// The protocol version used on the connection.
+    version: u16,
+    /// The capabilities supported by both ends of the connection.
+    capabilities: Capabilities,
+}
+
+impl Session {
+    /// Returns the protocol version negotiated for the connection.
+    pub fn get_version(&self) -> u16 {
+        self.version
+    }
+
+    /// Returns the capabilities supported by both ends of the connection.
+    pub fn get_capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Returns the codec used to encode packets on the connection.
+    pub fn get_codec(&self) -> Codec {
+        Codec::from_capabilities(self.capabilities)
+    }
+}
+
+/// Returns the newest protocol version within both `local_min..=local_version` and
+/// `remote_min..=remote_version`, or None if the two ranges do not overlap.
+fn negotiate_version(
+    local_version: u16,
+    local_min: u16,
+    remote_version: u16,
+    remote_min: u16,
+) -> Option<u16> {
+    let version = local_version.min(remote_version);
+    if version >= local_min.max(remote_min) {
+        Some(version)
+    } else {
+        None
+    }
+}
+
+/// Returns an error if `negotiated` lacks any of the capabilities in `required`.
+fn check_required(negotiated: Capabilities, required: Capabilities) -> anyhow::Result<()> {
+    let missing = Capabilities(required.0 & !negotiated.0);
+    if missing != Capabilities::NONE {
+        bail!(
+            "[!] error: peer does not support required capabilities: {}\n",
+            missing
+        );
+    }
+    Ok(())
+}
+
+/// Performs the initiating side of the handshake over `socket`, advertising `offered`
+/// capabilities. Sends a `MsgHello` and waits for either a `MsgHelloAck`, in which case the
+/// negotiated `Session` is returned, or a `MsgHelloReject`, in which case an error explaining the
+/// incompatibility is returned. The handshake also fails if the peer does not support every
+/// capability in `required`.
+pub async fn initiate_handshake(
+    socket: &mut TcpStream,
+    config: &FrameConfig,
+    offered: Capabilities,
+    required: Capabilities,
+) -> anyhow::Result<Session> {
+    let message = Message::build_msg_hello(PROTOCOL_VERSION, MIN_PROTOCOL_VERSION, offered)
+        .context("[!] error: failed to build MsgHello\n")?;
+
+    // Handshake messages are always encoded with bincode, since no other codec has been agreed yet
+    let packet = Packet::unaddressed(message)
+        .encode(Codec::Bincode)
+        .context("[!] error: failed to build packet\n")?;
+
+    if let Err(e) = send_packet(&packet, socket, config).await {
+        bail!("[!] error: unable to send MsgHello - {}\n", e);
+    }
+
+    let reply = match recv_packet(socket, config).await {
+        Ok(reply) => reply,
+        Err(e) => bail!("[!] error: no reply to MsgHello - {}\n", e),
+    };
+    let reply_packet = Packet::unpack(&reply, Codec::Bincode)?;
+
+    match Message::deserialize_message(&reply_packet.message) {
+        Ok(Message::MsgHelloAck {
+            version,
+            capabilities,
+        }) => {
+            if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
+                bail!(
+                    "[!] error: peer selected protocol version {}, but only versions {} to {} are supported\n",
+                    version,
+                    MIN_PROTOCOL_VERSION,
+                    PROTOCOL_VERSION
+                );
+            }
+            let capabilities = capabilities.intersection(offered);
+            check_required(capabilities, required)?;
+            Ok(Session {
+                version,
+                capabilities,
+            })
+        }
+        Ok(Message::MsgHelloReject {
+            version,
+            min_version,
+            reason,
+        }) => bail!(
+            "[!] error: handshake rejected by peer - {} (peer supports protocol versions {} to {}, local versions are {} to {})\n",
+            reason,
+            min_version,
+            version,
+            MIN_PROTOCOL_VERSION,
+            PROTOCOL_VERSION
+        ),
+        Ok(other) => bail!("[!] error: expected MsgHelloAck, received {:?}\n", other),
+        Err(e) => bail!("[!] error: unable to decode handshake reply - {}\n", e),
+    }
+}
+
+/// Performs the accepting side of the handshake over `socket`, supporting the `offered`
+/// capabilities. Waits for a `MsgHello` and replies with a `MsgHelloAck` if the peer's protocol
+/// versions are compatible with the local ones and the peer supports every capability in
+/// `required`, or with a `MsgHelloReject` otherwise. Returns the negotiated `Session` if the
+/// handshake succeeds.
+pub async fn accept_handshake(
+    socket: &mut TcpStream,
+    config: &FrameConfig,
+    offered: Capabilities,
+    required: Capabilities,
+) -> anyhow::Result<Session> {
+    let hello = match recv_packet(socket, config).await {
+        Ok(hello) => hello,
+        Err(e) => bail!("[!] error: did not receive MsgHello - {}\n", e),
+    };
+    let hello_packet = Packet::unpack(&hello, Codec::Bincode)
+        .context("[!] error: unable to decode handshake packet\n")?;
+
+    let (version, min_version, capabilities) =
+        match Message::deserialize_message(&hello_packet.message) {
+            Ok(Message::MsgHello {
+                version,
+                min_version,
+                capabilities,
+            }) => (version, min_version, capabilities),
+            Ok(other) => bail!("[!] error: expected MsgHello, received {:?}\n", other),
+            Err(e) => bail!(
+                "[!] error: unable to decode MsgHello; peer may be running an incompatible protocol version - {}\n",
+                e
+            ),
+        };
+
+    let capabilities = capabilities.intersection(offered);
+    if let Err(e) = check_required(capabilities, required) {
+        let reply = Message::build_msg_hello_reject(
+            PROTOCOL_VERSION,
+            MIN_PROTOCOL_VERSION,
+            &format!("required capabilities are missing: {}", required),
+        )?;
+        send_packet(
+            &Packet::unaddressed(reply).encode(Codec::Bincode)?,
+            socket,
+            config,
+        )
+        .await?;
+        return Err(e);
+    }
+
+    match negotiate_version(PROTOCOL_VERSION, MIN_PROTOCOL_VERSION, version, min_version) {
+        Some(version) => {
+            let session = Session {
+                version,
+                capabilities,
+            };
+
+            let reply = Message::build_msg_hello_ack(session.version, session.capabilities)?;
+            send_packet(
+                &Packet::unaddressed(reply).encode(Codec::Bincode)?,
+                socket,
+                config,
+            )
+            .await?;
+
+            Ok(session)
+        }
+        None => {
+            let reason = format!(
+                "protocol versions {} to {} are not supported",
+                min_version, version
+            );
+            let reply =
+                Message::build_msg_hello_reject(PROTOCOL_VERSION, MIN_PROTOCOL_VERSION, &reason)?;
+            send_packet(
+                &Packet::unaddressed(reply).encode(Codec::Bincode)?,
+                socket,
+                config,
+            )
+            .await?;
+
+            bail!("[!] error: rejected handshake - {}\n", reason)
+        }
+    }
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_negotiate_version() {
+        // Identical ranges agree on the newest version
+        assert_eq!(negotiate_version(1, 1, 1, 1), Some(1));
+
+        // Overlapping ranges agree on the newest common version
+        assert_eq!(negotiate_version(3, 1, 2, 2), Some(2));
+        assert_eq!(negotiate_version(2, 2, 5, 1), Some(2));
+
+        // Disjoint ranges cannot agree on any version
+        assert_eq!(negotiate_version(1, 1, 3, 2), None);
+        assert_eq!(negotiate_version(4, 3, 2, 1), None);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = Capabilities::NONE | Capabilities::NONCES;
+        assert!(capabilities.contains(Capabilities::NONCES));
+        assert!(!Capabilities::NONE.contains(Capabilities::NONCES));
+
+        // Unknown bits are dropped when intersecting with the supported capabilities
+        let remote = Capabilities(0xFFFF_FFFF);
+        assert_eq!(
+            remote.intersection(Capabilities::supported()),
+            Capabilities::supported()
+        );
+        assert_eq!(Capabilities::NONE.to_string(), "none");
+        assert_eq!(Capabilities::NONCES.to_string(), "nonces");
+        assert_eq!(
+            (Capabilities::NONCES | Capabilities::MULTI_HOP).to_string(),
+            "nonces, multi-hop"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handshake_round_trip() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let acceptor = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            accept_handshake(
+                &mut socket,
+                &FrameConfig::default(),
+                Capabilities::supported(),
+                Capabilities::NONE,
+            )
+            .await
+        });
+
+        let mut socket = TcpStream::connect(address).await.unwrap();
+        let session = initiate_handshake(
+            &mut socket,
+            &FrameConfig::default(),
+            Capabilities::supported(),
+            Capabilities::NONE,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(session.get_version(), PROTOCOL_VERSION);
+        assert_eq!(session.get_capabilities(), Capabilities::supported());
+        assert_eq!(acceptor.await.unwrap().unwrap(), session);
+    }
+
+    // A peer that requires encryption must not fall back to a plaintext session
+    #[tokio::test]
+    async fn test_handshake_missing_required_capability() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let acceptor = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            accept_handshake(
+                &mut socket,
+                &FrameConfig::default(),
+                Capabilities::supported(),
+                Capabilities::ENCRYPTION,
+            )
+            .await
+        });
+
+        let mut socket = TcpStream::connect(address).await.unwrap();
+        let session = initiate_handshake(
+            &mut socket,
+            &FrameConfig::default(),
+            Capabilities::NONCES,
+            Capabilities::NONE,
+        )
+        .await;
+
+        assert!(session.is_err());
+        assert!(acceptor.await.unwrap().is_err());
+    }
+}
@@ -0,0 +1,664 @@
+use clap::ValueEnum;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Represents a value reported by an agent during a round, as counted by an inference strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vote {
+    /// The ID of the agent that reported the value.
+    agent_id: usize,
+    /// The value reported by the agent.
+    value: u64,
+}
+
+impl Vote {
+    /// Returns a new instance of `Vote` for the `value` reported by the agent `agent_id`.
+    pub fn new(agent_id: usize, value: u64) -> Self {
+        Vote { agent_id, value }
+    }
+
+    pub fn get_agent_id(&self) -> usize {
+        self.agent_id
+    }
+
+    pub fn get_value(&self) -> u64 {
+        self.value
+    }
+}
+
+/// Represents a rule for determining the network value from the votes collected during a round.
+pub trait InferenceStrategy: fmt::Debug {
+    /// Infers the network value from `votes`. Returns every value tied as the network value, which
+    /// is empty if the strategy cannot settle on any value, or None if there are no votes.
+    fn infer(&self, votes: &[Vote]) -> Option<Vec<u64>>;
+
+    /// Returns the probability of every reported value being the network value, most likely first,
+    /// or None if the strategy does not estimate probabilities.
+    fn posterior(&self, _votes: &[Vote]) -> Option<Vec<(u64, f64)>> {
+        None
+    }
+}
+
+/// Returns the maximum number of faulty agents tolerated among `num_votes` votes, i.e, the largest
+/// f such that num_votes >= 3f + 1.
+fn max_fault_bound(num_votes: usize) -> usize {
+    num_votes.saturating_sub(1) / 3
+}
+
+/// Returns the values of `votes`, sorted in ascending order.
+fn sorted_values(votes: &[Vote]) -> Vec<u64> {
+    let mut values: Vec<u64> = votes.iter().map(Vote::get_value).collect();
+    values.sort_unstable();
+    values
+}
+
+/// Returns every value in `tally` with the highest weight, in ascending order.
+fn heaviest_values<W: PartialOrd + Copy>(tally: &BTreeMap<u64, W>) -> Vec<u64> {
+    let Some(max_weight) = tally
+        .values()
+        .copied()
+        .reduce(|max, weight| if weight > max { weight } else { max })
+    else {
+        return Vec::new();
+    };
+    tally
+        .iter()
+        .filter(|&(_, &weight)| weight == max_weight)
+        .map(|(&value, _)| value)
+        .collect()
+}
+
+/// Counts the number of votes for each different value.
+fn count_votes(votes: &[Vote]) -> BTreeMap<u64, usize> {
+    let mut counts = BTreeMap::new();
+    for vote in votes {
+        *counts.entry(vote.value).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// The value reported by the most agents is the network value.
+///
+/// Different values may be tied with the most number of occurrences, in which case all of them
+/// are returned as the network value. For example, given the values below, both 2 and 8 will be
+/// returned as the network value.
+///     Number 2: 4 votes
+///     Number 5: 1 vote
+///     Number 8: 4 votes
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Plurality;
+
+impl InferenceStrategy for Plurality {
+    fn infer(&self, votes: &[Vote]) -> Option<Vec<u64>> {
+        if votes.is_empty() {
+            return None;
+        }
+        Some(heaviest_values(&count_votes(votes)))
+    }
+}
+
+/// The network value must be reported by at least 2f + 1 agents, where f is the number of faulty
+/// agents tolerated among the n votes. If no bound is given, f is the largest value such that
+/// n >= 3f + 1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Supermajority {
+    /// The number of faulty agents tolerated, or None to tolerate as many as possible.
+    fault_bound: Option<usize>,
+}
+
+impl Supermajority {
+    /// Returns a new instance of `Supermajority` tolerating `fault_bound` faulty agents.
+    pub fn new(fault_bound: Option<usize>) -> Self {
+        Supermajority { fault_bound }
+    }
+}
+
+impl InferenceStrategy for Supermajority {
+    fn infer(&self, votes: &[Vote]) -> Option<Vec<u64>> {
+        if votes.is_empty() {
+            return None;
+        }
+        let fault_bound = self
+            .fault_bound
+            .unwrap_or_else(|| max_fault_bound(votes.len()));
+        let threshold = 2 * fault_bound + 1;
+
+        Some(
+            count_votes(votes)
+                .into_iter()
+                .filter(|&(_, count)| count >= threshold)
+                .map(|(value, _)| value)
+                .collect(),
+        )
+    }
+}
+
+/// The median of the reported values is the network value. Given an even number of votes, both
+/// middle values are returned if they differ.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Median;
+
+impl InferenceStrategy for Median {
+    fn infer(&self, votes: &[Vote]) -> Option<Vec<u64>> {
+        if votes.is_empty() {
+            return None;
+        }
+        let values = sorted_values(votes);
+        let middle = values.len() / 2;
+
+        let mut median = if values.len().is_multiple_of(2) {
+            vec![values[middle - 1], values[middle]]
+        } else {
+            vec![values[middle]]
+        };
+        median.dedup();
+        Some(median)
+    }
+}
+
+/// The mean of the reported values, rounded to the nearest integer, is the network value once the
+/// f lowest and the f highest values are discarded. If no bound is given, f is the largest value
+/// such that n >= 3f + 1 for n votes.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TrimmedMean {
+    /// The number of values discarded at each end, or None to discard as many as can be faulty.
+    fault_bound: Option<usize>,
+}
+
+impl TrimmedMean {
+    /// Returns a new instance of `TrimmedMean` discarding `fault_bound` values at each end.
+    pub fn new(fault_bound: Option<usize>) -> Self {
+        TrimmedMean { fault_bound }
+    }
+}
+
+impl InferenceStrategy for TrimmedMean {
+    fn infer(&self, votes: &[Vote]) -> Option<Vec<u64>> {
+        if votes.is_empty() {
+            return None;
+        }
+        let values = sorted_values(votes);
+        let trim = self
+            .fault_bound
+            .unwrap_or_else(|| max_fault_bound(values.len()));
+        if 2 * trim >= values.len() {
+            return Some(Vec::new());
+        }
+
+        let kept = &values[trim..values.len() - trim];
+        let sum: u128 = kept.iter().map(|&value| u128::from(value)).sum();
+        let count = kept.len() as u128;
+        // Round half up to the nearest integer
+        Some(vec![((2 * sum + count) / (2 * count)) as u64])
+    }
+}
+
+/// The value with the highest total weight is the network value, where each vote counts as much
+/// as the reputation of the agent that cast it. Agents without a known reputation have a weight
+/// of 1.0, and tied values are all returned.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ReputationWeighted {
+    /// The weight of every agent's vote, indexed by agent ID.
+    weights: HashMap<usize, f64>,
+}
+
+impl ReputationWeighted {
+    /// Returns a new instance of `ReputationWeighted` that weighs votes according to `weights`.
+    pub fn new(weights: HashMap<usize, f64>) -> Self {
+        ReputationWeighted { weights }
+    }
+}
+
+impl InferenceStrategy for ReputationWeighted {
+    fn infer(&self, votes: &[Vote]) -> Option<Vec<u64>> {
+        if votes.is_empty() {
+            return None;
+        }
+        let mut tally: BTreeMap<u64, f64> = BTreeMap::new();
+        for vote in votes {
+            let weight = self.weights.get(&vote.agent_id).copied().unwrap_or(1.0);
+            *tally.entry(vote.value).or_insert(0.0) += weight;
+        }
+        Some(heaviest_values(&tally))
+    }
+}
+
+/// The most likely value given the votes is the network value.
+///
+/// Honest agents report the network value, while liars report a value drawn uniformly from
+/// 1..=max_value other than the network value. Every value in 1..=max_value is equally likely to be
+/// the network value beforehand, and the proportion of liars follows a Beta prior whose mean is
+/// `liar_prior`. Given n votes, k of which report the value v, the likelihood of v is then
+///     (max_value - 1)^-(n - k) * B(α + n - k, β + k) / B(α, β)
+/// where α and β are the parameters of the prior. Tied values are all returned, and no value is
+/// returned if a value nobody reported is at least as likely as the reported ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bayesian {
+    /// The largest value a liar may report.
+    max_value: u64,
+    /// The expected proportion of liars among the voters, before any vote is seen.
+    liar_prior: f64,
+}
+
+impl Bayesian {
+    /// The weight of the prior on the proportion of liars, in number of votes, i.e, α + β.
+    const PRIOR_STRENGTH: f64 = 2.0;
+    /// Keeps both parameters of the prior positive when the expected proportion is 0 or 1.
+    const MIN_PRIOR: f64 = 1e-3;
+
+    /// Returns a new instance of `Bayesian` for a game whose liars report values up to
+    /// `max_value`, where a proportion `liar_prior` of the voters is expected to be liars.
+    pub fn new(max_value: u64, liar_prior: f64) -> Self {
+        Bayesian {
+            max_value,
+            liar_prior: liar_prior.clamp(Self::MIN_PRIOR, 1.0 - Self::MIN_PRIOR),
+        }
+    }
+
+    /// Returns the logarithm of the likelihood of a value reported by `matching` out of
+    /// `num_votes` votes, up to a constant shared by every value.
+    fn log_likelihood(&self, matching: usize, num_votes: usize) -> f64 {
+        let alpha = Self::PRIOR_STRENGTH * self.liar_prior;
+        let beta = Self::PRIOR_STRENGTH - alpha;
+        let mismatching = num_votes - matching;
+        let liar_choices = self.max_value.saturating_sub(1).max(1) as f64;
+
+        // B(α + m, β + k) / B(α, β), expanded into rising factorials of α, β and α + β
+        let log_ratio = (0..mismatching)
+            .map(|j| (alpha + j as f64).ln())
+            .chain((0..matching).map(|j| (beta + j as f64).ln()))
+            .sum::<f64>()
+            - (0..num_votes)
+                .map(|j| (alpha + beta + j as f64).ln())
+                .sum::<f64>();
+        log_ratio - mismatching as f64 * liar_choices.ln()
+    }
+
+    /// Returns the probability of every reported value in 1..=max_value being the network value,
+    /// most likely first, along with the probability of each value nobody reported.
+    fn estimate(&self, votes: &[Vote]) -> (Vec<(u64, f64)>, f64) {
+        let counts: BTreeMap<u64, usize> = count_votes(votes)
+            .into_iter()
+            .filter(|&(value, _)| (1..=self.max_value).contains(&value))
+            .collect();
+        let log_unreported = self.log_likelihood(0, votes.len());
+        let num_unreported = (self.max_value - counts.len() as u64) as f64;
+
+        let log_likelihoods: Vec<(u64, f64)> = counts
+            .iter()
+            .map(|(&value, &count)| (value, self.log_likelihood(count, votes.len())))
+            .collect();
+        // Subtract the largest log-likelihood before exponentiating, so that nothing underflows
+        let max_log = log_likelihoods
+            .iter()
+            .map(|&(_, log_likelihood)| log_likelihood)
+            .fold(log_unreported, f64::max);
+        let unreported = (log_unreported - max_log).exp();
+        let reported: Vec<(u64, f64)> = log_likelihoods
+            .into_iter()
+            .map(|(value, log_likelihood)| (value, (log_likelihood - max_log).exp()))
+            .collect();
+        let total =
+            reported.iter().map(|&(_, weight)| weight).sum::<f64>() + num_unreported * unreported;
+
+        let mut posterior: Vec<(u64, f64)> = reported
+            .into_iter()
+            .map(|(value, weight)| (value, weight / total))
+            .collect();
+        posterior.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        (posterior, unreported / total)
+    }
+}
+
+impl InferenceStrategy for Bayesian {
+    fn infer(&self, votes: &[Vote]) -> Option<Vec<u64>> {
+        if votes.is_empty() {
+            return None;
+        }
+        let (posterior, unreported) = self.estimate(votes);
+        let Some(&(_, most_likely)) = posterior.first() else {
+            return Some(Vec::new());
+        };
+        if unreported >= most_likely {
+            return Some(Vec::new());
+        }
+
+        Some(
+            posterior
+                .iter()
+                .filter(|&&(_, probability)| probability == most_likely)
+                .map(|&(value, _)| value)
+                .collect(),
+        )
+    }
+
+    fn posterior(&self, votes: &[Vote]) -> Option<Vec<(u64, f64)>> {
+        Some(self.estimate(votes).0)
+    }
+}
+
+/// Represents how the client infers the network value at the end of a round.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct InferenceOptions {
+    /// The inference strategy used to determine the network value.
+    strategy: InferenceKind,
+    /// The assumed maximum number of faulty agents among the voters, or None to derive it from
+    /// the proportion of liars in the game.
+    fault_bound: Option<usize>,
+    /// Whether to keep querying agents until the strategy settles on a single value.
+    adaptive: bool,
+    /// The expected proportion of liars among the voters, or None to use the proportion of liars
+    /// in the game.
+    liar_prior: Option<f32>,
+}
+
+impl InferenceOptions {
+    /// Returns a new instance of `InferenceOptions` inferring the network value with `strategy`
+    /// and assuming that no more than `fault_bound` voters are faulty. If `adaptive` is set, agents
+    /// are queried again until the strategy settles on a single value. A proportion `liar_prior`
+    /// of the voters is expected to be liars when estimating probabilities.
+    pub fn new(
+        strategy: InferenceKind,
+        fault_bound: Option<usize>,
+        adaptive: bool,
+        liar_prior: Option<f32>,
+    ) -> Self {
+        InferenceOptions {
+            strategy,
+            fault_bound,
+            adaptive,
+            liar_prior,
+        }
+    }
+
+    pub fn get_strategy(&self) -> InferenceKind {
+        self.strategy
+    }
+
+    pub fn get_fault_bound(&self) -> Option<usize> {
+        self.fault_bound
+    }
+
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive
+    }
+
+    pub fn get_liar_prior(&self) -> Option<f32> {
+        self.liar_prior
+    }
+}
+
+/// Represents how strongly the votes of a round support the network value, given an assumed bound
+/// f on the number of faulty agents among the voters.
+///
+/// Honest agents all report the same value, so a value reported by at least f + 1 agents is
+/// reported by at least one honest agent and is provably the network value, as long as no more than
+/// f voters are faulty. With fewer matching votes, the value is only probably correct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Confidence {
+    /// The network value being assessed.
+    value: u64,
+    /// The assumed maximum number of faulty agents among the voters.
+    fault_bound: usize,
+    /// The number of votes that match the network value.
+    support: usize,
+    /// The number of votes for the most reported value other than the network value.
+    runner_up: usize,
+}
+
+impl Confidence {
+    /// Assesses how strongly `votes` support the network value `value`, assuming that no more than
+    /// `fault_bound` voters are faulty.
+    pub fn assess(votes: &[Vote], value: u64, fault_bound: usize) -> Self {
+        let counts = count_votes(votes);
+        let support = counts.get(&value).copied().unwrap_or(0);
+        let runner_up = counts
+            .iter()
+            .filter(|&(&other, _)| other != value)
+            .map(|(_, &count)| count)
+            .max()
+            .unwrap_or(0);
+
+        Confidence {
+            value,
+            fault_bound,
+            support,
+            runner_up,
+        }
+    }
+
+    /// Returns the largest number of faulty agents expected among `num_votes` voters, when a
+    /// proportion `liar_ratio` of the agents are liars.
+    pub fn fault_bound_from_ratio(num_votes: usize, liar_ratio: f32) -> usize {
+        (num_votes as f32 * liar_ratio.clamp(0.0, 1.0)).ceil() as usize
+    }
+
+    pub fn get_value(&self) -> u64 {
+        self.value
+    }
+
+    pub fn get_fault_bound(&self) -> usize {
+        self.fault_bound
+    }
+
+    pub fn get_support(&self) -> usize {
+        self.support
+    }
+
+    pub fn get_runner_up(&self) -> usize {
+        self.runner_up
+    }
+
+    /// Returns true if enough votes match the network value for it to be provably correct.
+    pub fn is_guaranteed(&self) -> bool {
+        self.support > self.fault_bound
+    }
+
+    /// Returns by how many votes the network value leads the runner-up, which is negative if a
+    /// strategy other than plurality chose a value with fewer votes.
+    pub fn get_margin(&self) -> i64 {
+        self.support as i64 - self.runner_up as i64
+    }
+
+    /// Returns how many more honest replies matching the network value would be needed for it to
+    /// be provably correct.
+    pub fn get_votes_needed(&self) -> usize {
+        (self.fault_bound + 1).saturating_sub(self.support)
+    }
+}
+
+/// Represents the inference strategies that can be selected for a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum InferenceKind {
+    /// The value reported by the most agents.
+    #[default]
+    Plurality,
+    /// The value reported by at least 2f + 1 agents.
+    Supermajority,
+    /// The median of the reported values.
+    Median,
+    /// The mean of the reported values, without the f lowest and f highest ones.
+    TrimmedMean,
+    /// The value with the most votes, weighted by the reputation of each agent.
+    Reputation,
+    /// The most likely value, given how liars draw their values and a prior on the liar ratio.
+    Bayesian,
+}
+
+impl fmt::Display for InferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InferenceKind::Plurality => write!(f, "plurality"),
+            InferenceKind::Supermajority => write!(f, "supermajority"),
+            InferenceKind::Median => write!(f, "median"),
+            InferenceKind::TrimmedMean => write!(f, "trimmed-mean"),
+            InferenceKind::Reputation => write!(f, "reputation"),
+            InferenceKind::Bayesian => write!(f, "bayesian"),
+        }
+    }
+}
+
+impl InferenceKind {
+    /// Returns an instance of the strategy of this kind. Votes are weighted by `reputation`,
+    /// indexed by agent ID, under the `Reputation` strategy. The `Supermajority` and `TrimmedMean`
+    /// strategies tolerate `fault_bound` faulty agents, or as many as possible if None. The
+    /// `Bayesian` strategy expects liars to report values up to `max_value`, and a proportion
+    /// `liar_prior` of the voters to be liars.
+    pub fn build(
+        &self,
+        reputation: HashMap<usize, f64>,
+        fault_bound: Option<usize>,
+        max_value: u64,
+        liar_prior: f64,
+    ) -> Box<dyn InferenceStrategy> {
+        match self {
+            InferenceKind::Plurality => Box::new(Plurality),
+            InferenceKind::Supermajority => Box::new(Supermajority::new(fault_bound)),
+            InferenceKind::Median => Box::new(Median),
+            InferenceKind::TrimmedMean => Box::new(TrimmedMean::new(fault_bound)),
+            InferenceKind::Reputation => Box::new(ReputationWeighted::new(reputation)),
+            InferenceKind::Bayesian => Box::new(Bayesian::new(max_value, liar_prior)),
+        }
+    }
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a vote for every value in `values`, cast by agents 1, 2, 3...
+    fn votes(values: &[u64]) -> Vec<Vote> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| Vote::new(index + 1, value))
+            .collect()
+    }
+
+    #[test]
+    fn test_infer_network_value_ok() {
+        // A single network value should be returned
+        let network_values = Plurality.infer(&votes(&[1, 1, 2, 3, 4, 5, 6, 7])).unwrap();
+        assert_eq!(network_values, vec![1]);
+
+        // Two network values should be returned
+        let network_values = Plurality
+            .infer(&votes(&[1, 1, 2, 3, 4, 4, 5, 6, 7]))
+            .unwrap();
+        assert_eq!(network_values, vec![1, 4]);
+
+        // Multiple network values should be returned
+        let network_values = Plurality.infer(&votes(&[1, 2, 3, 4, 5, 6, 7])).unwrap();
+        assert_eq!(network_values, vec![1, 2, 3, 4, 5, 6, 7]);
+
+        // A coalition of liars sharing a false value outvotes a smaller number of honest agents
+        let network_values = Plurality.infer(&votes(&[5, 5, 9, 9, 9, 2])).unwrap();
+        assert_eq!(network_values, vec![9]);
+
+        // Should return `None`
+        assert!(Plurality.infer(&[]).is_none());
+    }
+
+    #[test]
+    fn test_supermajority() {
+        // With 7 votes, up to 2 faults are tolerated and 5 votes are required
+        let strategy = Supermajority::new(None);
+        assert_eq!(
+            strategy.infer(&votes(&[5, 5, 5, 5, 5, 9, 9])),
+            Some(vec![5])
+        );
+        assert_eq!(strategy.infer(&votes(&[5, 5, 5, 5, 9, 9, 9])), Some(vec![]));
+
+        let strategy = Supermajority::new(Some(1));
+        assert_eq!(
+            strategy.infer(&votes(&[5, 5, 5, 9, 9, 9, 2])),
+            Some(vec![5, 9])
+        );
+        assert!(strategy.infer(&[]).is_none());
+    }
+
+    #[test]
+    fn test_median_and_trimmed_mean() {
+        assert_eq!(Median.infer(&votes(&[9, 5, 1, 5, 100])), Some(vec![5]));
+        assert_eq!(Median.infer(&votes(&[9, 5, 1, 6])), Some(vec![5, 6]));
+        assert_eq!(Median.infer(&votes(&[5, 1, 5, 9])), Some(vec![5]));
+
+        // With 7 votes, the 2 lowest and 2 highest values are discarded
+        let values = votes(&[1, 2, 5, 5, 6, 90, 100]);
+        assert_eq!(TrimmedMean::new(None).infer(&values), Some(vec![5]));
+        assert_eq!(TrimmedMean::new(Some(0)).infer(&values), Some(vec![30]));
+        assert_eq!(TrimmedMean::new(Some(4)).infer(&values), Some(vec![]));
+    }
+
+    #[test]
+    fn test_reputation_weighted() {
+        // Agents 4 and 5 outnumber the honest agents, but have lost most of their reputation
+        let weights = HashMap::from([(4, 0.25), (5, 0.25), (6, 0.5)]);
+        let strategy = ReputationWeighted::new(weights);
+        assert_eq!(strategy.infer(&votes(&[5, 5, 7, 9, 9, 9])), Some(vec![5]));
+        assert_eq!(
+            ReputationWeighted::default().infer(&votes(&[5, 5, 7, 9, 9, 9])),
+            Some(vec![9])
+        );
+    }
+
+    #[test]
+    fn test_confidence() {
+        // With f = 2, three matching votes are enough to include an honest agent
+        let values = votes(&[5, 5, 5, 9, 9, 2, 7]);
+        let confidence = Confidence::assess(&values, 5, 2);
+        assert!(confidence.is_guaranteed());
+        assert_eq!(confidence.get_margin(), 1);
+        assert_eq!(confidence.get_votes_needed(), 0);
+
+        let confidence = Confidence::assess(&values, 5, 3);
+        assert!(!confidence.is_guaranteed());
+        assert_eq!(confidence.get_votes_needed(), 1);
+
+        // A value that nobody reported, e.g, a mean, has no support
+        let confidence = Confidence::assess(&values, 6, 0);
+        assert_eq!((confidence.get_support(), confidence.get_margin()), (0, -3));
+        assert_eq!(confidence.get_votes_needed(), 1);
+
+        assert_eq!(Confidence::fault_bound_from_ratio(7, 0.3), 3);
+        assert_eq!(Confidence::fault_bound_from_ratio(7, 0.0), 0);
+    }
+
+    #[test]
+    fn test_bayesian() {
+        let strategy = Bayesian::new(20, 0.5);
+        let values = votes(&[7, 3, 7, 12, 7]);
+        assert_eq!(strategy.infer(&values), Some(vec![7]));
+        let posterior = strategy.posterior(&values).unwrap();
+        assert_eq!(
+            posterior
+                .iter()
+                .map(|&(value, _)| value)
+                .collect::<Vec<_>>(),
+            [7, 3, 12]
+        );
+        assert!(posterior[0].1 > 0.9);
+        assert!(
+            posterior
+                .iter()
+                .map(|&(_, probability)| probability)
+                .sum::<f64>()
+                < 1.0
+        );
+
+        // Plurality ties in small games remain ties, but with a known probability
+        let values = votes(&[4, 9]);
+        assert_eq!(Bayesian::new(10, 0.5).infer(&values), Some(vec![4, 9]));
+        let posterior = Bayesian::new(10, 0.5).posterior(&values).unwrap();
+        assert_eq!(posterior[0].1, posterior[1].1);
+        assert!(posterior[0].1 < 0.5);
+
+        // If most voters are expected to lie, the value nobody reported is the most likely one
+        assert_eq!(Bayesian::new(3, 0.9).infer(&votes(&[1, 2])), Some(vec![]));
+        assert!(Bayesian::new(3, 0.9).infer(&[]).is_none());
+    }
+}
@@ -71,7 +71,7 @@ impl Keys {
         let private_key_bytes = general_purpose::STANDARD.decode(&self.private_key)?;
 
         let key_pair =
-            signature::Ed25519KeyPair::from_pkcs8(&private_key_bytes.as_ref()).map_err(|e| {
+            signature::Ed25519KeyPair::from_pkcs8(private_key_bytes.as_ref()).map_err(|e| {
                 anyhow::anyhow!(
                     "[!] error: unable to sign message; failed to reconstruct key pair - {}",
                     e
@@ -109,7 +109,7 @@ mod tests {
             .expect("[!] error: failed to decode public key from base64");
 
         // Reconstruct the key pair from the pkcs8 bytes representation
-        let key_pair = signature::Ed25519KeyPair::from_pkcs8(&private_key_bytes.as_ref())
+        let key_pair = signature::Ed25519KeyPair::from_pkcs8(private_key_bytes.as_ref())
             .expect("[!] error: unable to construct key pair");
 
         // Reconstruct the public key from the public key bytes
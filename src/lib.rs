@@ -1,10 +1,22 @@
+pub mod access;
 pub mod agent;
 pub mod agent_config;
 pub mod args;
+pub mod behavior;
 pub mod client;
+pub mod coalition;
+pub mod codec;
 pub mod commands;
+pub mod connection;
 pub mod game;
+pub mod gossip;
+pub mod handshake;
+pub mod inference;
 pub mod keys;
 pub mod message;
 pub mod network_utils;
 pub mod packet;
+pub mod reputation;
+pub mod secure_channel;
+pub mod suspects;
+pub mod topology;
@@ -1,11 +1,16 @@
+use liarslie::agent::LiarOptions;
 use liarslie::args::Args;
+use liarslie::client::RoundPolicy;
 use liarslie::commands::Commands;
+use liarslie::connection::NetworkOptions;
 use liarslie::game::Game;
+use liarslie::inference::InferenceOptions;
 
 #[tokio::main]
 async fn main() {
     let mut game = Game::new();
     Game::print_welcome();
+    game.load_reputation();
 
     loop {
         let user_input = match Game::get_user_input() {
@@ -24,21 +29,102 @@ async fn main() {
                     num_agents,
                     liar_ratio,
                     tamper_chance,
+                    equivocate,
+                    liar_strategy,
+                    coalition_size,
+                    max_frame_size,
+                    encrypted,
+                    codec,
+                    access_policy,
                 } => {
-                    game.start(value, max_value, num_agents, liar_ratio, tamper_chance)
-                        .await
+                    game.start(
+                        value,
+                        max_value,
+                        num_agents,
+                        liar_ratio,
+                        LiarOptions::new(tamper_chance, equivocate, &liar_strategy, coalition_size),
+                        NetworkOptions::new(max_frame_size, encrypted, codec, access_policy),
+                    )
+                    .await
+                }
+                Commands::Play {
+                    agent_timeout,
+                    round_timeout,
+                    retries,
+                    backoff,
+                    strategy,
+                    fault_bound,
+                    adaptive,
+                    liar_prior,
+                } => {
+                    game.play(
+                        RoundPolicy::from_millis(agent_timeout, round_timeout, retries, backoff),
+                        InferenceOptions::new(strategy, fault_bound, adaptive, liar_prior),
+                    )
+                    .await
                 }
-                Commands::Play => game.play().await,
                 Commands::Stop => game.stop().await,
                 Commands::Extend {
                     num_agents,
                     liar_ratio,
-                } => game.extend(num_agents, liar_ratio).await,
+                    liar_strategy,
+                    coalition_size,
+                    coalition,
+                } => {
+                    game.extend(
+                        num_agents,
+                        liar_ratio,
+                        &liar_strategy,
+                        coalition_size,
+                        coalition,
+                    )
+                    .await
+                }
                 Commands::PlayExpert {
                     num_agents,
                     liar_ratio,
-                } => game.play_expert(num_agents, liar_ratio).await,
+                    agent_timeout,
+                    round_timeout,
+                    retries,
+                    backoff,
+                    strategy,
+                    fault_bound,
+                    adaptive,
+                    liar_prior,
+                    ttl,
+                    by_reputation,
+                } => {
+                    game.play_expert(
+                        num_agents,
+                        liar_ratio,
+                        ttl,
+                        RoundPolicy::from_millis(agent_timeout, round_timeout, retries, backoff),
+                        InferenceOptions::new(strategy, fault_bound, adaptive, liar_prior),
+                        by_reputation,
+                    )
+                    .await
+                }
                 Commands::Kill { agent_id } => game.kill(agent_id).await,
+                Commands::Topology {
+                    kind,
+                    degree,
+                    probability,
+                } => game.topology(kind, degree, probability),
+                Commands::Gossip { interval, fanout } => game.gossip(interval, fanout),
+                Commands::Suspects => game.suspects(),
+                Commands::PlayGossip {
+                    agent_id,
+                    agent_timeout,
+                    round_timeout,
+                    retries,
+                    backoff,
+                } => {
+                    game.play_gossip(
+                        agent_id,
+                        RoundPolicy::from_millis(agent_timeout, round_timeout, retries, backoff),
+                    )
+                    .await
+                }
             },
 
             Err(e) => {
@@ -2,48 +2,232 @@ use bincode::{deserialize, serialize};
 use serde::{Deserialize, Serialize};
 
 use crate::agent_config::AgentConfig;
+use crate::handshake::Capabilities;
 use crate::packet::Packet;
 
 /// Represents actions used by the game client and agents to communicate among themselves.
+///
+/// The handshake variants must remain the first variants of this enum, so that their encoding
+/// does not change when other messages are added or modified in later protocol versions.
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub enum Message {
+    /// Sent by the side that opens a connection before any other message. Carries the newest and
+    /// oldest protocol versions the sender can speak, along with the capabilities it supports.
+    MsgHello {
+        version: u16,
+        min_version: u16,
+        capabilities: Capabilities,
+    },
+    /// Sent as a reply to an accepted `MsgHello`. Carries the protocol version that will be used
+    /// on the connection and the capabilities supported by both sides.
+    MsgHelloAck {
+        version: u16,
+        capabilities: Capabilities,
+    },
+    /// Sent as a reply to a `MsgHello` whose protocol versions are incompatible with the
+    /// receiver's. The connection is closed afterwards.
+    MsgHelloReject {
+        version: u16,
+        min_version: u16,
+        reason: String,
+    },
     /// Used to request the receiving agent's value. Should expect a `MsgSendValue` as a reply.
-    MsgQueryValue,
-    /// Used by an agent to send its value as a reply to a `MsgQueryValue`.
-    MsgSendValue { agent_id: usize, value: u64 },
+    /// The `nonce` is chosen by the requester and must be echoed in the reply.
+    MsgQueryValue { nonce: u64 },
+    /// Used by an agent to send its value as a reply to a `MsgQueryValue`. Carries the `nonce`
+    /// of the query it answers, binding the signed value to a single round.
+    MsgSendValue {
+        agent_id: usize,
+        value: u64,
+        nonce: u64,
+    },
     /// Used by the game's client to kill an active agent.
     MsgKillAgent { agent_id: usize },
-    /// Used by the game's client to request an agent to query other agents' values.
+    /// Used by the game's client, or by a relay agent, to request an agent to query other agents'
+    /// values. The `nonce` is propagated by the agent to every `MsgQueryValue` it sends to its
+    /// peers. While `ttl` is greater than 1, peers that cannot be reached directly are fetched
+    /// through neighbours with a decremented `ttl`. `visited` holds the IDs of the relays the
+    /// request has already gone through, which are never asked to relay it again.
     MsgFetchValues {
         agent_id: usize,
         peer_addresses: Vec<AgentConfig>,
+        nonce: u64,
+        ttl: u8,
+        visited: Vec<usize>,
     },
     /// Used by agents to forward other agents' values to the game's client.
     MsgFwdValues {
         agent_id: usize,
         peer_values: Vec<Packet>,
     },
+    /// Sent by an agent in place of a `MsgSendValue` when a `MsgQueryValue` is not authorized by
+    /// its access policy. Carries the `nonce` of the refused query and the `reason` for refusing it.
+    MsgQueryRefused {
+        agent_id: usize,
+        nonce: u64,
+        reason: String,
+    },
+    /// Used by the game's client to request the values of the agents in `agent_ids` from a relay
+    /// agent, for the round identified by `nonce`. Should expect a `MsgSendValues` as a reply.
+    MsgQueryValues { agent_ids: Vec<usize>, nonce: u64 },
+    /// Used by a relay agent to send the signed values it holds for the agents requested in a
+    /// `MsgQueryValues`. Agents whose values are unavailable are left out.
+    MsgSendValues {
+        agent_id: usize,
+        values: Vec<Packet>,
+    },
+    /// Pushed by an agent to a random neighbour, carrying the freshest signed value it holds for
+    /// every agent, including its own. Does not expect a reply.
+    MsgGossipValues {
+        agent_id: usize,
+        values: Vec<Packet>,
+    },
+    /// Used by the game's client to request the collection of values an agent has gathered
+    /// through gossip, during the round identified by `nonce`. Should expect a `MsgSendValues` as
+    /// a reply.
+    MsgQueryGossip { nonce: u64 },
 }
-// NOTE: It would be an improvement to include nonces in messages in order to prevent replay attacks.
 
 impl Message {
-    /// Builds and returns a `MsgQueryValue` serialized into binary format using bincode.
-    /// Takes no parameters.
-    pub fn build_msg_query_value() -> Result<Vec<u8>, bincode::Error> {
-        let message = Message::MsgQueryValue.serialize_message()?;
+    /// Builds a `MsgHello` advertising the protocol versions in the range
+    /// `min_version..=version` and the set of `capabilities`. Returns the message serialized into
+    /// binary format using bincode.
+    pub fn build_msg_hello(
+        version: u16,
+        min_version: u16,
+        capabilities: Capabilities,
+    ) -> Result<Vec<u8>, bincode::Error> {
+        let message = Message::MsgHello {
+            version,
+            min_version,
+            capabilities,
+        }
+        .serialize_message()?;
+        Ok(message)
+    }
+
+    /// Builds a `MsgHelloAck` containing the negotiated protocol `version` and `capabilities`.
+    /// Returns the message serialized into binary format using bincode.
+    pub fn build_msg_hello_ack(
+        version: u16,
+        capabilities: Capabilities,
+    ) -> Result<Vec<u8>, bincode::Error> {
+        let message = Message::MsgHelloAck {
+            version,
+            capabilities,
+        }
+        .serialize_message()?;
+        Ok(message)
+    }
+
+    /// Builds a `MsgHelloReject` containing the range of protocol versions supported by the
+    /// sender and the `reason` for the rejection. Returns the message serialized into binary
+    /// format using bincode.
+    pub fn build_msg_hello_reject(
+        version: u16,
+        min_version: u16,
+        reason: &str,
+    ) -> Result<Vec<u8>, bincode::Error> {
+        let message = Message::MsgHelloReject {
+            version,
+            min_version,
+            reason: reason.to_owned(),
+        }
+        .serialize_message()?;
+        Ok(message)
+    }
+
+    /// Builds a `MsgQueryValue` containing the requester's `nonce` and returns it serialized into
+    /// binary format using bincode.
+    pub fn build_msg_query_value(nonce: u64) -> Result<Vec<u8>, bincode::Error> {
+        let message = Message::MsgQueryValue { nonce }.serialize_message()?;
+        Ok(message)
+    }
+
+    /// Builds a `MsgQueryRefused` containing the refusing agent's ID `agent_id`, the `nonce` of the
+    /// refused query and the `reason` for the refusal. Returns the message serialized into binary
+    /// format using bincode.
+    pub fn build_msg_query_refused(
+        agent_id: usize,
+        nonce: u64,
+        reason: &str,
+    ) -> Result<Vec<u8>, bincode::Error> {
+        let message = Message::MsgQueryRefused {
+            agent_id,
+            nonce,
+            reason: reason.to_owned(),
+        }
+        .serialize_message()?;
+        Ok(message)
+    }
+
+    /// Builds a `MsgQueryValues` requesting the values of the agents in `agent_ids` during the round
+    /// identified by `nonce`. Returns the message serialized into binary format using bincode.
+    pub fn build_msg_query_values(
+        agent_ids: &[usize],
+        nonce: u64,
+    ) -> Result<Vec<u8>, bincode::Error> {
+        let message = Message::MsgQueryValues {
+            agent_ids: agent_ids.to_vec(),
+            nonce,
+        }
+        .serialize_message()?;
+        Ok(message)
+    }
+
+    /// Builds a `MsgSendValues` containing the relay agent's ID `agent_id` and the signed `values`
+    /// it holds for the requested agents. Returns the message serialized into binary format using
+    /// bincode.
+    pub fn build_msg_send_values(
+        agent_id: usize,
+        values: &[Packet],
+    ) -> Result<Vec<u8>, bincode::Error> {
+        let message = Message::MsgSendValues {
+            agent_id,
+            values: values.to_vec(),
+        }
+        .serialize_message()?;
+        Ok(message)
+    }
+
+    /// Builds a `MsgGossipValues` containing the sending agent's ID `agent_id` and the signed
+    /// `values` it holds. Returns the message serialized into binary format using bincode.
+    pub fn build_msg_gossip_values(
+        agent_id: usize,
+        values: &[Packet],
+    ) -> Result<Vec<u8>, bincode::Error> {
+        let message = Message::MsgGossipValues {
+            agent_id,
+            values: values.to_vec(),
+        }
+        .serialize_message()?;
+        Ok(message)
+    }
+
+    /// Builds a `MsgQueryGossip` containing the client's `nonce` and returns it serialized into
+    /// binary format using bincode.
+    pub fn build_msg_query_gossip(nonce: u64) -> Result<Vec<u8>, bincode::Error> {
+        let message = Message::MsgQueryGossip { nonce }.serialize_message()?;
         Ok(message)
     }
 
-    /// Builds a `MsgFetchValues` containing a target agent ID `agent_id` and a list of
-    /// peer_addresses as a `Vec<AgentConfig>`. Returns the message serialized into binary format
-    /// using bincode.
+    /// Builds a `MsgFetchValues` containing a target agent ID `agent_id`, a list of
+    /// peer_addresses as a `Vec<AgentConfig>`, the round's `nonce`, the remaining hop limit `ttl`
+    /// and the IDs of the relays already `visited`. Returns the message serialized into binary
+    /// format using bincode.
     pub fn build_msg_fetch_values(
         agent_id: usize,
-        peers: &Vec<AgentConfig>,
+        peers: &[AgentConfig],
+        nonce: u64,
+        ttl: u8,
+        visited: &[usize],
     ) -> Result<Vec<u8>, bincode::Error> {
         let message = Message::MsgFetchValues {
             agent_id,
             peer_addresses: peers.to_vec(),
+            nonce,
+            ttl,
+            visited: visited.to_vec(),
         }
         .serialize_message()?;
         Ok(message)
@@ -54,7 +238,7 @@ impl Message {
     /// serialized into binary format using bincode.
     pub fn build_msg_fwd_values(
         agent_id: usize,
-        peer_replies: &Vec<Packet>,
+        peer_replies: &[Packet],
     ) -> Result<Vec<u8>, bincode::Error> {
         let message = Message::MsgFwdValues {
             agent_id,
@@ -64,10 +248,19 @@ impl Message {
         Ok(message)
     }
 
-    /// Builds a `MsgSendValue` containing `value` and `agent_id` and returns it serialized into
-    /// binary format.
-    pub fn build_msg_send_value(value: u64, agent_id: usize) -> Result<Vec<u8>, bincode::Error> {
-        let message = Message::MsgSendValue { value, agent_id }.serialize_message()?;
+    /// Builds a `MsgSendValue` containing `value`, `agent_id` and the `nonce` of the query being
+    /// answered and returns it serialized into binary format.
+    pub fn build_msg_send_value(
+        value: u64,
+        agent_id: usize,
+        nonce: u64,
+    ) -> Result<Vec<u8>, bincode::Error> {
+        let message = Message::MsgSendValue {
+            value,
+            agent_id,
+            nonce,
+        }
+        .serialize_message()?;
         Ok(message)
     }
 
@@ -98,11 +291,30 @@ impl Message {
 mod tests {
     use super::*;
 
+    #[test]
+    fn build_msg_hello_ok() {
+        let message = Message::build_msg_hello(2, 1, Capabilities::NONCES);
+        assert!(message.is_ok());
+
+        assert_eq!(
+            Message::deserialize_message(&message.unwrap()).unwrap(),
+            Message::MsgHello {
+                version: 2,
+                min_version: 1,
+                capabilities: Capabilities::NONCES,
+            }
+        );
+    }
+
     #[test]
     fn test_build_msg_query_value_ok() {
-        let message = Message::build_msg_query_value();
+        let message = Message::build_msg_query_value(42);
         assert!(message.is_ok());
-        assert!(!message.unwrap().is_empty());
+
+        assert_eq!(
+            Message::deserialize_message(&message.unwrap()).unwrap(),
+            Message::MsgQueryValue { nonce: 42 }
+        );
     }
 
     #[test]
@@ -123,7 +335,7 @@ mod tests {
             ),
         ];
 
-        let message = Message::build_msg_fetch_values(agent_id, &peers);
+        let message = Message::build_msg_fetch_values(agent_id, &peers, 99, 3, &[4]);
         assert!(message.is_ok());
 
         assert_eq!(
@@ -143,14 +355,17 @@ mod tests {
                         9002,
                         "Hv9PImawhJ9+0ulJ/dlKjxTu+vKcKnyoJG5ahh4+DjY=",
                     ),
-                ]
+                ],
+                nonce: 99,
+                ttl: 3,
+                visited: vec![4],
             }
         );
     }
 
     #[test]
     fn build_msg_send_value_ok() {
-        let message = Message::build_msg_send_value(10, 1);
+        let message = Message::build_msg_send_value(10, 1, 7);
         assert!(message.is_ok());
 
         assert_eq!(
@@ -158,6 +373,7 @@ mod tests {
             Message::MsgSendValue {
                 agent_id: 1,
                 value: 10,
+                nonce: 7,
             }
         );
     }
@@ -173,30 +389,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn build_msg_query_refused_ok() {
+        let message = Message::build_msg_query_refused(4, 9, "Agent 3 is not a known peer");
+        assert!(message.is_ok());
+
+        assert_eq!(
+            Message::deserialize_message(&message.unwrap()).unwrap(),
+            Message::MsgQueryRefused {
+                agent_id: 4,
+                nonce: 9,
+                reason: "Agent 3 is not a known peer".to_owned()
+            }
+        );
+    }
+
     #[test]
     fn build_msg_fwd_values_ok() {
-        let message1 = Message::build_msg_send_value(10, 1).unwrap();
-        let message2 = Message::build_msg_send_value(15, 2).unwrap();
+        let message1 = Message::build_msg_send_value(10, 1, 3).unwrap();
+        let message2 = Message::build_msg_send_value(15, 2, 3).unwrap();
 
-        let packet1 = Packet::new(message1.clone(), None);
-        let packet2 = Packet::new(message2.clone(), None);
+        let packet1 = Packet::new(1, 50, 3, message1);
+        let packet2 = Packet::new(2, 50, 3, message2);
 
-        let msg_fwd_values = Message::build_msg_fwd_values(50, &vec![packet1, packet2]);
+        let msg_fwd_values = Message::build_msg_fwd_values(50, &[packet1.clone(), packet2.clone()]);
 
         assert_eq!(
             Message::deserialize_message(&msg_fwd_values.unwrap()).unwrap(),
             Message::MsgFwdValues {
                 agent_id: 50,
-                peer_values: vec![
-                    Packet {
-                        message: message1,
-                        msg_sig: None
-                    },
-                    Packet {
-                        message: message2,
-                        msg_sig: None
-                    }
-                ]
+                peer_values: vec![packet1, packet2]
+            }
+        )
+    }
+
+    #[test]
+    fn build_msg_query_values_ok() {
+        let message = Message::build_msg_query_values(&[1, 4], 9);
+        assert!(message.is_ok());
+
+        assert_eq!(
+            Message::deserialize_message(&message.unwrap()).unwrap(),
+            Message::MsgQueryValues {
+                agent_ids: vec![1, 4],
+                nonce: 9
+            }
+        );
+    }
+
+    #[test]
+    fn build_msg_send_values_ok() {
+        let value = Message::build_msg_send_value(10, 1, 9).unwrap();
+        let packet = Packet::new(1, 3, 9, value);
+
+        let msg_send_values = Message::build_msg_send_values(3, std::slice::from_ref(&packet));
+
+        assert_eq!(
+            Message::deserialize_message(&msg_send_values.unwrap()).unwrap(),
+            Message::MsgSendValues {
+                agent_id: 3,
+                values: vec![packet]
+            }
+        )
+    }
+
+    #[test]
+    fn build_msg_gossip_values_ok() {
+        let message = Message::build_msg_send_value(10, 1, 0).unwrap();
+        let packet = Packet::new(1, 0, 0, message);
+
+        let msg_gossip_values = Message::build_msg_gossip_values(2, std::slice::from_ref(&packet));
+
+        assert_eq!(
+            Message::deserialize_message(&msg_gossip_values.unwrap()).unwrap(),
+            Message::MsgGossipValues {
+                agent_id: 2,
+                values: vec![packet]
             }
         )
     }
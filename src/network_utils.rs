@@ -1,34 +1,217 @@
-use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+use std::fmt;
+use std::time::Duration;
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+/// The default maximum size, in bytes, of a single frame.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 4 * 1024 * 1024;
+/// The default time allowed for a frame to be read once it is expected.
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// The default time allowed for a frame to be written.
+pub const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Represents the limits enforced when reading and writing length-prefixed frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameConfig {
+    /// The maximum size, in bytes, of a frame. Larger frames are neither sent nor received.
+    max_frame_size: usize,
+    /// The time allowed for a frame to be read once it is expected.
+    read_timeout: Duration,
+    /// The time allowed for a frame to be written.
+    write_timeout: Duration,
+}
+
+impl Default for FrameConfig {
+    fn default() -> Self {
+        FrameConfig {
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            write_timeout: DEFAULT_WRITE_TIMEOUT,
+        }
+    }
+}
+
+impl FrameConfig {
+    /// Returns a new instance of `FrameConfig` initialized with the values from `max_frame_size`,
+    /// `read_timeout` and `write_timeout`.
+    pub fn new(max_frame_size: usize, read_timeout: Duration, write_timeout: Duration) -> Self {
+        FrameConfig {
+            max_frame_size,
+            read_timeout,
+            write_timeout,
+        }
+    }
+
+    /// Returns a copy of `self` with the maximum frame size set to `max_frame_size`.
+    pub fn with_max_frame_size(self, max_frame_size: usize) -> Self {
+        FrameConfig {
+            max_frame_size,
+            ..self
+        }
+    }
+
+    /// Returns the maximum size, in bytes, of a frame.
+    pub fn get_max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Returns the time allowed for a frame to be read once it is expected.
+    pub fn get_read_timeout(&self) -> Duration {
+        self.read_timeout
+    }
+
+    /// Returns the time allowed for a frame to be written.
+    pub fn get_write_timeout(&self) -> Duration {
+        self.write_timeout
+    }
+}
+
+/// Represents the ways in which reading or writing a frame can fail.
+#[derive(Debug)]
+pub enum FrameError {
+    /// The peer closed the connection before a new frame was started.
+    PeerClosed,
+    /// The peer closed the connection in the middle of a frame.
+    Truncated { expected: usize, received: usize },
+    /// The frame is larger than the configured maximum frame size.
+    Oversized {
+        length: usize,
+        max_frame_size: usize,
+    },
+    /// The frame was not read or written before the deadline expired.
+    Timeout(Duration),
+    /// The contents of the frame could not be decoded.
+    Malformed(bincode::Error),
+    /// The frame could not be encrypted, or failed to decrypt on an encrypted connection.
+    Encryption,
+    /// Any other I/O error reported by the underlying socket.
+    Io(io::Error),
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::PeerClosed => write!(f, "peer closed the connection"),
+            FrameError::Truncated { expected, received } => write!(
+                f,
+                "truncated frame; expected {} bytes, received {}",
+                expected, received
+            ),
+            FrameError::Oversized {
+                length,
+                max_frame_size,
+            } => write!(
+                f,
+                "oversized frame of {} bytes; the maximum frame size is {} bytes",
+                length, max_frame_size
+            ),
+            FrameError::Timeout(deadline) => {
+                write!(f, "timed out after {} ms", deadline.as_millis())
+            }
+            FrameError::Malformed(e) => write!(f, "malformed frame - {}", e),
+            FrameError::Encryption => write!(f, "frame failed encryption or authentication"),
+            FrameError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+impl From<io::Error> for FrameError {
+    fn from(e: io::Error) -> Self {
+        FrameError::Io(e)
+    }
+}
+
 /// Returns the length of `data` as a big-endian 4 bytes array.
 pub fn get_length(data: &[u8]) -> [u8; 4] {
     (data.len() as u32).to_be_bytes()
 }
 
-/// Attempts to write `packet` to `socket`. Returns `tokio::io:Error`` upon failure.
-pub async fn send_packet(packet: &[u8], socket: &mut TcpStream) -> io::Result<()> {
+/// Attempts to write `packet`, preceded by its length prefix, to `socket`. Returns
+/// `FrameError::Oversized` without writing anything if `packet` exceeds the maximum frame size,
+/// and `FrameError::Timeout` if the write does not complete before the write deadline.
+pub async fn send_packet<W>(
+    packet: &[u8],
+    socket: &mut W,
+    config: &FrameConfig,
+) -> Result<(), FrameError>
+where
+    W: AsyncWrite + Unpin,
+{
+    if packet.len() > config.max_frame_size {
+        return Err(FrameError::Oversized {
+            length: packet.len(),
+            max_frame_size: config.max_frame_size,
+        });
+    }
+
     let packet_len = get_length(packet);
 
-    // Send the length prefix
-    socket.write_all(&packet_len).await?;
+    let write = async {
+        // Send the length prefix
+        socket.write_all(&packet_len).await?;
 
-    // Send the packet
-    socket.write_all(packet).await?;
+        // Send the packet
+        socket.write_all(packet).await?;
 
+        Ok(())
+    };
+
+    match timeout(config.write_timeout, write).await {
+        Ok(result) => result,
+        Err(_) => Err(FrameError::Timeout(config.write_timeout)),
+    }
+}
+
+/// Reads exactly `buffer.len()` bytes from `socket`. Returns `FrameError::PeerClosed` if the
+/// connection is closed before any byte is read and `FrameError::Truncated` if it is closed after
+/// only part of the buffer was filled.
+async fn read_full<R>(socket: &mut R, buffer: &mut [u8]) -> Result<(), FrameError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut received = 0;
+    while received < buffer.len() {
+        match socket.read(&mut buffer[received..]).await? {
+            0 if received == 0 => return Err(FrameError::PeerClosed),
+            0 => {
+                return Err(FrameError::Truncated {
+                    expected: buffer.len(),
+                    received,
+                })
+            }
+            read => received += read,
+        }
+    }
     Ok(())
 }
 
-/// Reads a packet containing a length prefix from a TcpStream and returns it as usize.
-pub async fn read_length_prefix(socket: &mut TcpStream) -> Result<usize, io::Error> {
+/// Reads a length prefix from a TcpStream and returns it as usize. Returns
+/// `FrameError::Oversized` if the announced length exceeds the maximum frame size.
+pub async fn read_length_prefix<R>(
+    socket: &mut R,
+    config: &FrameConfig,
+) -> Result<usize, FrameError>
+where
+    R: AsyncRead + Unpin,
+{
     let mut buffer_length = [0u8; 4];
 
     // Read 4 bytes from the TcpStream
-    socket
-        .read_exact(&mut buffer_length)
-        .await
-        .expect("[!] error: failed to read data from socket\n");
+    read_full(socket, &mut buffer_length).await?;
 
     let packet_length = u32::from_be_bytes(buffer_length) as usize;
+
+    // Refuse to allocate a buffer for a frame larger than the configured maximum
+    if packet_length > config.max_frame_size {
+        return Err(FrameError::Oversized {
+            length: packet_length,
+            max_frame_size: config.max_frame_size,
+        });
+    }
+
     Ok(packet_length)
 }
 
@@ -37,18 +220,56 @@ fn alloc_buffer(length: usize) -> Vec<u8> {
     vec![0u8; length]
 }
 
-/// Reads a packet from a TcpStream `socket` and returns it as a String.
-pub async fn recv_packet(socket: &mut TcpStream) -> Result<Vec<u8>, io::Error> {
-    // Read the 4 bytes length prefix
-    let packet_length = read_length_prefix(socket).await?;
-
+/// Reads the body of a frame whose length prefix has already been read.
+async fn read_packet_body<R>(socket: &mut R, packet_length: usize) -> Result<Vec<u8>, FrameError>
+where
+    R: AsyncRead + Unpin,
+{
     // Allocate a buffer with the same length as the incoming packet
     let mut buffer = alloc_buffer(packet_length);
 
-    // Read the packet into the buffer
-    socket.read_exact(&mut buffer).await?;
+    // Read the packet into the buffer. A connection closed at this point always truncates the frame.
+    match read_full(socket, &mut buffer).await {
+        Err(FrameError::PeerClosed) => Err(FrameError::Truncated {
+            expected: packet_length,
+            received: 0,
+        }),
+        Err(e) => Err(e),
+        Ok(()) => Ok(buffer),
+    }
+}
+
+/// Reads a packet from a TcpStream `socket` and returns it as a byte vector. The whole packet must
+/// be received before the read deadline expires. Used when a reply is expected from the peer.
+pub async fn recv_packet<R>(socket: &mut R, config: &FrameConfig) -> Result<Vec<u8>, FrameError>
+where
+    R: AsyncRead + Unpin,
+{
+    let read = async {
+        // Read the 4 bytes length prefix
+        let packet_length = read_length_prefix(socket, config).await?;
+        read_packet_body(socket, packet_length).await
+    };
 
-    Ok(buffer)
+    match timeout(config.read_timeout, read).await {
+        Ok(result) => result,
+        Err(_) => Err(FrameError::Timeout(config.read_timeout)),
+    }
+}
+
+/// Waits for the next packet on an idle, persistent connection and returns it as a byte vector.
+/// The wait for the length prefix is unbounded, but once a frame has started the rest of it must
+/// be received before the read deadline expires.
+pub async fn wait_packet<R>(socket: &mut R, config: &FrameConfig) -> Result<Vec<u8>, FrameError>
+where
+    R: AsyncRead + Unpin,
+{
+    let packet_length = read_length_prefix(socket, config).await?;
+
+    match timeout(config.read_timeout, read_packet_body(socket, packet_length)).await {
+        Ok(result) => result,
+        Err(_) => Err(FrameError::Timeout(config.read_timeout)),
+    }
 }
 
 /// Attempts to establish a connection to `address`:`port` and return the
@@ -56,3 +277,114 @@ pub async fn recv_packet(socket: &mut TcpStream) -> Result<Vec<u8>, io::Error> {
 pub async fn connect(address: &str, port: usize) -> Result<TcpStream, io::Error> {
     TcpStream::connect(format!("{}:{}", address, port,)).await
 }
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_recv_packet_ok() {
+        let config = FrameConfig::default();
+        let (mut sender, mut receiver) = io::duplex(64);
+
+        send_packet(b"Tis but a scratch!", &mut sender, &config)
+            .await
+            .unwrap();
+        assert_eq!(
+            recv_packet(&mut receiver, &config).await.unwrap(),
+            b"Tis but a scratch!"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recv_packet_peer_closed() {
+        let config = FrameConfig::default();
+        let (sender, mut receiver) = io::duplex(64);
+        drop(sender);
+
+        assert!(matches!(
+            recv_packet(&mut receiver, &config).await,
+            Err(FrameError::PeerClosed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recv_packet_truncated() {
+        let config = FrameConfig::default();
+        let (mut sender, mut receiver) = io::duplex(64);
+
+        // A prefix announcing 10 bytes followed by only 3 bytes
+        sender.write_all(&10u32.to_be_bytes()).await.unwrap();
+        sender.write_all(&[1, 2, 3]).await.unwrap();
+        drop(sender);
+
+        assert!(matches!(
+            recv_packet(&mut receiver, &config).await,
+            Err(FrameError::Truncated {
+                expected: 10,
+                received: 3
+            })
+        ));
+
+        // A connection closed in the middle of the length prefix
+        let (mut sender, mut receiver) = io::duplex(64);
+        sender.write_all(&[0, 0]).await.unwrap();
+        drop(sender);
+
+        assert!(matches!(
+            recv_packet(&mut receiver, &config).await,
+            Err(FrameError::Truncated {
+                expected: 4,
+                received: 2
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_recv_packet_oversized() {
+        let config = FrameConfig::default().with_max_frame_size(16);
+        let (mut sender, mut receiver) = io::duplex(64);
+
+        // A prefix of 0xFFFFFFFF must be rejected before any allocation takes place
+        sender.write_all(&[0xFF; 4]).await.unwrap();
+
+        assert!(matches!(
+            recv_packet(&mut receiver, &config).await,
+            Err(FrameError::Oversized {
+                length: 0xFFFF_FFFF,
+                max_frame_size: 16
+            })
+        ));
+
+        // Oversized packets are not sent either
+        assert!(matches!(
+            send_packet(&[0; 17], &mut sender, &config).await,
+            Err(FrameError::Oversized { .. })
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_recv_packet_timeout() {
+        let config = FrameConfig::default();
+        let (mut sender, mut receiver) = io::duplex(64);
+
+        // A peer that announces a frame but never sends its body
+        sender.write_all(&10u32.to_be_bytes()).await.unwrap();
+
+        assert!(matches!(
+            recv_packet(&mut receiver, &config).await,
+            Err(FrameError::Timeout(_))
+        ));
+
+        // Idle connections may wait indefinitely, but a started frame must still complete in time
+        sender.write_all(&10u32.to_be_bytes()).await.unwrap();
+        assert!(matches!(
+            wait_packet(&mut receiver, &config).await,
+            Err(FrameError::Timeout(_))
+        ));
+    }
+}
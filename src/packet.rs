@@ -1,36 +1,275 @@
-use bincode::{deserialize, serialize};
-use serde::{Deserialize, Serialize};
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use anyhow::bail;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::codec::{Codec, CodecError};
+use crate::keys::Keys;
+use crate::message::Message;
+
+/// The ID used by the game's client in the `sender` and `recipient` fields of a `Packet`. Agent IDs
+/// start at 1, so this ID is never assigned to an agent.
+pub const CLIENT_ID: usize = 0;
+
+/// How far, in either direction, the timestamp of a received `Packet` may be from the local clock
+/// before the packet is considered stale.
+pub const FRESHNESS_WINDOW: Duration = Duration::from_secs(30);
 
 /// Encapsulates message data to be sent between the game's client and agents.
 ///
-/// A `Packet` contains a field `message`, which specifies a request or a response, and
-/// an optional field `msg_sig` which contains a signature of `message` by the sender.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+/// A `Packet` is an envelope containing a field `message`, which specifies a request or a response,
+/// the context in which it was sent, i.e, the IDs of its `sender` and intended `recipient`, the
+/// `round` it belongs to and the `timestamp` at which it was built, and an optional field `msg_sig`
+/// which contains a signature of the message and its context by the sender. Since the context is
+/// signed, a signed message cannot be lifted into a different conversation or replayed later on.
+///
+/// `message` always holds the canonical bincode encoding of a `Message`, which is what gets signed.
+/// On the wire, however, the message is laid out as a structured value in the format of the codec
+/// in use, and it is converted back into its canonical bytes when the packet is decoded.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Packet {
+    /// The ID of the sender, either an agent ID or `CLIENT_ID`.
+    pub sender: usize,
+    /// The ID of the intended recipient, either an agent ID or `CLIENT_ID`.
+    pub recipient: usize,
+    /// The ID of the round this packet belongs to. Packets sent outside of a round use 0.
+    pub round: u64,
+    /// The time at which the packet was built, in milliseconds since the UNIX epoch.
+    pub timestamp: u64,
     /// A message containing the data to be sent.
     pub message: Vec<u8>,
-    /// An optional signature of the message for authentication purposes.
+    /// An optional signature of the message and its context for authentication purposes.
     pub msg_sig: Option<Vec<u8>>,
 }
 
+/// The layout of a `Packet` on the wire when it is being encoded.
+#[derive(Serialize)]
+#[serde(rename = "Packet")]
+struct WirePacketRef<'a> {
+    sender: usize,
+    recipient: usize,
+    round: u64,
+    timestamp: u64,
+    message: Message,
+    msg_sig: &'a Option<Vec<u8>>,
+}
+
+/// The layout of a `Packet` on the wire when it is being decoded.
+#[derive(Deserialize)]
+#[serde(rename = "Packet")]
+struct WirePacket {
+    sender: usize,
+    recipient: usize,
+    round: u64,
+    timestamp: u64,
+    message: Message,
+    msg_sig: Option<Vec<u8>>,
+}
+
+impl Serialize for Packet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let message = Message::deserialize_message(&self.message).map_err(S::Error::custom)?;
+        WirePacketRef {
+            sender: self.sender,
+            recipient: self.recipient,
+            round: self.round,
+            timestamp: self.timestamp,
+            message,
+            msg_sig: &self.msg_sig,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Packet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let packet = WirePacket::deserialize(deserializer)?;
+        Ok(Packet {
+            sender: packet.sender,
+            recipient: packet.recipient,
+            round: packet.round,
+            timestamp: packet.timestamp,
+            message: packet
+                .message
+                .serialize_message()
+                .map_err(D::Error::custom)?,
+            msg_sig: packet.msg_sig,
+        })
+    }
+}
+
 impl Packet {
-    pub fn new(message: Vec<u8>, msg_sig: Option<Vec<u8>>) -> Self {
-        Packet { message, msg_sig }
+    /// Returns a new, unsigned instance of `Packet` containing `message`, sent by `sender` to
+    /// `recipient` during `round` and stamped with the current time.
+    pub fn new(sender: usize, recipient: usize, round: u64, message: Vec<u8>) -> Self {
+        Packet {
+            sender,
+            recipient,
+            round,
+            timestamp: Self::now(),
+            message,
+            msg_sig: None,
+        }
+    }
+
+    /// Returns a new, unsigned instance of `Packet` containing `message` that is not addressed to
+    /// anyone in particular. Used before the identities of both ends are known, e.g, during the
+    /// protocol handshake.
+    pub fn unaddressed(message: Vec<u8>) -> Self {
+        Self::new(CLIENT_ID, CLIENT_ID, 0, message)
+    }
+
+    /// Signs the message and its context with `keys` and returns the signed packet.
+    pub fn sign(mut self, keys: &Keys) -> anyhow::Result<Self> {
+        self.msg_sig = Some(keys.sign(&self.signed_bytes()?)?);
+        Ok(self)
+    }
+
+    /// Returns the bytes covered by the signature of the packet, i.e, the message along with the
+    /// IDs of the sender, recipient and round and the timestamp.
+    pub fn signed_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(&(
+            self.sender,
+            self.recipient,
+            self.round,
+            self.timestamp,
+            &self.message,
+        ))
+    }
+
+    /// Verifies if the packet carries a valid signature of its message and context by the owner
+    /// of the private key that corresponds to `public_key`. Returns Ok(()) if the signature is valid.
+    pub fn verify(&self, public_key: &str) -> anyhow::Result<()> {
+        match &self.msg_sig {
+            Some(signature) => Keys::verify(&self.signed_bytes()?, signature, public_key),
+            None => bail!("[!] error: packet requires a signature, but contains None\n"),
+        }
+    }
+
+    /// Checks that the packet is addressed to `recipient` and that its timestamp is within
+    /// `window` of the current time. Returns Ok(()) if the packet should be accepted.
+    pub fn check_context(&self, recipient: usize, window: Duration) -> anyhow::Result<()> {
+        if self.recipient != recipient {
+            bail!(
+                "[!] error: packet was addressed to {}, but received by {}\n",
+                Self::describe_id(self.recipient),
+                Self::describe_id(recipient)
+            );
+        }
+
+        let age = Self::now().abs_diff(self.timestamp);
+        if u128::from(age) > window.as_millis() {
+            bail!(
+                "[!] error: packet from {} is {} ms away from the local clock; possible replay\n",
+                Self::describe_id(self.sender),
+                age
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the packet encoded with `codec`.
+    pub fn encode(&self, codec: Codec) -> Result<Vec<u8>, CodecError> {
+        codec.encode(self)
+    }
+
+    /// Receives a byte array `data`, expected to be encoded with `codec`, and attempts to decode
+    /// it into an instance of `Packet`. Returns `CodecError` if the format of `data` is invalid.
+    pub fn unpack(data: &[u8], codec: Codec) -> Result<Self, CodecError> {
+        codec.decode(data)
+    }
+
+    /// Returns a readable description of a sender or recipient ID.
+    fn describe_id(id: usize) -> String {
+        match id {
+            CLIENT_ID => "the game's client".to_owned(),
+            agent_id => format!("Agent {}", agent_id),
+        }
+    }
+
+    /// Returns the current time in milliseconds since the UNIX epoch.
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0)
+    }
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A packet re-encoded with any codec must still carry the exact bytes that were signed
+    #[test]
+    fn test_packet_codecs_preserve_signature() {
+        let keys = Keys::new_key_pair();
+        let message = Message::build_msg_send_value(5, 1, 42).unwrap();
+        let forwarded = Packet::new(1, 2, 42, message).sign(&keys).unwrap();
+        let fwd_values = Message::build_msg_fwd_values(2, &[forwarded]).unwrap();
+
+        for codec in [Codec::Bincode, Codec::Json, Codec::Cbor] {
+            let data = Packet::unaddressed(fwd_values.clone())
+                .encode(codec)
+                .unwrap();
+            let packet = Packet::unpack(&data, codec).unwrap();
+            assert_eq!(packet.message, fwd_values);
+
+            match Message::deserialize_message(&packet.message).unwrap() {
+                Message::MsgFwdValues { peer_values, .. } => {
+                    assert!(peer_values[0].verify(keys.get_public_key()).is_ok());
+                }
+                other => panic!("unexpected message {:?}", other),
+            }
+        }
     }
 
-    /// Builds a new instance of `Packet`, containing a message `message` and an optional message
-    /// signature `msg_sig`, and returns it serialized into binary format.
-    pub fn build_packet(
-        message: Vec<u8>,
-        msg_sig: Option<Vec<u8>>,
-    ) -> Result<Vec<u8>, bincode::Error> {
-        let packet = Self::new(message, msg_sig);
-        serialize(&packet)
+    // Changing any part of the context must invalidate the signature
+    #[test]
+    fn test_packet_signature_covers_context() {
+        let keys = Keys::new_key_pair();
+        let message = Message::build_msg_send_value(5, 1, 42).unwrap();
+        let packet = Packet::new(1, CLIENT_ID, 42, message).sign(&keys).unwrap();
+        assert!(packet.verify(keys.get_public_key()).is_ok());
+
+        let mut redirected = packet.clone();
+        redirected.recipient = 3;
+        assert!(redirected.verify(keys.get_public_key()).is_err());
+
+        let mut other_round = packet.clone();
+        other_round.round = 43;
+        assert!(other_round.verify(keys.get_public_key()).is_err());
+
+        let mut delayed = packet.clone();
+        delayed.timestamp += 1;
+        assert!(delayed.verify(keys.get_public_key()).is_err());
+
+        let unsigned = Packet::new(1, CLIENT_ID, 42, packet.message.clone());
+        assert!(unsigned.verify(keys.get_public_key()).is_err());
     }
 
-    /// Receives a byte array `data`, expected to be in binary format, and attempts to deserialize
-    /// it into an instance of `Packet`. Returns `bincode::Error` if the format of `data` is invalid.
-    pub fn unpack(data: &[u8]) -> Result<Self, bincode::Error> {
-        deserialize(data)
+    #[test]
+    fn test_packet_check_context() {
+        let message = Message::build_msg_query_value(7).unwrap();
+        let packet = Packet::new(CLIENT_ID, 1, 7, message);
+        assert!(packet.check_context(1, FRESHNESS_WINDOW).is_ok());
+
+        // Addressed to someone else
+        assert!(packet.check_context(2, FRESHNESS_WINDOW).is_err());
+
+        // Outside the freshness window, in the past or in the future
+        let mut stale = packet.clone();
+        stale.timestamp -= FRESHNESS_WINDOW.as_millis() as u64 + 1000;
+        assert!(stale.check_context(1, FRESHNESS_WINDOW).is_err());
+
+        let mut early = packet;
+        early.timestamp += FRESHNESS_WINDOW.as_millis() as u64 + 1000;
+        assert!(early.check_context(1, FRESHNESS_WINDOW).is_err());
     }
 }
@@ -0,0 +1,191 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The file in which the client keeps the reputation of agents between sessions.
+pub const REPUTATION_FILE: &str = "reputation.json";
+/// How many disagreements a single equivocation, or relayed tampered packet, is worth.
+const MISBEHAVIOUR_PENALTY: u64 = 2;
+
+/// The behaviour observed from a single agent, identified by its public key, over every round in
+/// which it took part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ReputationRecord {
+    /// The number of rounds in which the agent reported the accepted network value.
+    agreements: u64,
+    /// The number of rounds in which the agent reported a value other than the accepted one.
+    disagreements: u64,
+    /// The number of rounds in which the agent signed conflicting values.
+    equivocations: u64,
+    /// The number of rejected packets the agent relayed.
+    tampering: u64,
+}
+
+impl ReputationRecord {
+    pub fn get_agreements(&self) -> u64 {
+        self.agreements
+    }
+
+    pub fn get_disagreements(&self) -> u64 {
+        self.disagreements
+    }
+
+    pub fn get_equivocations(&self) -> u64 {
+        self.equivocations
+    }
+
+    pub fn get_tampering(&self) -> u64 {
+        self.tampering
+    }
+
+    /// Returns the agent's score, between 0 and 1. Every agent starts at 0.5, and its score goes
+    /// up with every agreement and down with every disagreement, while every equivocation or
+    /// tampered packet counts as `MISBEHAVIOUR_PENALTY` disagreements.
+    pub fn get_score(&self) -> f64 {
+        let good = 1 + self.agreements;
+        let bad =
+            1 + self.disagreements + MISBEHAVIOUR_PENALTY * (self.equivocations + self.tampering);
+        good as f64 / (good + bad) as f64
+    }
+}
+
+/// Keeps the reputation of agents, indexed by their base64-encoded public key, across rounds and,
+/// if it is backed by a file, across sessions.
+#[derive(Debug, Clone, Default)]
+pub struct ReputationBook {
+    /// The file the reputation is saved to, or None if it is only kept in memory.
+    path: Option<PathBuf>,
+    /// The record of every agent the client has seen, indexed by public key.
+    records: BTreeMap<String, ReputationRecord>,
+}
+
+impl ReputationBook {
+    /// Returns the reputation saved to the file at `path`, which is also where it will be saved
+    /// to. Starts with no records if the file does not exist yet.
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let records = if path.is_file() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("failed to parse {}", path.display()))?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(ReputationBook {
+            path: Some(path),
+            records,
+        })
+    }
+
+    /// Writes every record to the file backing the reputation, if any.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let contents = serde_json::to_string_pretty(&self.records)?;
+        fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Returns the record of the agent whose public key is `public_key`, if it was ever seen.
+    pub fn get_record(&self, public_key: &str) -> Option<&ReputationRecord> {
+        self.records.get(public_key)
+    }
+
+    /// Returns the score of the agent whose public key is `public_key`. Agents that were never
+    /// seen have a neutral score of 0.5.
+    pub fn get_score(&self, public_key: &str) -> f64 {
+        self.records
+            .get(public_key)
+            .copied()
+            .unwrap_or_default()
+            .get_score()
+    }
+
+    /// Returns the number of agents with a record.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Records whether the agent whose public key is `public_key` reported the accepted network
+    /// value in a round.
+    pub fn record_vote(&mut self, public_key: &str, agreed: bool) {
+        let record = self.records.entry(public_key.to_owned()).or_default();
+        if agreed {
+            record.agreements += 1;
+        } else {
+            record.disagreements += 1;
+        }
+    }
+
+    /// Records that the agent whose public key is `public_key` signed conflicting values in a
+    /// round.
+    pub fn record_equivocation(&mut self, public_key: &str) {
+        self.records
+            .entry(public_key.to_owned())
+            .or_default()
+            .equivocations += 1;
+    }
+
+    /// Records that the agent whose public key is `public_key` relayed a rejected packet.
+    pub fn record_tampering(&mut self, public_key: &str) {
+        self.records
+            .entry(public_key.to_owned())
+            .or_default()
+            .tampering += 1;
+    }
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reputation_scores() {
+        let mut book = ReputationBook::default();
+        assert_eq!(book.get_score("honest"), 0.5);
+
+        book.record_vote("honest", true);
+        book.record_vote("honest", true);
+        book.record_vote("liar", false);
+        book.record_tampering("relay");
+        assert!(book.get_score("honest") > 0.5);
+        assert!(book.get_score("liar") < 0.5);
+        // Misbehaving is worse than disagreeing
+        assert!(book.get_score("relay") < book.get_score("liar"));
+
+        book.record_equivocation("honest");
+        assert_eq!(book.get_record("honest").unwrap().get_equivocations(), 1);
+        assert_eq!(book.get_score("honest"), 0.5);
+        assert_eq!(book.len(), 3);
+    }
+
+    #[test]
+    fn test_reputation_persists() {
+        let path = std::env::temp_dir().join(format!("reputation-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut book = ReputationBook::open(&path).unwrap();
+        assert!(book.is_empty());
+        book.record_vote("key", false);
+        book.save().unwrap();
+
+        let reopened = ReputationBook::open(&path).unwrap();
+        assert_eq!(reopened.get_record("key").unwrap().get_disagreements(), 1);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(ReputationBook::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,426 @@
+use anyhow::{anyhow, bail};
+use bincode::{deserialize, serialize};
+use ring::aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305};
+use ring::agreement::{self, EphemeralPrivateKey, UnparsedPublicKey, X25519};
+use ring::hkdf::{Salt, HKDF_SHA256};
+use ring::rand::SystemRandom;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::handshake::Capabilities;
+use crate::keys::Keys;
+use crate::network_utils::*;
+
+/// Prepended to the transcript signed by the initiator of a secure channel.
+const INITIATOR_LABEL: &[u8] = b"liarslie secure channel initiator";
+/// Prepended to the transcript signed by the responder of a secure channel.
+const RESPONDER_LABEL: &[u8] = b"liarslie secure channel responder";
+/// Used to derive the key encrypting frames sent by the initiator.
+const INITIATOR_KEY_LABEL: &[u8] = b"liarslie initiator to responder";
+/// Used to derive the key encrypting frames sent by the responder.
+const RESPONDER_KEY_LABEL: &[u8] = b"liarslie responder to initiator";
+
+/// Selects whether the connections of a game are carried in plaintext or over an encrypted,
+/// mutually authenticated channel.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Transport {
+    #[default]
+    Plaintext,
+    Encrypted,
+}
+
+impl Transport {
+    /// Returns the capabilities advertised during the protocol handshake.
+    pub fn offered_capabilities(&self) -> Capabilities {
+        match self {
+            Transport::Plaintext => Capabilities::NONCES | Capabilities::MULTI_HOP,
+            Transport::Encrypted => {
+                Capabilities::NONCES | Capabilities::MULTI_HOP | Capabilities::ENCRYPTION
+            }
+        }
+    }
+
+    /// Returns the capabilities that the remote end must support for the handshake to succeed.
+    /// An encrypted game refuses to fall back to plaintext connections.
+    pub fn required_capabilities(&self) -> Capabilities {
+        match self {
+            Transport::Plaintext => Capabilities::NONE,
+            Transport::Encrypted => Capabilities::ENCRYPTION,
+        }
+    }
+}
+
+/// The first message of the key exchange, sent by the initiator.
+#[derive(Serialize, Deserialize)]
+struct InitiatorHello {
+    /// The initiator's ephemeral X25519 public key.
+    ephemeral_key: Vec<u8>,
+}
+
+/// The reply to `InitiatorHello`, proving that the responder owns the Ed25519 key `identity`.
+#[derive(Serialize, Deserialize)]
+struct ResponderHello {
+    /// The responder's ephemeral X25519 public key.
+    ephemeral_key: Vec<u8>,
+    /// The responder's base64-encoded Ed25519 public key.
+    identity: String,
+    /// The responder's signature of `RESPONDER_LABEL` followed by both ephemeral keys.
+    signature: Vec<u8>,
+}
+
+/// The last message of the key exchange, proving that the initiator owns the Ed25519 key `identity`.
+#[derive(Serialize, Deserialize)]
+struct InitiatorProof {
+    /// The initiator's base64-encoded Ed25519 public key.
+    identity: String,
+    /// The initiator's signature of `INITIATOR_LABEL` followed by both ephemeral keys.
+    signature: Vec<u8>,
+}
+
+/// Encrypts the frames sent over one direction of a secure channel.
+#[derive(Debug)]
+pub struct Sealer {
+    key: LessSafeKey,
+    /// The number of frames sealed so far, used as the nonce of the next frame.
+    counter: u64,
+}
+
+impl Sealer {
+    /// Encrypts and authenticates `plaintext`, returning the ciphertext followed by its tag.
+    pub fn seal(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, FrameError> {
+        let nonce = next_nonce(&mut self.counter)?;
+        let mut in_out = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(nonce, Aad::empty(), &mut in_out)
+            .map_err(|_| FrameError::Encryption)?;
+        Ok(in_out)
+    }
+}
+
+/// Decrypts the frames received over one direction of a secure channel.
+#[derive(Debug)]
+pub struct Opener {
+    key: LessSafeKey,
+    /// The number of frames opened so far, used as the nonce of the next frame.
+    counter: u64,
+}
+
+impl Opener {
+    /// Decrypts `ciphertext` and returns the plaintext. Returns `FrameError::Encryption` if the
+    /// frame was modified, replayed, reordered or encrypted with a different key.
+    pub fn open(&mut self, mut ciphertext: Vec<u8>) -> Result<Vec<u8>, FrameError> {
+        let nonce = next_nonce(&mut self.counter)?;
+        let plaintext_len = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut ciphertext)
+            .map_err(|_| FrameError::Encryption)?
+            .len();
+        ciphertext.truncate(plaintext_len);
+        Ok(ciphertext)
+    }
+}
+
+/// Represents an established secure channel, i.e, the keys protecting both directions of a
+/// connection and the authenticated identity of the remote end.
+#[derive(Debug)]
+pub struct SecureChannel {
+    sealer: Sealer,
+    opener: Opener,
+    /// The base64-encoded Ed25519 public key proven by the remote end.
+    peer_identity: String,
+}
+
+impl SecureChannel {
+    /// Returns the base64-encoded Ed25519 public key proven by the remote end.
+    pub fn get_peer_identity(&self) -> &str {
+        &self.peer_identity
+    }
+
+    /// Splits the channel into the halves used to encrypt outgoing and decrypt incoming frames.
+    pub fn split(self) -> (Sealer, Opener) {
+        (self.sealer, self.opener)
+    }
+}
+
+/// Returns the nonce for the frame numbered `counter` and increments it. Each direction of a
+/// channel uses its own key, therefore a nonce is never reused with the same key.
+fn next_nonce(counter: &mut u64) -> Result<Nonce, FrameError> {
+    let mut nonce = [0u8; aead::NONCE_LEN];
+    nonce[aead::NONCE_LEN - 8..].copy_from_slice(&counter.to_be_bytes());
+    *counter = counter.checked_add(1).ok_or(FrameError::Encryption)?;
+    Ok(Nonce::assume_unique_for_key(nonce))
+}
+
+/// Derives the key for one direction of a channel from the X25519 `shared_secret`. The key is
+/// bound to the `transcript` of the key exchange and separated by `label`.
+fn derive_key(
+    shared_secret: &[u8],
+    transcript: &[u8],
+    label: &[u8],
+) -> anyhow::Result<LessSafeKey> {
+    let info = [label];
+    let prk = Salt::new(HKDF_SHA256, transcript).extract(shared_secret);
+    let okm = prk
+        .expand(&info, &CHACHA20_POLY1305)
+        .map_err(|_| anyhow!("[!] error: failed to derive channel keys\n"))?;
+    Ok(LessSafeKey::new(UnboundKey::from(okm)))
+}
+
+/// Generates an ephemeral X25519 key pair and returns the private key with its public key bytes.
+fn gen_ephemeral_key() -> anyhow::Result<(EphemeralPrivateKey, Vec<u8>)> {
+    let rng = SystemRandom::new();
+    let private_key = EphemeralPrivateKey::generate(&X25519, &rng)
+        .map_err(|_| anyhow!("[!] error: failed to generate ephemeral key\n"))?;
+    let public_key = private_key
+        .compute_public_key()
+        .map_err(|_| anyhow!("[!] error: failed to generate ephemeral key\n"))?;
+    Ok((private_key, public_key.as_ref().to_vec()))
+}
+
+/// Combines `private_key` with the remote ephemeral key, returning the keys protecting frames
+/// sent by the initiator and by the responder, in this order.
+fn derive_channel_keys(
+    private_key: EphemeralPrivateKey,
+    remote_key: &[u8],
+    transcript: &[u8],
+) -> anyhow::Result<(LessSafeKey, LessSafeKey)> {
+    agreement::agree_ephemeral(
+        private_key,
+        &UnparsedPublicKey::new(&X25519, remote_key),
+        |shared_secret| {
+            Ok((
+                derive_key(shared_secret, transcript, INITIATOR_KEY_LABEL)?,
+                derive_key(shared_secret, transcript, RESPONDER_KEY_LABEL)?,
+            ))
+        },
+    )
+    .map_err(|_| anyhow!("[!] error: invalid ephemeral key received from peer\n"))?
+}
+
+/// Returns `label` followed by the transcript of the key exchange.
+fn signed_data(label: &[u8], transcript: &[u8]) -> Vec<u8> {
+    [label, transcript].concat()
+}
+
+/// Serializes `message` and sends it over `socket` as a single frame.
+async fn send_message<S, T>(message: &T, socket: &mut S, config: &FrameConfig) -> anyhow::Result<()>
+where
+    S: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let bytes = serialize(message)?;
+    if let Err(e) = send_packet(&bytes, socket, config).await {
+        bail!("[!] error: key exchange failed - {}\n", e);
+    }
+    Ok(())
+}
+
+/// Receives a single frame from `socket` and deserializes it into an instance of `T`.
+async fn recv_message<S, T>(socket: &mut S, config: &FrameConfig) -> anyhow::Result<T>
+where
+    S: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    match recv_packet(socket, config).await {
+        Ok(bytes) => deserialize(&bytes)
+            .map_err(|e| anyhow!("[!] error: malformed key exchange message - {}\n", e)),
+        Err(e) => bail!("[!] error: key exchange failed - {}\n", e),
+    }
+}
+
+/// Performs the initiating side of the key exchange over `socket`, which must follow a handshake
+/// in which `Capabilities::ENCRYPTION` was negotiated. The remote end must prove ownership of
+/// `expected_identity`, while the local end proves ownership of `identity`.
+pub async fn initiate_secure_channel<S>(
+    socket: &mut S,
+    config: &FrameConfig,
+    identity: &Keys,
+    expected_identity: &str,
+) -> anyhow::Result<SecureChannel>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (private_key, ephemeral_key) = gen_ephemeral_key()?;
+    send_message(
+        &InitiatorHello {
+            ephemeral_key: ephemeral_key.clone(),
+        },
+        socket,
+        config,
+    )
+    .await?;
+
+    let reply: ResponderHello = recv_message(socket, config).await?;
+    if reply.identity != expected_identity {
+        bail!(
+            "[!] error: peer identified itself as {}, but {} was expected\n",
+            reply.identity,
+            expected_identity
+        );
+    }
+
+    let transcript = [ephemeral_key.as_slice(), &reply.ephemeral_key].concat();
+    Keys::verify(
+        &signed_data(RESPONDER_LABEL, &transcript),
+        &reply.signature,
+        expected_identity,
+    )
+    .map_err(|e| anyhow!("[!] error: peer failed to prove its identity - {}\n", e))?;
+
+    let signature = identity.sign(&signed_data(INITIATOR_LABEL, &transcript))?;
+    send_message(
+        &InitiatorProof {
+            identity: identity.get_public_key().to_owned(),
+            signature,
+        },
+        socket,
+        config,
+    )
+    .await?;
+
+    let (initiator_key, responder_key) =
+        derive_channel_keys(private_key, &reply.ephemeral_key, &transcript)?;
+
+    Ok(SecureChannel {
+        sealer: Sealer {
+            key: initiator_key,
+            counter: 0,
+        },
+        opener: Opener {
+            key: responder_key,
+            counter: 0,
+        },
+        peer_identity: reply.identity,
+    })
+}
+
+/// Performs the accepting side of the key exchange over `socket`, which must follow a handshake
+/// in which `Capabilities::ENCRYPTION` was negotiated. The local end proves ownership of
+/// `identity`. The identity proven by the remote end is available through the returned channel.
+pub async fn accept_secure_channel<S>(
+    socket: &mut S,
+    config: &FrameConfig,
+    identity: &Keys,
+) -> anyhow::Result<SecureChannel>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let hello: InitiatorHello = recv_message(socket, config).await?;
+
+    let (private_key, ephemeral_key) = gen_ephemeral_key()?;
+    let transcript = [hello.ephemeral_key.as_slice(), &ephemeral_key].concat();
+
+    let signature = identity.sign(&signed_data(RESPONDER_LABEL, &transcript))?;
+    send_message(
+        &ResponderHello {
+            ephemeral_key,
+            identity: identity.get_public_key().to_owned(),
+            signature,
+        },
+        socket,
+        config,
+    )
+    .await?;
+
+    let proof: InitiatorProof = recv_message(socket, config).await?;
+    Keys::verify(
+        &signed_data(INITIATOR_LABEL, &transcript),
+        &proof.signature,
+        &proof.identity,
+    )
+    .map_err(|e| anyhow!("[!] error: peer failed to prove its identity - {}\n", e))?;
+
+    let (initiator_key, responder_key) =
+        derive_channel_keys(private_key, &hello.ephemeral_key, &transcript)?;
+
+    Ok(SecureChannel {
+        sealer: Sealer {
+            key: responder_key,
+            counter: 0,
+        },
+        opener: Opener {
+            key: initiator_key,
+            counter: 0,
+        },
+        peer_identity: proof.identity,
+    })
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io;
+
+    #[tokio::test]
+    async fn test_secure_channel_round_trip() {
+        let config = FrameConfig::default();
+        let client_keys = Keys::new_key_pair();
+        let agent_keys = Keys::new_key_pair();
+        let agent_pubkey = agent_keys.get_public_key().to_owned();
+        let (mut initiator, mut responder) = io::duplex(1024);
+
+        let acceptor = tokio::spawn(async move {
+            accept_secure_channel(&mut responder, &FrameConfig::default(), &agent_keys).await
+        });
+        let initiated =
+            initiate_secure_channel(&mut initiator, &config, &client_keys, &agent_pubkey)
+                .await
+                .unwrap();
+        let accepted = acceptor.await.unwrap().unwrap();
+
+        assert_eq!(initiated.get_peer_identity(), agent_pubkey);
+        assert_eq!(accepted.get_peer_identity(), client_keys.get_public_key());
+
+        let (mut client_sealer, mut client_opener) = initiated.split();
+        let (mut agent_sealer, mut agent_opener) = accepted.split();
+
+        let ciphertext = client_sealer.seal(b"Tis but a scratch!").unwrap();
+        assert_ne!(ciphertext, b"Tis but a scratch!");
+        assert_eq!(
+            agent_opener.open(ciphertext).unwrap(),
+            b"Tis but a scratch!"
+        );
+
+        let ciphertext = agent_sealer.seal(b"Just a flesh wound").unwrap();
+        assert_eq!(
+            client_opener.open(ciphertext).unwrap(),
+            b"Just a flesh wound"
+        );
+
+        // Tampered and replayed frames fail to decrypt
+        let mut ciphertext = client_sealer.seal(b"Tis but a scratch!").unwrap();
+        ciphertext[0] ^= 1;
+        assert!(matches!(
+            agent_opener.open(ciphertext),
+            Err(FrameError::Encryption)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_secure_channel_rejects_wrong_identity() {
+        let config = FrameConfig::default();
+        let client_keys = Keys::new_key_pair();
+        let agent_keys = Keys::new_key_pair();
+        let impostor_keys = Keys::new_key_pair();
+        let (mut initiator, mut responder) = io::duplex(1024);
+
+        // The client expects `agent_keys`, but the responder holds `impostor_keys`
+        tokio::spawn(async move {
+            let _ = accept_secure_channel(&mut responder, &FrameConfig::default(), &impostor_keys)
+                .await;
+        });
+
+        assert!(initiate_secure_channel(
+            &mut initiator,
+            &config,
+            &client_keys,
+            agent_keys.get_public_key()
+        )
+        .await
+        .is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use anyhow::bail;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::message::Message;
+use crate::packet::Packet;
+
+/// Represents the reasons for which the client rejects a packet relayed to it in expert mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RejectionReason {
+    /// The signature of the packet does not match the agent it claims to have been sent by.
+    BadSignature,
+    /// The packet claims to have been sent by an agent that is not part of the game.
+    UnknownAgent,
+    /// The packet carries a message that relays never forward, or a nested `MsgFwdValues` that
+    /// exceeds the hop limit of the round.
+    WrongMessageType,
+    /// The reply sent by the relay could not be decoded.
+    Undecodable,
+    /// The packet was correctly signed, but for another round or recipient.
+    Replayed,
+    /// The packet was forwarded more than once by the same relay.
+    Duplicate,
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RejectionReason::BadSignature => write!(f, "bad signature"),
+            RejectionReason::UnknownAgent => write!(f, "unknown agent ID"),
+            RejectionReason::WrongMessageType => write!(f, "wrong message type"),
+            RejectionReason::Undecodable => write!(f, "undecodable bytes"),
+            RejectionReason::Replayed => write!(f, "replayed from another round or recipient"),
+            RejectionReason::Duplicate => write!(f, "duplicated packet"),
+        }
+    }
+}
+
+/// Evidence that a relay forwarded a packet the client had to reject during an expert round.
+///
+/// Every relay signs the packets it forwards, and honest relays only forward replies of the
+/// expected type, sent by their known peers, whose signature they verified. A forwarded packet
+/// that is rejected by the client was therefore altered, or made up, by the last relay of its
+/// `custody_chain`, the `culprit`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TamperEvidence {
+    /// The ID of the agent that first altered the packet.
+    culprit: usize,
+    /// The ID of the agent that the rejected packet claims to have been sent by.
+    claimed_sender: usize,
+    /// The IDs of the relays that signed for the packet, starting with the agent queried by the
+    /// client and ending with the culprit.
+    custody_chain: Vec<usize>,
+    /// Why the packet was rejected.
+    reason: RejectionReason,
+}
+
+impl fmt::Display for TamperEvidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chain: Vec<String> = self.custody_chain.iter().map(usize::to_string).collect();
+        write!(
+            f,
+            "Agent {} relayed a rejected packet from Agent {}: {} (custody chain: {})",
+            self.culprit,
+            self.claimed_sender,
+            self.reason,
+            chain.join(" -> ")
+        )
+    }
+}
+
+impl TamperEvidence {
+    /// Returns a new instance of `TamperEvidence` for a packet claiming to come from
+    /// `claimed_sender`, rejected for `reason` after travelling through the relays in
+    /// `custody_chain`. The last relay of the chain is held responsible.
+    pub fn new(claimed_sender: usize, custody_chain: &[usize], reason: RejectionReason) -> Self {
+        TamperEvidence {
+            culprit: custody_chain.last().copied().unwrap_or_default(),
+            claimed_sender,
+            custody_chain: custody_chain.to_vec(),
+            reason,
+        }
+    }
+
+    pub fn get_culprit(&self) -> usize {
+        self.culprit
+    }
+
+    pub fn get_claimed_sender(&self) -> usize {
+        self.claimed_sender
+    }
+
+    pub fn get_custody_chain(&self) -> &[usize] {
+        &self.custody_chain
+    }
+
+    pub fn get_reason(&self) -> RejectionReason {
+        self.reason
+    }
+}
+
+/// Proof that an agent equivocated, i.e, signed two different values for the same round.
+///
+/// Both packets are kept untouched, so that anyone holding the agent's public key can check the
+/// proof with `EquivocationProof::verify`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquivocationProof {
+    /// The ID of the agent that signed both values.
+    agent_id: usize,
+    /// The first signed `MsgSendValue` received from the agent.
+    first: Packet,
+    /// A signed `MsgSendValue` for the same round carrying a different value.
+    second: Packet,
+}
+
+impl fmt::Display for EquivocationProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match Self::conflicting_values(&self.first, &self.second) {
+            Ok((first, second)) => write!(
+                f,
+                "Agent {} signed both {} (for Agent {}) and {} (for Agent {})",
+                self.agent_id, first, self.first.recipient, second, self.second.recipient
+            ),
+            Err(_) => write!(f, "Agent {} signed conflicting values", self.agent_id),
+        }
+    }
+}
+
+impl EquivocationProof {
+    /// Returns a new instance of `EquivocationProof` if the `MsgSendValue` packets `first` and
+    /// `second` were sent by the same agent for the same round and carry different values, and
+    /// None otherwise. Signatures are not verified here.
+    pub fn new(first: &Packet, second: &Packet) -> Option<Self> {
+        Self::conflicting_values(first, second).ok()?;
+        Some(EquivocationProof {
+            agent_id: first.sender,
+            first: first.clone(),
+            second: second.clone(),
+        })
+    }
+
+    /// Verifies that both packets of the proof were signed by the owner of the private key that
+    /// corresponds to `public_key` and carry conflicting values. Returns Ok(()) if the proof holds.
+    pub fn verify(&self, public_key: &str) -> anyhow::Result<()> {
+        Self::conflicting_values(&self.first, &self.second)?;
+        self.first.verify(public_key)?;
+        self.second.verify(public_key)
+    }
+
+    pub fn get_agent_id(&self) -> usize {
+        self.agent_id
+    }
+
+    /// Returns the two signed packets that make up the proof.
+    pub fn get_packets(&self) -> (&Packet, &Packet) {
+        (&self.first, &self.second)
+    }
+
+    /// Checks that `first` and `second` both carry a `MsgSendValue` sent by the agent they belong
+    /// to, for the same round, with different values. Returns both values if they conflict.
+    fn conflicting_values(first: &Packet, second: &Packet) -> anyhow::Result<(u64, u64)> {
+        let signed_value = |packet: &Packet| match Message::deserialize_message(&packet.message) {
+            Ok(Message::MsgSendValue {
+                agent_id,
+                value,
+                nonce,
+            }) if agent_id == packet.sender && nonce == packet.round => Ok(value),
+            _ => bail!(
+                "[!] error: packet from Agent {} does not carry a MsgSendValue\n",
+                packet.sender
+            ),
+        };
+
+        let (first_value, second_value) = (signed_value(first)?, signed_value(second)?);
+        if first.sender != second.sender || first.round != second.round {
+            bail!("[!] error: values were not signed by the same agent for the same round\n");
+        }
+        if first_value == second_value {
+            bail!(
+                "[!] error: Agent {} signed the same value twice\n",
+                first.sender
+            );
+        }
+        Ok((first_value, second_value))
+    }
+}
+
+/// Summarizes the evidence collected against a single agent across expert rounds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suspect {
+    /// The ID of the suspected agent.
+    agent_id: usize,
+    /// The number of expert rounds in which the agent forwarded at least one rejected packet or
+    /// equivocated.
+    rounds_flagged: usize,
+    /// The number of rejected packets forwarded by the agent, by reason.
+    rejections: BTreeMap<RejectionReason, usize>,
+    /// Every distinct piece of evidence of tampering collected against the agent.
+    evidence: Vec<TamperEvidence>,
+    /// A proof for every expert round in which the agent equivocated.
+    equivocations: Vec<EquivocationProof>,
+}
+
+impl fmt::Display for Suspect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rejections: Vec<String> = self
+            .rejections
+            .iter()
+            .map(|(reason, count)| format!("{}: {}", reason, count))
+            .collect();
+        if !self.equivocations.is_empty() {
+            rejections.push(format!("equivocation: {}", self.equivocations.len()));
+        }
+        write!(
+            f,
+            "Agent {} was flagged in {} round(s) ({})",
+            self.agent_id,
+            self.rounds_flagged,
+            rejections.join(", ")
+        )
+    }
+}
+
+impl Suspect {
+    pub fn get_agent_id(&self) -> usize {
+        self.agent_id
+    }
+
+    pub fn get_rounds_flagged(&self) -> usize {
+        self.rounds_flagged
+    }
+
+    /// Returns the total number of rejected packets forwarded by the agent.
+    pub fn get_num_rejections(&self) -> usize {
+        self.rejections.values().sum()
+    }
+
+    pub fn get_evidence(&self) -> &Vec<TamperEvidence> {
+        &self.evidence
+    }
+
+    pub fn get_equivocations(&self) -> &Vec<EquivocationProof> {
+        &self.equivocations
+    }
+}
+
+/// The evidence of misbehaviour collected during a single expert round.
+#[derive(Debug, Clone, Default)]
+struct RoundEvidence {
+    tampering: Vec<TamperEvidence>,
+    equivocations: Vec<EquivocationProof>,
+}
+
+/// Keeps the evidence of tampering and equivocation collected by the client over every expert
+/// round of a game. Clones share the same evidence.
+#[derive(Debug, Clone, Default)]
+pub struct SuspectList {
+    /// The evidence collected in every expert round, in the order the rounds were played.
+    rounds: Arc<Mutex<Vec<RoundEvidence>>>,
+}
+
+impl SuspectList {
+    /// Records the evidence of `tampering` and the `equivocations` detected during a single
+    /// expert round.
+    pub fn record_round(&self, tampering: &[TamperEvidence], equivocations: &[EquivocationProof]) {
+        self.rounds.lock().unwrap().push(RoundEvidence {
+            tampering: tampering.to_vec(),
+            equivocations: equivocations.to_vec(),
+        });
+    }
+
+    /// Adds the evidence of `tampering` and the `equivocations` detected after the last expert
+    /// round was recorded, e.g, while re-querying agents, to that round.
+    pub fn amend_last_round(
+        &self,
+        tampering: &[TamperEvidence],
+        equivocations: &[EquivocationProof],
+    ) {
+        let mut rounds = self.rounds.lock().unwrap();
+        match rounds.last_mut() {
+            Some(round) => {
+                round.tampering.extend_from_slice(tampering);
+                round.equivocations.extend_from_slice(equivocations);
+            }
+            None => rounds.push(RoundEvidence {
+                tampering: tampering.to_vec(),
+                equivocations: equivocations.to_vec(),
+            }),
+        }
+    }
+
+    /// Returns the number of expert rounds recorded so far.
+    pub fn get_num_rounds(&self) -> usize {
+        self.rounds.lock().unwrap().len()
+    }
+
+    /// Returns every agent against which evidence was collected, the agents flagged in the most
+    /// rounds, and then with the most rejected packets, first.
+    pub fn get_suspects(&self) -> Vec<Suspect> {
+        let rounds = self.rounds.lock().unwrap();
+        let mut suspects: BTreeMap<usize, Suspect> = BTreeMap::new();
+
+        for round in rounds.iter() {
+            let culprits: BTreeSet<usize> = round
+                .tampering
+                .iter()
+                .map(|evidence| evidence.culprit)
+                .chain(round.equivocations.iter().map(|proof| proof.agent_id))
+                .collect();
+            for culprit in culprits {
+                suspects
+                    .entry(culprit)
+                    .or_insert_with(|| Suspect {
+                        agent_id: culprit,
+                        rounds_flagged: 0,
+                        rejections: BTreeMap::new(),
+                        evidence: Vec::new(),
+                        equivocations: Vec::new(),
+                    })
+                    .rounds_flagged += 1;
+            }
+
+            for evidence in &round.tampering {
+                let suspect = suspects.get_mut(&evidence.culprit).unwrap();
+                *suspect.rejections.entry(evidence.reason).or_default() += 1;
+                if !suspect.evidence.contains(evidence) {
+                    suspect.evidence.push(evidence.clone());
+                }
+            }
+            for proof in &round.equivocations {
+                let suspect = suspects.get_mut(&proof.agent_id).unwrap();
+                suspect.equivocations.push(proof.clone());
+            }
+        }
+
+        let mut suspects: Vec<Suspect> = suspects.into_values().collect();
+        suspects.sort_by(|a, b| {
+            b.rounds_flagged
+                .cmp(&a.rounds_flagged)
+                .then(b.get_num_rejections().cmp(&a.get_num_rejections()))
+                .then(a.agent_id.cmp(&b.agent_id))
+        });
+        suspects
+    }
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suspects_ranked_by_rounds_flagged() {
+        let suspects = SuspectList::default();
+        let forged = TamperEvidence::new(1, &[2, 5], RejectionReason::BadSignature);
+        let made_up = TamperEvidence::new(9, &[3], RejectionReason::UnknownAgent);
+
+        suspects.record_round(&[made_up.clone(), made_up.clone(), forged.clone()], &[]);
+        suspects.record_round(&[], &[]);
+        suspects.record_round(std::slice::from_ref(&forged), &[]);
+        assert_eq!(suspects.get_num_rounds(), 3);
+
+        let ranked = suspects.get_suspects();
+        assert_eq!(ranked.len(), 2);
+
+        // The relay that signed for the forged packet is blamed, not the relay it went through
+        assert_eq!(ranked[0].get_agent_id(), 5);
+        assert_eq!(ranked[0].get_rounds_flagged(), 2);
+        assert_eq!(ranked[0].get_num_rejections(), 2);
+        assert_eq!(ranked[0].get_evidence(), &vec![forged]);
+
+        assert_eq!(ranked[1].get_agent_id(), 3);
+        assert_eq!(ranked[1].get_rounds_flagged(), 1);
+        assert_eq!(ranked[1].get_num_rejections(), 2);
+
+        // Evidence found while re-querying agents belongs to the last round
+        suspects.amend_last_round(&[made_up], &[]);
+        assert_eq!(suspects.get_num_rounds(), 3);
+        let ranked = suspects.get_suspects();
+        assert_eq!(ranked[0].get_agent_id(), 3);
+        assert_eq!(ranked[0].get_rounds_flagged(), 2);
+        assert_eq!(ranked[0].get_num_rejections(), 3);
+    }
+}
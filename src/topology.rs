@@ -0,0 +1,390 @@
+use anyhow::bail;
+use clap::ValueEnum;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// The default number of neighbours of each agent in `random-regular` and `small-world` topologies.
+pub const DEFAULT_DEGREE: usize = 4;
+/// The default probability of linking two agents in an `erdos-renyi` topology, or of rewiring a
+/// link in a `small-world` topology.
+pub const DEFAULT_LINK_PROBABILITY: f32 = 0.2;
+
+/// How many random edge switches are attempted per link when shuffling a random-regular graph.
+const SWITCHES_PER_LINK: usize = 10;
+
+/// Represents the families of graphs that can be used as the network topology of a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TopologyKind {
+    /// Every agent may connect to every other agent.
+    #[default]
+    Complete,
+    /// Agents are placed on a cycle and linked to the agents right before and after them.
+    Ring,
+    /// Agents are placed on a square grid, row by row, and linked to their horizontal and
+    /// vertical neighbours.
+    Grid,
+    /// Every agent is linked to exactly `degree` agents, chosen at random.
+    RandomRegular,
+    /// Every pair of agents is linked with probability `probability`.
+    ErdosRenyi,
+    /// A ring in which every agent is linked to its `degree` closest agents, after which every
+    /// link is rewired to a random agent with probability `probability`.
+    SmallWorld,
+}
+
+impl fmt::Display for TopologyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopologyKind::Complete => write!(f, "complete"),
+            TopologyKind::Ring => write!(f, "ring"),
+            TopologyKind::Grid => write!(f, "grid"),
+            TopologyKind::RandomRegular => write!(f, "random-regular"),
+            TopologyKind::ErdosRenyi => write!(f, "erdos-renyi"),
+            TopologyKind::SmallWorld => write!(f, "small-world"),
+        }
+    }
+}
+
+/// Records which agents may connect to which. Links are undirected, and the game's client is not
+/// part of the topology, i.e, it may always connect to every agent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Topology {
+    /// The family of graphs the topology was generated from.
+    kind: TopologyKind,
+    /// The number of neighbours requested for `random-regular` and `small-world` topologies.
+    degree: usize,
+    /// The probability used by `erdos-renyi` and `small-world` topologies.
+    probability: f32,
+    /// The neighbours of every agent in the topology, indexed by agent ID.
+    links: BTreeMap<usize, BTreeSet<usize>>,
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Topology {
+            kind: TopologyKind::Complete,
+            degree: DEFAULT_DEGREE,
+            probability: DEFAULT_LINK_PROBABILITY,
+            links: BTreeMap::new(),
+        }
+    }
+}
+
+impl Topology {
+    /// Generates a topology of the given `kind` over the agents in `agent_ids`. `degree` is only
+    /// used by `random-regular` and `small-world` topologies, and `probability` only by
+    /// `erdos-renyi` and `small-world` topologies. Returns an error if no graph of the requested
+    /// kind exists for the number of agents given.
+    pub fn generate(
+        kind: TopologyKind,
+        agent_ids: &[usize],
+        degree: usize,
+        probability: f32,
+    ) -> anyhow::Result<Self> {
+        let mut topology = Topology {
+            kind,
+            degree,
+            probability,
+            links: agent_ids.iter().map(|&id| (id, BTreeSet::new())).collect(),
+        };
+
+        match kind {
+            TopologyKind::Complete => topology.link_complete(agent_ids),
+            TopologyKind::Ring => topology.link_ring(agent_ids),
+            TopologyKind::Grid => topology.link_grid(agent_ids),
+            TopologyKind::RandomRegular => topology.link_random_regular(agent_ids)?,
+            TopologyKind::ErdosRenyi => topology.link_erdos_renyi(agent_ids),
+            TopologyKind::SmallWorld => topology.link_small_world(agent_ids)?,
+        }
+
+        Ok(topology)
+    }
+
+    /// Generates a new topology with the same kind and parameters as this one over the agents in
+    /// `agent_ids`, e.g, after the game is extended.
+    pub fn regenerate(&self, agent_ids: &[usize]) -> anyhow::Result<Self> {
+        Self::generate(self.kind, agent_ids, self.degree, self.probability)
+    }
+
+    pub fn get_kind(&self) -> TopologyKind {
+        self.kind
+    }
+
+    /// Returns the neighbours of the agent `agent_id`, or None if every agent may connect to it,
+    /// i.e, in a complete topology.
+    pub fn get_neighbours(&self, agent_id: usize) -> Option<&BTreeSet<usize>> {
+        match self.kind {
+            TopologyKind::Complete => None,
+            _ => self.links.get(&agent_id),
+        }
+    }
+
+    /// Checks whether the agents `first` and `second` may connect to each other. An agent may
+    /// always connect to itself.
+    pub fn allows(&self, first: usize, second: usize) -> bool {
+        first == second
+            || self.kind == TopologyKind::Complete
+            || self
+                .links
+                .get(&first)
+                .is_some_and(|neighbours| neighbours.contains(&second))
+    }
+
+    /// Adds an undirected link between the agents `first` and `second`.
+    fn link(&mut self, first: usize, second: usize) {
+        if first == second {
+            return;
+        }
+        self.links.entry(first).or_default().insert(second);
+        self.links.entry(second).or_default().insert(first);
+    }
+
+    /// Removes the link between the agents `first` and `second`, if there is one.
+    fn unlink(&mut self, first: usize, second: usize) {
+        if let Some(neighbours) = self.links.get_mut(&first) {
+            neighbours.remove(&second);
+        }
+        if let Some(neighbours) = self.links.get_mut(&second) {
+            neighbours.remove(&first);
+        }
+    }
+
+    /// Links every agent to every other agent.
+    fn link_complete(&mut self, agent_ids: &[usize]) {
+        for (i, &first) in agent_ids.iter().enumerate() {
+            for &second in &agent_ids[i + 1..] {
+                self.link(first, second);
+            }
+        }
+    }
+
+    /// Links every agent to the agents right before and after it in `agent_ids`, wrapping around.
+    fn link_ring(&mut self, agent_ids: &[usize]) {
+        for (i, &agent_id) in agent_ids.iter().enumerate() {
+            self.link(agent_id, agent_ids[(i + 1) % agent_ids.len()]);
+        }
+    }
+
+    /// Places the agents on the smallest square grid that fits them, row by row, and links every
+    /// agent to the agents on its right and below it.
+    fn link_grid(&mut self, agent_ids: &[usize]) {
+        let columns = (agent_ids.len() as f64).sqrt().ceil() as usize;
+        for (i, &agent_id) in agent_ids.iter().enumerate() {
+            if (i + 1) % columns != 0 {
+                if let Some(&right) = agent_ids.get(i + 1) {
+                    self.link(agent_id, right);
+                }
+            }
+            if let Some(&below) = agent_ids.get(i + columns) {
+                self.link(agent_id, below);
+            }
+        }
+    }
+
+    /// Links every agent to exactly `Topology.degree` random agents. The agents are shuffled onto a
+    /// circulant graph, which is regular by construction, whose links are then shuffled by random
+    /// edge switches: two links a-b and c-d are replaced by a-c and b-d whenever that adds neither
+    /// a loop nor a repeated link. Switches preserve the degree of every agent, so generation
+    /// never fails once a graph of the requested degree exists.
+    fn link_random_regular(&mut self, agent_ids: &[usize]) -> anyhow::Result<()> {
+        let num_agents = agent_ids.len();
+        if self.degree >= num_agents || !(self.degree * num_agents).is_multiple_of(2) {
+            bail!(
+                "[!] error: a random-regular topology of degree {} requires more than {} agents, \
+                with an even product of agents and degree\n",
+                self.degree,
+                self.degree
+            );
+        }
+
+        let mut rng = thread_rng();
+        let mut shuffled = agent_ids.to_vec();
+        shuffled.shuffle(&mut rng);
+
+        // Link every agent to its `degree / 2` closest agents on a ring and, for odd degrees, to
+        // the agent right across the ring
+        let mut links = Vec::new();
+        for (i, &agent_id) in shuffled.iter().enumerate() {
+            for step in 1..=self.degree / 2 {
+                links.push((agent_id, shuffled[(i + step) % num_agents]));
+            }
+            if !self.degree.is_multiple_of(2) && i < num_agents / 2 {
+                links.push((agent_id, shuffled[i + num_agents / 2]));
+            }
+        }
+        for &(first, second) in &links {
+            self.link(first, second);
+        }
+
+        if links.is_empty() {
+            return Ok(());
+        }
+        for _ in 0..SWITCHES_PER_LINK * links.len() {
+            let i = rng.gen_range(0..links.len());
+            let j = rng.gen_range(0..links.len());
+            let (a, b) = links[i];
+            let (c, d) = if rng.gen() {
+                links[j]
+            } else {
+                (links[j].1, links[j].0)
+            };
+
+            // An agent is always allowed to connect to itself, so loops are ruled out as well
+            if self.allows(a, c) || self.allows(b, d) {
+                continue;
+            }
+            self.unlink(a, b);
+            self.unlink(c, d);
+            self.link(a, c);
+            self.link(b, d);
+            links[i] = (a, c);
+            links[j] = (b, d);
+        }
+
+        Ok(())
+    }
+
+    /// Links every pair of agents with probability `Topology.probability`.
+    fn link_erdos_renyi(&mut self, agent_ids: &[usize]) {
+        let mut rng = thread_rng();
+        for (i, &first) in agent_ids.iter().enumerate() {
+            for &second in &agent_ids[i + 1..] {
+                if rng.gen::<f32>() < self.probability {
+                    self.link(first, second);
+                }
+            }
+        }
+    }
+
+    /// Builds a Watts-Strogatz graph: every agent is linked to its `Topology.degree` closest
+    /// agents on a ring, then every link is moved, with probability `Topology.probability`, from
+    /// its far end to a random agent that is not yet a neighbour.
+    fn link_small_world(&mut self, agent_ids: &[usize]) -> anyhow::Result<()> {
+        let num_agents = agent_ids.len();
+        if !self.degree.is_multiple_of(2) || self.degree >= num_agents {
+            bail!(
+                "[!] error: a small-world topology requires an even degree smaller than the number \
+                of agents, but the degree is {} and there are {} agents\n",
+                self.degree,
+                num_agents
+            );
+        }
+
+        let mut lattice = Vec::new();
+        for (i, &agent_id) in agent_ids.iter().enumerate() {
+            for step in 1..=self.degree / 2 {
+                let neighbour = agent_ids[(i + step) % num_agents];
+                self.link(agent_id, neighbour);
+                lattice.push((agent_id, neighbour));
+            }
+        }
+
+        let mut rng = thread_rng();
+        for (agent_id, neighbour) in lattice {
+            if rng.gen::<f32>() >= self.probability {
+                continue;
+            }
+
+            let candidates: Vec<usize> = agent_ids
+                .iter()
+                .copied()
+                .filter(|&candidate| candidate != agent_id && !self.allows(agent_id, candidate))
+                .collect();
+            if let Some(&candidate) = candidates.choose(&mut rng) {
+                self.unlink(agent_id, neighbour);
+                self.link(agent_id, candidate);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.kind == TopologyKind::Complete {
+            return writeln!(f, "every agent may connect to every other agent");
+        }
+
+        for (agent_id, neighbours) in &self.links {
+            let neighbours: Vec<String> = neighbours.iter().map(|id| id.to_string()).collect();
+            writeln!(f, "Agent {}: {}", agent_id, neighbours.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// ******************************************************************************************
+// ************************************* UNIT TESTS *****************************************
+// ******************************************************************************************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn degrees(topology: &Topology) -> Vec<usize> {
+        topology.links.values().map(BTreeSet::len).collect()
+    }
+
+    #[test]
+    fn test_generate_deterministic_topologies() {
+        let agent_ids: Vec<usize> = (1..=6).collect();
+
+        let complete = Topology::generate(TopologyKind::Complete, &agent_ids, 0, 0.0).unwrap();
+        assert!(complete.allows(1, 6));
+        assert_eq!(complete.get_neighbours(1), None);
+
+        let ring = Topology::generate(TopologyKind::Ring, &agent_ids, 0, 0.0).unwrap();
+        assert_eq!(degrees(&ring), vec![2; 6]);
+        assert!(ring.allows(1, 6) && ring.allows(6, 1));
+        assert!(!ring.allows(1, 3));
+
+        // A 3x3 grid with the last row only partially filled:
+        //   1 2 3
+        //   4 5 6
+        let grid = Topology::generate(TopologyKind::Grid, &agent_ids, 0, 0.0).unwrap();
+        assert_eq!(grid.get_neighbours(5), Some(&BTreeSet::from([2, 4, 6])));
+        assert!(!grid.allows(3, 4));
+    }
+
+    #[test]
+    fn test_generate_random_topologies() {
+        let agent_ids: Vec<usize> = (1..=10).collect();
+
+        let regular = Topology::generate(TopologyKind::RandomRegular, &agent_ids, 3, 0.0).unwrap();
+        assert_eq!(degrees(&regular), vec![3; 10]);
+        let complete = Topology::generate(TopologyKind::RandomRegular, &agent_ids, 9, 0.0).unwrap();
+        assert_eq!(degrees(&complete), vec![9; 10]);
+
+        // No 3-regular graph exists over an odd number of agents
+        assert!(Topology::generate(TopologyKind::RandomRegular, &agent_ids[1..], 3, 0.0).is_err());
+
+        let empty = Topology::generate(TopologyKind::ErdosRenyi, &agent_ids, 0, 0.0).unwrap();
+        assert_eq!(degrees(&empty), vec![0; 10]);
+        let full = Topology::generate(TopologyKind::ErdosRenyi, &agent_ids, 0, 1.0).unwrap();
+        assert_eq!(degrees(&full), vec![9; 10]);
+
+        // Rewiring moves links around, but never adds or removes any
+        let small_world = Topology::generate(TopologyKind::SmallWorld, &agent_ids, 4, 0.5).unwrap();
+        assert_eq!(degrees(&small_world).iter().sum::<usize>(), 10 * 4);
+        assert!(Topology::generate(TopologyKind::SmallWorld, &agent_ids, 3, 0.5).is_err());
+    }
+
+    // Drawing a random-regular topology never fails when a graph of the requested degree exists
+    #[test]
+    fn test_generate_random_regular_degrees() {
+        for num_agents in [20, 50, 100] {
+            let agent_ids: Vec<usize> = (1..=num_agents).collect();
+            for degree in [DEFAULT_DEGREE, 6] {
+                for _ in 0..5 {
+                    let regular =
+                        Topology::generate(TopologyKind::RandomRegular, &agent_ids, degree, 0.0)
+                            .unwrap();
+                    assert_eq!(degrees(&regular), vec![degree; num_agents]);
+                }
+            }
+        }
+    }
+}